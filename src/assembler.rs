@@ -1,17 +1,521 @@
-pub struct Assembler {}
+use std::collections::HashMap;
 
-enum AssemblyPhase {
-    FirstPass,
-    SecondPass,
+use crate::vm::{
+    isa::{self, CSR_NAMES, Format, LMUL_NAMES, ROUNDING_MODE_NAMES, RegisterClass, SEW_NAMES, VectorSource},
+    profile::{BaseIsa, IsaProfile},
+};
+
+const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+const FP_ABI_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2", "fa3",
+    "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9", "fs10", "fs11",
+    "ft8", "ft9", "ft10", "ft11",
+];
+
+const ROUNDING_MODE_DYNAMIC: u32 = 0b111;
+const ROUNDING_MODE_FIELD: u32 = 0x7 << 12;
+const VM: u32 = 1 << 25;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AssemblerError {
+    InvalidRegister(String),
+    /// The register exists in RV32I but not in the RV32E base the program is assembled for.
+    RegisterNotInBase(String),
+    UnknownInstruction(String),
+    /// The operands don't match the format of the instruction, holds the whole line.
+    InvalidOperands(String),
+    InvalidImmediate(String),
+    ImmediateOutOfRange(i64),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+}
+
+#[derive(Default)]
+pub struct Assembler {
+    base: BaseIsa,
 }
 
 impl Assembler {
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// An assembler that only accepts what the base isa of `profile` has.
+    pub fn for_profile(profile: &IsaProfile) -> Self {
+        Self {
+            base: profile.base_isa(),
+        }
+    }
+
+    /// Assembles one instruction per line into little endian machine code. Lines can start with
+    /// `label:` and `#` starts a comment. Branches and jumps take a label or a byte offset.
+    pub fn assemble(&self, text: &str) -> Result<Vec<u8>, AssemblerError> {
+        // the first pass only collects the addresses of the labels
+        let mut labels = HashMap::new();
+        let mut address = 0;
+        for line in text.lines() {
+            let (line_labels, instruction) = split_labels(line);
+            for label in line_labels {
+                if labels.insert(label.to_string(), address).is_some() {
+                    return Err(AssemblerError::DuplicateLabel(label.to_string()));
+                }
+            }
+            if instruction.is_some() {
+                address += 4;
+            }
+        }
+
+        let mut code = Vec::with_capacity(address);
+        for line in text.lines() {
+            if let (_, Some(instruction)) = split_labels(line) {
+                let word = self.encode(instruction, code.len(), &labels)?;
+                code.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        Ok(code)
+    }
+
+    /// Encodes a single instruction like `addi a0, zero, 5`, labels are resolved relative to
+    /// `address`.
+    pub fn encode(
+        &self,
+        line: &str,
+        address: usize,
+        labels: &HashMap<String, usize>,
+    ) -> Result<u32, AssemblerError> {
+        let line = line.trim();
+        let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let mnemonic = mnemonic.to_ascii_lowercase();
+        let mut operands: Vec<&str> = operands.split(',').map(str::trim).filter(|x| !x.is_empty()).collect();
+        let invalid_operands = || AssemblerError::InvalidOperands(line.to_string());
+        let unknown_instruction = || AssemblerError::UnknownInstruction(mnemonic.clone());
+
+        // atomics take their ordering bits as a suffix, like `amoadd.w.aqrl`
+        let (name, ordering) = match mnemonic.rsplit_once('.') {
+            Some((name, ordering @ ("aq" | "rl" | "aqrl"))) => (name, ordering),
+            _ => (mnemonic.as_str(), ""),
+        };
+        let row = isa::find_mnemonic(name, self.base.xlen()).ok_or_else(unknown_instruction)?;
+
+        let mut word = row.match_val;
+        if !ordering.is_empty() {
+            if !matches!(row.format, Format::LoadReserved | Format::Atomic) {
+                return Err(unknown_instruction());
+            }
+            word |= (ordering.contains("aq") as u32) << 26 | (ordering.contains("rl") as u32) << 25;
+        }
+
+        // the rounding mode is an optional last operand of the float rows that leave rm open
+        if matches!(row.format, Format::Float { .. } | Format::R4) && row.mask & ROUNDING_MODE_FIELD == 0 {
+            let rm = operands.last().and_then(|x| ROUNDING_MODE_NAMES.iter().position(|name| name == x));
+            let rm = match rm {
+                Some(rm) => {
+                    operands.pop();
+                    rm as u32
+                }
+                None => ROUNDING_MODE_DYNAMIC,
+            };
+            word |= rm << 12;
+        }
+
+        // vector instructions are masked by a trailing `v0.t`, vm is set when it is left out
+        if matches!(row.format, Format::VectorMemory { .. } | Format::VectorArithmetic { .. }) {
+            match operands.last() {
+                Some(&"v0.t") => {
+                    operands.pop();
+                }
+                _ => word |= VM,
+            }
+        }
+
+        // a bare `fence` orders everything
+        if row.format == Format::Fence && operands.is_empty() {
+            operands = vec!["iorw", "iorw"];
+        }
+
+        let expected = match row.format {
+            Format::System => 0,
+            Format::Unary | Format::Load { .. } | Format::Store { .. } | Format::U | Format::J => 2,
+            Format::LoadReserved | Format::Fence => 2,
+            Format::Float { rd, rs1, rs2 } => {
+                [rd, rs1, rs2].iter().filter(|x| **x != RegisterClass::Unused).count()
+            }
+            Format::ByteSelect | Format::R4 => 4,
+            Format::VectorMemory { strided } => 2 + strided as usize,
+            // the vtype is a number or up to four names
+            Format::VectorConfig => operands.len().clamp(3, 6),
+            _ => 3,
+        };
+        if operands.len() != expected {
+            return Err(invalid_operands());
+        }
+
+        let integer = |operand: &str| self.parse_register(operand).map(|x| x as u32);
+        let float = |operand: &str| parse_float_register(operand).map(|x| x as u32);
+        let vector = |operand: &str| parse_vector_register(operand).map(|x| x as u32);
+        let immediate = |operand: &str| self.immediate(row.format, operand, address, labels);
+
+        word |= match row.format {
+            Format::R => {
+                integer(operands[0])? << 7 | integer(operands[1])? << 15 | integer(operands[2])? << 20
+            }
+            Format::ByteSelect => {
+                integer(operands[0])? << 7
+                    | integer(operands[1])? << 15
+                    | integer(operands[2])? << 20
+                    | immediate(operands[3])?
+            }
+            Format::Unary => integer(operands[0])? << 7 | integer(operands[1])? << 15,
+            Format::I | Format::Shift { .. } => {
+                integer(operands[0])? << 7 | integer(operands[1])? << 15 | immediate(operands[2])?
+            }
+            Format::Load { rd } => {
+                let (offset, base) = split_offset(operands[1]).ok_or_else(invalid_operands)?;
+                let rd = match rd {
+                    RegisterClass::Float => float(operands[0])?,
+                    _ => integer(operands[0])?,
+                };
+                rd << 7 | integer(base)? << 15 | immediate(offset)?
+            }
+            Format::Store { rs2 } => {
+                let (offset, base) = split_offset(operands[1]).ok_or_else(invalid_operands)?;
+                let rs2 = match rs2 {
+                    RegisterClass::Float => float(operands[0])?,
+                    _ => integer(operands[0])?,
+                };
+                rs2 << 20 | integer(base)? << 15 | immediate(offset)?
+            }
+            Format::Branch => {
+                integer(operands[0])? << 15 | integer(operands[1])? << 20 | immediate(operands[2])?
+            }
+            Format::U | Format::J => integer(operands[0])? << 7 | immediate(operands[1])?,
+            Format::System => 0,
+            Format::Fence => {
+                let pred = parse_fence_set(operands[0]).ok_or_else(invalid_operands)?;
+                let succ = parse_fence_set(operands[1]).ok_or_else(invalid_operands)?;
+                pred << 24 | succ << 20
+            }
+            Format::Csr => {
+                integer(operands[0])? << 7 | self.csr(operands[1])? | integer(operands[2])? << 15
+            }
+            Format::CsrImmediate => {
+                let uimm = parse_number(operands[2])?;
+                if !(0..32).contains(&uimm) {
+                    return Err(AssemblerError::ImmediateOutOfRange(uimm));
+                }
+                integer(operands[0])? << 7 | self.csr(operands[1])? | (uimm as u32) << 15
+            }
+            Format::Float { rd, rs1, rs2 } => {
+                let mut operands = operands.iter();
+                let mut fields = 0;
+                for (class, shift) in [(rd, 7), (rs1, 15), (rs2, 20)] {
+                    let number = match class {
+                        RegisterClass::Integer => integer(operands.next().unwrap())?,
+                        RegisterClass::Float => float(operands.next().unwrap())?,
+                        RegisterClass::Unused => continue,
+                    };
+                    fields |= number << shift;
+                }
+                fields
+            }
+            Format::R4 => {
+                float(operands[0])? << 7
+                    | float(operands[1])? << 15
+                    | float(operands[2])? << 20
+                    | float(operands[3])? << 27
+            }
+            Format::LoadReserved => {
+                let base = register_address(operands[1]).ok_or_else(invalid_operands)?;
+                integer(operands[0])? << 7 | integer(base)? << 15
+            }
+            Format::Atomic => {
+                let base = register_address(operands[2]).ok_or_else(invalid_operands)?;
+                integer(operands[0])? << 7 | integer(operands[1])? << 20 | integer(base)? << 15
+            }
+            Format::VectorConfig => {
+                let vtype = match operands[2..] {
+                    [number] if parse_number(number).is_ok() => immediate(number)?,
+                    ref names => parse_vtype(names).ok_or_else(invalid_operands)? << 20,
+                };
+                integer(operands[0])? << 7 | integer(operands[1])? << 15 | vtype
+            }
+            Format::VectorMemory { strided } => {
+                let base = register_address(operands[1]).ok_or_else(invalid_operands)?;
+                let stride = if strided { integer(operands[2])? << 20 } else { 0 };
+                vector(operands[0])? << 7 | integer(base)? << 15 | stride
+            }
+            Format::VectorArithmetic { source } => {
+                let source = match source {
+                    VectorSource::Vector => vector(operands[2])? << 15,
+                    VectorSource::Scalar => integer(operands[2])? << 15,
+                    VectorSource::Immediate => immediate(operands[2])?,
+                };
+                vector(operands[0])? << 7 | vector(operands[1])? << 20 | source
+            }
+        };
+        Ok(word)
+    }
+
+    /// Parses `x0`..`x31` or an abi name like `a0` into a register number.
+    pub fn parse_register(&self, name: &str) -> Result<usize, AssemblerError> {
+        let name = name.trim();
+        let register = match name.strip_prefix('x') {
+            Some(number) => number.parse::<usize>().ok().filter(|x| *x < 32),
+            None if name == "fp" => Some(8),
+            None => ABI_NAMES.iter().position(|x| *x == name),
+        }
+        .ok_or_else(|| AssemblerError::InvalidRegister(name.to_string()))?;
+
+        if register >= self.base.integer_register_count() {
+            return Err(AssemblerError::RegisterNotInBase(name.to_string()));
+        }
+        Ok(register)
+    }
+
+    // the encoded immediate, branch and jump targets can also be labels
+    fn immediate(
+        &self,
+        format: Format,
+        operand: &str,
+        address: usize,
+        labels: &HashMap<String, usize>,
+    ) -> Result<u32, AssemblerError> {
+        let is_target = matches!(format, Format::Branch | Format::J);
+        let value = match labels.get(operand) {
+            Some(target) if is_target => *target as i64 - address as i64,
+            _ if is_target && operand.starts_with(|x: char| x.is_alphabetic() || x == '_') => {
+                return Err(AssemblerError::UnknownLabel(operand.to_string()));
+            }
+            _ => parse_number(operand)?,
+        };
+
+        let (min, max) = format.immediate_range();
+        let misaligned = is_target && value % 2 != 0;
+        if value < min || value > max || misaligned {
+            return Err(AssemblerError::ImmediateOutOfRange(value));
+        }
+        Ok(format.encode_immediate(value as i32))
+    }
+
+    fn csr(&self, operand: &str) -> Result<u32, AssemblerError> {
+        let csr = match CSR_NAMES.iter().find(|(name, _)| *name == operand) {
+            Some((_, csr)) => *csr as i64,
+            None => parse_number(operand)?,
+        };
+        if !(0..0x1000).contains(&csr) {
+            return Err(AssemblerError::ImmediateOutOfRange(csr));
+        }
+        Ok((csr as u32) << 20)
+    }
+}
+
+fn parse_float_register(name: &str) -> Result<usize, AssemblerError> {
+    let name = name.trim();
+    match name.strip_prefix('f').and_then(|x| x.parse::<usize>().ok()) {
+        Some(number) if number < 32 => Some(number),
+        _ => FP_ABI_NAMES.iter().position(|x| *x == name),
+    }
+    .ok_or_else(|| AssemblerError::InvalidRegister(name.to_string()))
+}
+
+fn parse_vector_register(name: &str) -> Result<usize, AssemblerError> {
+    let name = name.trim();
+    match name.strip_prefix('v').and_then(|x| x.parse::<usize>().ok()) {
+        Some(number) if number < 32 => Ok(number),
+        _ => Err(AssemblerError::InvalidRegister(name.to_string())),
+    }
+}
+
+// `e32, m2, ta, ma` into vtype bits, the group size and the policies can be left out for `m1`,
+// `tu` and `mu`
+fn parse_vtype(names: &[&str]) -> Option<u32> {
+    let (sew, rest) = names.split_first()?;
+    let mut bits = (SEW_NAMES.iter().position(|x| x == sew)? as u32) << 3;
+    let mut rest = rest.iter().peekable();
+    if let Some(vlmul) = rest.peek().and_then(|x| LMUL_NAMES.iter().position(|name| !name.is_empty() && name == *x)) {
+        bits |= vlmul as u32;
+        rest.next();
+    }
+    if rest.next_if(|x| **x == "ta").is_some() {
+        bits |= 0x40;
+    } else {
+        rest.next_if(|x| **x == "tu");
+    }
+    if rest.next_if(|x| **x == "ma").is_some() {
+        bits |= 0x80;
+    } else {
+        rest.next_if(|x| **x == "mu");
+    }
+    rest.next().is_none().then_some(bits)
+}
+
+// decimal or 0x prefixed hex, both can be negative
+fn parse_number(text: &str) -> Result<i64, AssemblerError> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = match digits.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    }
+    .map_err(|_| AssemblerError::InvalidImmediate(text.to_string()))?;
+    Ok(if negative { -value } else { value })
+}
+
+// `8(sp)` into `("8", "sp")`, `(sp)` has an empty offset
+fn split_offset(operand: &str) -> Option<(&str, &str)> {
+    let (offset, base) = operand.strip_suffix(')')?.split_once('(')?;
+    Some((offset.trim(), base.trim()))
+}
+
+// `iorw` or any subset of it in that order, `0` for the empty set
+fn parse_fence_set(operand: &str) -> Option<u32> {
+    if operand == "0" {
+        return Some(0);
+    }
+    let mut bits = 0;
+    let mut rest = operand;
+    for (index, access) in ['i', 'o', 'r', 'w'].into_iter().enumerate() {
+        if let Some(stripped) = rest.strip_prefix(access) {
+            bits |= 8 >> index;
+            rest = stripped;
+        }
+    }
+    (rest.is_empty() && !operand.is_empty()).then_some(bits)
+}
+
+// `(sp)`, the address operand of the atomics has no offset
+fn register_address(operand: &str) -> Option<&str> {
+    split_offset(operand).filter(|(offset, _)| offset.is_empty()).map(|(_, base)| base)
+}
+
+// the labels a line defines and the instruction after them, comments are dropped
+fn split_labels(line: &str) -> (Vec<&str>, Option<&str>) {
+    let mut rest = line.split('#').next().unwrap_or("").trim();
+    let mut labels = vec![];
+    while let Some((label, after)) = rest.split_once(':') {
+        labels.push(label.trim());
+        rest = after.trim();
+    }
+    (labels, Some(rest).filter(|x| !x.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Assembler, AssemblerError};
+    use crate::{disassembler::disassemble, vm::isa::{Format, INSTRUCTIONS}};
+
+    #[test]
+    fn test_parse_register() {
+        let assembler = Assembler::new();
+        assert_eq!(assembler.parse_register("x0"), Ok(0));
+        assert_eq!(assembler.parse_register(" x31"), Ok(31));
+        assert_eq!(assembler.parse_register("a0"), Ok(10));
+        assert_eq!(assembler.parse_register("fp"), Ok(8));
+        assert_eq!(assembler.parse_register("t6"), Ok(31));
+        assert_eq!(assembler.parse_register("x32"), Err(AssemblerError::InvalidRegister("x32".to_string())));
+        assert_eq!(assembler.parse_register("q1"), Err(AssemblerError::InvalidRegister("q1".to_string())));
+    }
+
+    #[test]
+    fn test_parse_register_rv32e() {
+        let assembler = Assembler::for_profile(&"rv32e".parse().unwrap());
+        assert_eq!(assembler.parse_register("x15"), Ok(15));
+        assert_eq!(assembler.parse_register("a5"), Ok(15));
+        assert_eq!(assembler.parse_register("x16"), Err(AssemblerError::RegisterNotInBase("x16".to_string())));
+        assert_eq!(assembler.parse_register("a6"), Err(AssemblerError::RegisterNotInBase("a6".to_string())));
+    }
+
+    #[test]
+    fn test_assemble() {
+        let program = "
+            addi x1, x0, 3       # counter
+        loop:
+            addi x1, x1, -1
+            bne x1, zero, loop
+            lhu s0, 4(x0)
+            jalr ra, 0(t0)
+            fence
+            fence.i
+            vsetvli t0, a0, e32, m2, ta, ma
+            vlse32.v v2, (a1), t1
+            vadd.vi v4, v2, -1, v0.t
+            vredsum.vs v1, v4, v0
+            lui x3, 0x12345
+            auipc t0, 0x1
+            jal ra, done
+            ecall
+            ecall
+        done:
+            ecall
+        ";
+        let code = Assembler::new().assemble(program).unwrap();
+        assert_eq!(
+            code,
+            [
+                0x93, 0x00, 0x30, 0x00, // ADDI x1, x0, 3
+                0x93, 0x80, 0xf0, 0xff, // ADDI x1, x1, -1
+                0xe3, 0x9e, 0x00, 0xfe, // BNE x1, x0, -4
+                0x03, 0x54, 0x40, 0x00, // LHU x8, 4(x0)
+                0xe7, 0x80, 0x02, 0x00, // JALR x1, 0(x5)
+                0x0f, 0x00, 0xf0, 0x0f, // FENCE iorw, iorw
+                0x0f, 0x10, 0x00, 0x00, // FENCE.I
+                0xd7, 0x72, 0x15, 0x0d, // VSETVLI x5, x10, e32, m2, ta, ma
+                0x07, 0xe1, 0x65, 0x0a, // VLSE32.V v2, (x11), x6
+                0x57, 0xb2, 0x2f, 0x00, // VADD.VI v4, v2, -1, v0.t
+                0xd7, 0x20, 0x40, 0x02, // VREDSUM.VS v1, v4, v0
+                0xb7, 0x51, 0x34, 0x12, // LUI x3, 0x12345
+                0x97, 0x12, 0x00, 0x00, // AUIPC x5, 0x1
+                0xef, 0x00, 0xc0, 0x00, // JAL x1, 12
+                0x73, 0x00, 0x00, 0x00, // ECALL
+                0x73, 0x00, 0x00, 0x00, // ECALL
+                0x73, 0x00, 0x00, 0x00, // ECALL
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let assembler = Assembler::new();
+        let error = |text: &str| assembler.assemble(text).unwrap_err();
+        assert_eq!(error("addx x1, x0, 1"), AssemblerError::UnknownInstruction("addx".to_string()));
+        assert_eq!(error("addi x1, x0"), AssemblerError::InvalidOperands("addi x1, x0".to_string()));
+        assert_eq!(error("addi x1, x0, 2048"), AssemblerError::ImmediateOutOfRange(2048));
+        assert_eq!(error("beq x1, x0, 3"), AssemblerError::ImmediateOutOfRange(3));
+        assert_eq!(error("beq x1, x0, nowhere"), AssemblerError::UnknownLabel("nowhere".to_string()));
+        assert_eq!(error("add.aq x1, x0, x0"), AssemblerError::UnknownInstruction("add.aq".to_string()));
+        assert_eq!(error("a: ecall\na: ecall"), AssemblerError::DuplicateLabel("a".to_string()));
+        assert_eq!(
+            error("vsetvli x0, x0, e32, ta, m1"),
+            AssemblerError::InvalidOperands("vsetvli x0, x0, e32, ta, m1".to_string())
+        );
+        assert_eq!(error("vadd.vi v1, v2, 16"), AssemblerError::ImmediateOutOfRange(16));
     }
 
-    pub fn assemble(text: &str) {
-        let mut text = String::new();
-        for line in text.lines() {}
+    // every row of the table assembles back from its own disassembly
+    #[test]
+    fn test_table_round_trips() {
+        let rv32 = Assembler::new();
+        let rv64 = Assembler::for_profile(&"rv64i".parse().unwrap());
+        let labels = HashMap::new();
+        for row in INSTRUCTIONS {
+            let assembler = if row.xlen == Some(64) { &rv64 } else { &rv32 };
+            // the fences leave their other fields reserved, assembly always writes them as zero
+            let open = match row.format {
+                Format::System => 0,
+                Format::Fence => 0x0ff0_0000,
+                _ => !row.mask,
+            };
+            let word = row.match_val | (0x5a5a_0a80 & open);
+            let text = disassemble(word).unwrap();
+            assert_eq!(assembler.encode(&text, 0, &labels), Ok(word), "{text}");
+        }
     }
 }
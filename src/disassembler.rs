@@ -0,0 +1,186 @@
+use crate::vm::isa::{
+    self, CSR_NAMES, Format, LMUL_NAMES, ROUNDING_MODE_NAMES, RegisterClass, SEW_NAMES, VectorSource,
+};
+
+const ROUNDING_MODE_FIELD: u32 = 0x7 << 12;
+const AQ: u32 = 1 << 26;
+const RL: u32 = 1 << 25;
+const VM: u32 = 1 << 25;
+
+fn field(instruction: u32, shift: u32) -> u32 {
+    (instruction >> shift) & 0x1f
+}
+
+fn register(class: RegisterClass, number: u32) -> String {
+    match class {
+        RegisterClass::Float => format!("f{number}"),
+        RegisterClass::Integer | RegisterClass::Unused => format!("x{number}"),
+    }
+}
+
+// the four bits of a fence predecessor or successor set, like `rw`
+fn fence_set(bits: u32) -> String {
+    let set: String = ['i', 'o', 'r', 'w']
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| bits & (8 >> index) != 0)
+        .map(|(_, access)| access)
+        .collect();
+    match set.is_empty() {
+        true => "0".to_string(),
+        false => set,
+    }
+}
+
+// `e32, m1, ta, ma`, or the number when it has reserved bits
+fn vtype(bits: u32) -> Vec<String> {
+    let lmul = LMUL_NAMES[(bits & 0x7) as usize];
+    match SEW_NAMES.get(((bits >> 3) & 0x7) as usize) {
+        Some(sew) if bits >> 8 == 0 && !lmul.is_empty() => {
+            let tail = if bits & 0x40 != 0 { "ta" } else { "tu" };
+            let mask = if bits & 0x80 != 0 { "ma" } else { "mu" };
+            [sew, lmul, tail, mask].map(str::to_string).to_vec()
+        }
+        _ => vec![format!("{bits:#x}")],
+    }
+}
+
+fn csr(number: i32) -> String {
+    match CSR_NAMES.iter().find(|(_, csr)| *csr as i32 == number) {
+        Some((name, _)) => name.to_string(),
+        None => format!("{number:#05x}"),
+    }
+}
+
+/// Formats an instruction word the way the assembler reads it, like `addi x1, x0, 5`. Branch and
+/// jump targets are written as offsets from the instruction. Returns `None` for words that aren't
+/// in the instruction table.
+pub fn disassemble(instruction: u32) -> Option<String> {
+    use RegisterClass::{Float, Integer, Unused};
+
+    let row = isa::find(instruction)?;
+    let immediate = row.format.immediate(instruction);
+    let rd = field(instruction, 7);
+    let rs1 = field(instruction, 15);
+    let rs2 = field(instruction, 20);
+
+    let mut mnemonic = row.mnemonic.to_string();
+    let mut operands = match row.format {
+        Format::R => vec![register(Integer, rd), register(Integer, rs1), register(Integer, rs2)],
+        Format::ByteSelect => vec![
+            register(Integer, rd),
+            register(Integer, rs1),
+            register(Integer, rs2),
+            immediate.to_string(),
+        ],
+        Format::Unary => vec![register(Integer, rd), register(Integer, rs1)],
+        Format::I | Format::Shift { .. } => {
+            vec![register(Integer, rd), register(Integer, rs1), immediate.to_string()]
+        }
+        Format::Load { rd: class } => vec![register(class, rd), format!("{immediate}(x{rs1})")],
+        Format::Store { rs2: class } => vec![register(class, rs2), format!("{immediate}(x{rs1})")],
+        Format::Branch => vec![register(Integer, rs1), register(Integer, rs2), immediate.to_string()],
+        Format::U => vec![register(Integer, rd), format!("{immediate:#x}")],
+        Format::J => vec![register(Integer, rd), immediate.to_string()],
+        Format::System => vec![],
+        Format::Fence => vec![fence_set(instruction >> 24), fence_set(instruction >> 20)],
+        Format::Csr => vec![register(Integer, rd), csr(immediate), register(Integer, rs1)],
+        Format::CsrImmediate => vec![register(Integer, rd), csr(immediate), rs1.to_string()],
+        Format::Float { rd: rd_class, rs1: rs1_class, rs2: rs2_class } => {
+            [(rd_class, rd), (rs1_class, rs1), (rs2_class, rs2)]
+                .into_iter()
+                .filter(|(class, _)| *class != Unused)
+                .map(|(class, number)| register(class, number))
+                .collect()
+        }
+        Format::R4 => vec![
+            register(Float, rd),
+            register(Float, rs1),
+            register(Float, rs2),
+            register(Float, field(instruction, 27)),
+        ],
+        Format::LoadReserved => vec![register(Integer, rd), format!("(x{rs1})")],
+        Format::Atomic => vec![register(Integer, rd), register(Integer, rs2), format!("(x{rs1})")],
+        Format::VectorConfig => {
+            let mut operands = vec![register(Integer, rd), register(Integer, rs1)];
+            operands.extend(vtype(immediate as u32));
+            operands
+        }
+        Format::VectorMemory { strided } => {
+            let mut operands = vec![format!("v{rd}"), format!("(x{rs1})")];
+            if strided {
+                operands.push(register(Integer, rs2));
+            }
+            operands
+        }
+        Format::VectorArithmetic { source } => {
+            let source = match source {
+                VectorSource::Vector => format!("v{rs1}"),
+                VectorSource::Scalar => register(Integer, rs1),
+                VectorSource::Immediate => immediate.to_string(),
+            };
+            vec![format!("v{rd}"), format!("v{rs2}"), source]
+        }
+    };
+
+    let is_vector = matches!(row.format, Format::VectorMemory { .. } | Format::VectorArithmetic { .. });
+    if is_vector && instruction & VM == 0 {
+        operands.push("v0.t".to_string());
+    }
+
+    if matches!(row.format, Format::LoadReserved | Format::Atomic) {
+        mnemonic += match (instruction & AQ != 0, instruction & RL != 0) {
+            (true, true) => ".aqrl",
+            (true, false) => ".aq",
+            (false, true) => ".rl",
+            (false, false) => "",
+        };
+    }
+
+    let mut text = mnemonic;
+    if !operands.is_empty() {
+        text = format!("{text} {}", operands.join(", "));
+    }
+    // the dynamic rounding mode is the default and left out
+    if matches!(row.format, Format::Float { .. } | Format::R4) && row.mask & ROUNDING_MODE_FIELD == 0 {
+        let rm = ((instruction & ROUNDING_MODE_FIELD) >> 12) as usize;
+        if let Some(name) = ROUNDING_MODE_NAMES.get(rm) {
+            text = format!("{text}, {name}");
+        }
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disassemble;
+
+    #[test]
+    fn test_disassemble() {
+        assert_eq!(disassemble(0x0050_0093).as_deref(), Some("addi x1, x0, 5"));
+        assert_eq!(disassemble(0x0040_5403).as_deref(), Some("lhu x8, 4(x0)"));
+        assert_eq!(disassemble(0xfe20_8ee3).as_deref(), Some("beq x1, x2, -4"));
+        assert_eq!(disassemble(0x0000_0073).as_deref(), Some("ecall"));
+        assert_eq!(disassemble(0x0ff0_000f).as_deref(), Some("fence iorw, iorw"));
+        assert_eq!(disassemble(0x0310_000f).as_deref(), Some("fence rw, w"));
+        assert_eq!(disassemble(0x1234_51b7).as_deref(), Some("lui x3, 0x12345"));
+        assert_eq!(disassemble(0x0000_1297).as_deref(), Some("auipc x5, 0x1"));
+        assert_eq!(disassemble(0x00c0_00ef).as_deref(), Some("jal x1, 12"));
+        assert_eq!(disassemble(0x0030_9073).as_deref(), Some("csrrw x0, fcsr, x1"));
+        assert_eq!(disassemble(0x0020_f0d3).as_deref(), Some("fadd.s f1, f1, f2"));
+        assert_eq!(disassemble(0xc000_1053).as_deref(), Some("fcvt.w.s x0, f0, rtz"));
+        assert_eq!(disassemble(0xe620_81b3).as_deref(), Some("aes32esmi x3, x1, x2, 3"));
+        assert_eq!(disassemble(0x0620_a1af).as_deref(), Some("amoadd.w.aqrl x3, x2, (x1)"));
+        assert_eq!(disassemble(0x0d10_7057).as_deref(), Some("vsetvli x0, x0, e32, m2, ta, ma"));
+        assert_eq!(disassemble(0x0201_6087).as_deref(), Some("vle32.v v1, (x2)"));
+        assert_eq!(disassemble(0x0a31_60a7).as_deref(), Some("vsse32.v v1, (x2), x3"));
+        assert_eq!(disassemble(0x0020_b0d7).as_deref(), Some("vadd.vi v1, v2, 1, v0.t"));
+        assert_eq!(disassemble(0xffff_ffff), None);
+    }
+
+    #[test]
+    fn test_disassemble_jalr() {
+        // jalr used to be decoded as lhu
+        assert_eq!(disassemble(0x0002_80e7).as_deref(), Some("jalr x1, 0(x5)"));
+    }
+}
@@ -125,12 +125,16 @@ pub const MATCH_BCLR: u32 = 0x48001033;
 pub const MASK_BCLR: u32 = 0xfe00707f;
 pub const MATCH_BCLRI: u32 = 0x48001013;
 pub const MASK_BCLRI: u32 = 0xfc00707f;
+pub const MATCH_BCLRI_RV32: u32 = 0x48001013;
+pub const MASK_BCLRI_RV32: u32 = 0xfe00707f;
 pub const MATCH_BEQ: u32 = 0x63;
 pub const MASK_BEQ: u32 = 0x707f;
 pub const MATCH_BEXT: u32 = 0x48005033;
 pub const MASK_BEXT: u32 = 0xfe00707f;
 pub const MATCH_BEXTI: u32 = 0x48005013;
 pub const MASK_BEXTI: u32 = 0xfc00707f;
+pub const MATCH_BEXTI_RV32: u32 = 0x48005013;
+pub const MASK_BEXTI_RV32: u32 = 0xfe00707f;
 pub const MATCH_BGE: u32 = 0x5063;
 pub const MASK_BGE: u32 = 0x707f;
 pub const MATCH_BGEU: u32 = 0x7063;
@@ -139,6 +143,8 @@ pub const MATCH_BINV: u32 = 0x68001033;
 pub const MASK_BINV: u32 = 0xfe00707f;
 pub const MATCH_BINVI: u32 = 0x68001013;
 pub const MASK_BINVI: u32 = 0xfc00707f;
+pub const MATCH_BINVI_RV32: u32 = 0x68001013;
+pub const MASK_BINVI_RV32: u32 = 0xfe00707f;
 pub const MATCH_BLT: u32 = 0x4063;
 pub const MASK_BLT: u32 = 0x707f;
 pub const MATCH_BLTU: u32 = 0x6063;
@@ -149,6 +155,8 @@ pub const MATCH_BSET: u32 = 0x28001033;
 pub const MASK_BSET: u32 = 0xfe00707f;
 pub const MATCH_BSETI: u32 = 0x28001013;
 pub const MASK_BSETI: u32 = 0xfc00707f;
+pub const MATCH_BSETI_RV32: u32 = 0x28001013;
+pub const MASK_BSETI_RV32: u32 = 0xfe00707f;
 pub const MATCH_C_ADD: u32 = 0x9002;
 pub const MASK_C_ADD: u32 = 0xf003;
 pub const MATCH_C_ADDI: u32 = 0x1;
@@ -761,6 +769,8 @@ pub const MATCH_MULW: u32 = 0x200003b;
 pub const MASK_MULW: u32 = 0xfe00707f;
 pub const MATCH_OR: u32 = 0x6033;
 pub const MASK_OR: u32 = 0xfe00707f;
+pub const MATCH_ORC_B: u32 = 0x28705013;
+pub const MASK_ORC_B: u32 = 0xfff0707f;
 pub const MATCH_ORI: u32 = 0x6013;
 pub const MASK_ORI: u32 = 0x707f;
 pub const MATCH_ORN: u32 = 0x40006033;
@@ -779,6 +789,8 @@ pub const MATCH_REMUW: u32 = 0x200703b;
 pub const MASK_REMUW: u32 = 0xfe00707f;
 pub const MATCH_REMW: u32 = 0x200603b;
 pub const MASK_REMW: u32 = 0xfe00707f;
+pub const MATCH_REV8_RV32: u32 = 0x69805013;
+pub const MASK_REV8_RV32: u32 = 0xfff0707f;
 pub const MATCH_ROL: u32 = 0x60001033;
 pub const MASK_ROL: u32 = 0xfe00707f;
 pub const MATCH_ROLW: u32 = 0x6000103b;
@@ -787,6 +799,8 @@ pub const MATCH_ROR: u32 = 0x60005033;
 pub const MASK_ROR: u32 = 0xfe00707f;
 pub const MATCH_RORI: u32 = 0x60005013;
 pub const MASK_RORI: u32 = 0xfc00707f;
+pub const MATCH_RORI_RV32: u32 = 0x60005013;
+pub const MASK_RORI_RV32: u32 = 0xfe00707f;
 pub const MATCH_RORIW: u32 = 0x6000501b;
 pub const MASK_RORIW: u32 = 0xfe00707f;
 pub const MATCH_RORW: u32 = 0x6000503b;
@@ -867,6 +881,8 @@ pub const MATCH_SLL: u32 = 0x1033;
 pub const MASK_SLL: u32 = 0xfe00707f;
 pub const MATCH_SLLI: u32 = 0x1013;
 pub const MASK_SLLI: u32 = 0xfc00707f;
+pub const MATCH_SLLI_RV32: u32 = 0x1013;
+pub const MASK_SLLI_RV32: u32 = 0xfe00707f;
 pub const MATCH_SLLI_UW: u32 = 0x800101b;
 pub const MASK_SLLI_UW: u32 = 0xfc00707f;
 pub const MATCH_SLLIW: u32 = 0x101b;
@@ -893,6 +909,8 @@ pub const MATCH_SRA: u32 = 0x40005033;
 pub const MASK_SRA: u32 = 0xfe00707f;
 pub const MATCH_SRAI: u32 = 0x40005013;
 pub const MASK_SRAI: u32 = 0xfc00707f;
+pub const MATCH_SRAI_RV32: u32 = 0x40005013;
+pub const MASK_SRAI_RV32: u32 = 0xfe00707f;
 pub const MATCH_SRAIW: u32 = 0x4000501b;
 pub const MASK_SRAIW: u32 = 0xfe00707f;
 pub const MATCH_SRAW: u32 = 0x4000503b;
@@ -903,6 +921,8 @@ pub const MATCH_SRL: u32 = 0x5033;
 pub const MASK_SRL: u32 = 0xfe00707f;
 pub const MATCH_SRLI: u32 = 0x5013;
 pub const MASK_SRLI: u32 = 0xfc00707f;
+pub const MATCH_SRLI_RV32: u32 = 0x5013;
+pub const MASK_SRLI_RV32: u32 = 0xfe00707f;
 pub const MATCH_SRLIW: u32 = 0x501b;
 pub const MASK_SRLIW: u32 = 0xfe00707f;
 pub const MATCH_SRLW: u32 = 0x503b;
@@ -1801,6 +1821,8 @@ pub const MATCH_XPERM4: u32 = 0x28002033;
 pub const MASK_XPERM4: u32 = 0xfe00707f;
 pub const MATCH_XPERM8: u32 = 0x28004033;
 pub const MASK_XPERM8: u32 = 0xfe00707f;
+pub const MATCH_ZEXT_H_RV32: u32 = 0x8004033;
+pub const MASK_ZEXT_H_RV32: u32 = 0xfff0707f;
 pub const CSR_FFLAGS: u16 = 0x1;
 pub const CSR_FRM: u16 = 0x2;
 pub const CSR_FCSR: u16 = 0x3;
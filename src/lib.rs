@@ -1,12 +1,13 @@
+#![allow(clippy::upper_case_acronyms, clippy::module_inception)]
+
 pub mod assembler;
+pub mod disassembler;
 pub mod inst;
 pub mod instruction_parser;
 pub mod vm;
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     fn get_assembly_string<'a>() -> &'a str {
         r#"
     li 
@@ -15,7 +16,7 @@ mod tests {
 
     #[test]
     fn run_assembly() {
-        let asm = get_assembly_string();
+        let _asm = get_assembly_string();
         assert_eq!(4, 4);
     }
 }
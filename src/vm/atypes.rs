@@ -0,0 +1,56 @@
+use super::common::{AtomicOperation, OperandsFormat, REGISTER_COUNT};
+
+/// Extra memory stage cycles of an amo, which reads the word and then writes it back.
+pub const AMO_MEMORY_CYCLES: usize = 1;
+/// Extra memory stage cycles for each of the aq and rl bits, spent waiting until the accesses
+/// before (rl) or after (aq) the atomic can no longer be reordered around it.
+pub const ORDERING_MEMORY_CYCLES: usize = 1;
+
+/// Number of cycles an atomic holds the memory stage on top of the single cycle of a plain load.
+pub fn extra_memory_cycles(operation: AtomicOperation, aq: bool, rl: bool) -> usize {
+    let access = match operation {
+        AtomicOperation::LoadReserved | AtomicOperation::StoreConditional => 0,
+        _ => AMO_MEMORY_CYCLES,
+    };
+    access + (aq as usize + rl as usize) * ORDERING_MEMORY_CYCLES
+}
+
+/// The value an amo writes back, given the word it loaded and rs2.
+pub fn amo_value(operation: AtomicOperation, loaded: i32, r2_val: i32) -> i32 {
+    match operation {
+        AtomicOperation::Swap => r2_val,
+        AtomicOperation::Add => loaded.wrapping_add(r2_val),
+        AtomicOperation::Xor => loaded ^ r2_val,
+        AtomicOperation::And => loaded & r2_val,
+        AtomicOperation::Or => loaded | r2_val,
+        AtomicOperation::Min => loaded.min(r2_val),
+        AtomicOperation::Max => loaded.max(r2_val),
+        AtomicOperation::MinUnsigned => (loaded as u32).min(r2_val as u32) as i32,
+        AtomicOperation::MaxUnsigned => (loaded as u32).max(r2_val as u32) as i32,
+        AtomicOperation::LoadReserved
+        | AtomicOperation::StoreConditional
+        | AtomicOperation::CompareAndSwap => unreachable!(),
+    }
+}
+
+pub fn extract_atype(
+    instruction: u32,
+    registers: &[i64; REGISTER_COUNT],
+    operation: AtomicOperation,
+) -> OperandsFormat {
+    let rd = ((instruction >> 7) & 0x1f) as usize;
+    let r1 = ((instruction >> 15) & 0x1f) as usize;
+    let r2 = ((instruction >> 20) & 0x1f) as usize;
+
+    OperandsFormat::Atype {
+        r1_val: registers[r1],
+        r2_val: registers[r2],
+        rd_val: registers[rd],
+        aq: (instruction >> 26) & 1 == 1,
+        rl: (instruction >> 25) & 1 == 1,
+        operation,
+        rd,
+        r1,
+        r2,
+    }
+}
@@ -0,0 +1,5 @@
+// orc.b sets every byte that isn't zero to 0xff
+pub fn or_combine_bytes(value: i32) -> i32 {
+    let bytes = value.to_le_bytes().map(|byte| if byte == 0 { 0x00 } else { 0xff });
+    i32::from_le_bytes(bytes)
+}
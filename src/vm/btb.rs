@@ -0,0 +1,193 @@
+// A set-associative cache of jump targets that fetch looks up with the address of every word it
+// fetches. Entries keep the whole address as their tag, so a hit always belongs to the jump that
+// was installed. Jumps whose addresses map to the same set compete for its ways and evict each
+// other, which is the aliasing the stats count.
+
+/// Which way of a full set a new jump replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacementPolicy {
+    /// The entry that was hit or installed the longest time ago.
+    LeastRecentlyUsed,
+    /// The entry that was installed first, hits don't keep an entry around.
+    FirstInFirstOut,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BtbConfig {
+    /// Total number of entries, 0 turns the BTB off and every jump flushes.
+    pub entries: usize,
+    /// Entries per set, `entries` has to be a multiple of it.
+    pub associativity: usize,
+    pub replacement: ReplacementPolicy,
+}
+
+impl Default for BtbConfig {
+    /// 32 entries in 16 sets of 2.
+    fn default() -> Self {
+        Self {
+            entries: 32,
+            associativity: 2,
+            replacement: ReplacementPolicy::LeastRecentlyUsed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BtbConfigError {
+    ZeroAssociativity,
+    /// `entries` isn't a multiple of `associativity`.
+    UnevenSets,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BtbStats {
+    /// Jumps that fetch found in the BTB and followed without a bubble.
+    pub hits: u64,
+    /// Jumps that weren't in the BTB, or had a stale target, and flushed when they executed.
+    pub misses: u64,
+    /// Installs that evicted the entry of another jump mapping to the same set.
+    pub aliasing: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    address: usize,
+    target: usize,
+    stamp: u64, // when the entry was installed, or last hit under LRU
+}
+
+pub struct BranchTargetBuffer {
+    config: BtbConfig,
+    sets: Vec<Vec<Entry>>,
+    clock: u64,
+    stats: BtbStats,
+}
+
+impl BranchTargetBuffer {
+    pub fn new(config: BtbConfig) -> Result<Self, BtbConfigError> {
+        if config.associativity == 0 {
+            return Err(BtbConfigError::ZeroAssociativity);
+        }
+        if !config.entries.is_multiple_of(config.associativity) {
+            return Err(BtbConfigError::UnevenSets);
+        }
+        Ok(Self {
+            config,
+            sets: vec![Vec::with_capacity(config.associativity); config.entries / config.associativity],
+            clock: 0,
+            stats: BtbStats::default(),
+        })
+    }
+
+    pub fn config(&self) -> BtbConfig {
+        self.config
+    }
+
+    pub fn stats(&self) -> BtbStats {
+        self.stats
+    }
+
+    // instructions are word aligned, the low bits would leave most sets unused
+    fn set(&mut self, address: usize) -> Option<&mut Vec<Entry>> {
+        let count = self.sets.len();
+        (count > 0).then(|| &mut self.sets[(address / 4) % count])
+    }
+
+    /// The target of the jump at `address`, if it is in the BTB.
+    pub fn lookup(&mut self, address: usize) -> Option<usize> {
+        self.clock += 1;
+        let (clock, policy) = (self.clock, self.config.replacement);
+        let entry = self.set(address)?.iter_mut().find(|x| x.address == address)?;
+        if policy == ReplacementPolicy::LeastRecentlyUsed {
+            entry.stamp = clock;
+        }
+        Some(entry.target)
+    }
+
+    /// Called when the jump at `address` executes, `hit` tells whether fetch already followed it to
+    /// `target`. A miss installs the jump.
+    pub fn update(&mut self, address: usize, target: usize, hit: bool) {
+        if hit {
+            self.stats.hits += 1;
+            return;
+        }
+        self.stats.misses += 1;
+
+        self.clock += 1;
+        let (clock, ways) = (self.clock, self.config.associativity);
+        let Some(set) = self.set(address) else {
+            return;
+        };
+        let entry = Entry {
+            address,
+            target,
+            stamp: clock,
+        };
+        if let Some(stale) = set.iter_mut().find(|x| x.address == address) {
+            *stale = entry;
+        } else if set.len() < ways {
+            set.push(entry);
+        } else {
+            let victim = set.iter_mut().min_by_key(|x| x.stamp).unwrap();
+            *victim = entry;
+            self.stats.aliasing += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BranchTargetBuffer, BtbConfig, BtbConfigError, BtbStats, ReplacementPolicy};
+
+    fn btb(entries: usize, associativity: usize, replacement: ReplacementPolicy) -> BranchTargetBuffer {
+        BranchTargetBuffer::new(BtbConfig {
+            entries,
+            associativity,
+            replacement,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_install_and_hit() {
+        let mut btb = btb(4, 1, ReplacementPolicy::LeastRecentlyUsed);
+        assert_eq!(btb.lookup(8), None);
+        btb.update(8, 100, false);
+        assert_eq!(btb.lookup(8), Some(100));
+        btb.update(8, 100, true);
+        // 8 and 24 share set 2 of the direct mapped BTB
+        btb.update(24, 200, false);
+        assert_eq!(btb.lookup(8), None);
+        assert_eq!(btb.lookup(24), Some(200));
+        assert_eq!(btb.stats(), BtbStats { hits: 1, misses: 2, aliasing: 1 });
+    }
+
+    #[test]
+    fn test_replacement_policies() {
+        // one set of two ways, 0 is installed first and hit last
+        for (policy, survivor, evicted) in [
+            (ReplacementPolicy::LeastRecentlyUsed, 0, 4),
+            (ReplacementPolicy::FirstInFirstOut, 4, 0),
+        ] {
+            let mut btb = btb(2, 2, policy);
+            btb.update(0, 100, false);
+            btb.update(4, 104, false);
+            btb.lookup(0);
+            btb.update(8, 108, false);
+            assert!(btb.lookup(survivor).is_some(), "{policy:?}");
+            assert_eq!(btb.lookup(evicted), None, "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn test_disabled_and_invalid() {
+        let mut btb = btb(0, 1, ReplacementPolicy::FirstInFirstOut);
+        btb.update(0, 100, false);
+        assert_eq!(btb.lookup(0), None);
+
+        let config = BtbConfig { entries: 6, associativity: 4, ..BtbConfig::default() };
+        assert_eq!(BranchTargetBuffer::new(config).err(), Some(BtbConfigError::UnevenSets));
+        let config = BtbConfig { associativity: 0, ..BtbConfig::default() };
+        assert_eq!(BranchTargetBuffer::new(config).err(), Some(BtbConfigError::ZeroAssociativity));
+    }
+}
@@ -1,8 +1,7 @@
-use crate::inst::{MASK_BEQ, MASK_BGE, MASK_BGEU, MASK_BLT, MASK_BLTU, MASK_BNE, MATCH_BEQ, MATCH_BGE, MATCH_BGEU, MATCH_BLT, MATCH_BLTU, MATCH_BNE};
+use super::common::{OperandsFormat, REGISTER_COUNT};
 
-use super::common::{EXMEM, ExecuteResult, IDEX, InstructionDefinition, OperandsFormat};
-
-fn extract_btype(instruction: u32, registers: &[i32; 32]) -> OperandsFormat {
+/// The offset of the branch target, fetch reads it to follow branches predicted taken.
+pub fn btype_offset(instruction: u32) -> i32 {
     let imm_11 = ((instruction >> 7) & 0x01) as i32;
     let imm_4_1 = ((instruction >> 8) & 0x0f) as i32;
     let imm_10_5 = ((instruction >> 25) & 0x3f) as i32;
@@ -14,252 +13,18 @@ fn extract_btype(instruction: u32, registers: &[i32; 32]) -> OperandsFormat {
     if imm & (1 << 12) != 0 {
         imm |= !0 << 13;
     }
+    imm
+}
 
+pub fn extract_btype(instruction: u32, registers: &[i64; REGISTER_COUNT]) -> OperandsFormat {
     let r1 = ((instruction >> 15) & 0x1f) as usize;
     let r2 = ((instruction >> 20) & 0x1f) as usize;
 
     OperandsFormat::Btype {
-        imm,
+        imm: btype_offset(instruction),
         r1_val: registers[r1],
         r2_val: registers[r2],
         r1,
         r2,
     }
 }
-
-pub const BTYPE_LIST: [InstructionDefinition; 6] = [
-    InstructionDefinition {
-        mask: MASK_BEQ,
-        match_val: MATCH_BEQ,
-        decode: |instruction, registers, address| IDEX {
-            operands: Some(extract_btype(instruction, registers)),
-            memory_operation: None,
-            address,
-            execute: |id_ex| {
-                if let Some(OperandsFormat::Btype {
-                    imm,
-                    r1_val,
-                    r2_val,
-                    ..
-                }) = &id_ex.operands
-                {
-                    let old_pc = id_ex.address;
-                    let mut flush = false;
-                    let mut new_pc = None;
-                    if r1_val == r2_val {
-                        new_pc = Some(old_pc.wrapping_add(*imm as usize));
-                        flush = true;
-                    }
-                    ExecuteResult {
-                        ex_mem: EXMEM {
-                            rd: None,
-                            calculation_result: imm << 12,
-                            memory_operation: None,
-                            operands: id_ex.operands.clone(),
-                        },
-                        flush,
-                        new_pc,
-                        trap_type: None,
-                    }
-                } else {
-                    unreachable!()
-                }
-            },
-        },
-    },
-    InstructionDefinition {
-        mask: MASK_BNE,
-        match_val: MATCH_BNE,
-        decode: |instruction, registers, address| IDEX {
-            operands: Some(extract_btype(instruction, registers)),
-            memory_operation: None,
-            address,
-            execute: |id_ex| {
-                if let Some(OperandsFormat::Btype {
-                    imm,
-                    r1_val,
-                    r2_val,
-                    ..
-                }) = &id_ex.operands
-                {
-                    let old_pc = id_ex.address;
-                    let mut flush = false;
-                    let mut new_pc = None;
-                    if r1_val != r2_val {
-                        new_pc = Some(old_pc.wrapping_add(*imm as usize));
-                        flush = true;
-                    }
-                    ExecuteResult {
-                        ex_mem: EXMEM {
-                            rd: None,
-                            calculation_result: imm << 12,
-                            memory_operation: None,
-                            operands: id_ex.operands.clone(),
-                        },
-                        flush,
-                        new_pc,
-                        trap_type: None,
-                    }
-                } else {
-                    unreachable!()
-                }
-            },
-        },
-    },
-    InstructionDefinition {
-        mask: MASK_BLT,
-        match_val: MATCH_BLT,
-        decode: |instruction, registers, address| IDEX {
-            operands: Some(extract_btype(instruction, registers)),
-            memory_operation: None,
-            address,
-            execute: |id_ex| {
-                if let Some(OperandsFormat::Btype {
-                    imm,
-                    r1_val,
-                    r2_val,
-                    ..
-                }) = &id_ex.operands
-                {
-                    let old_pc = id_ex.address;
-                    let mut flush = false;
-                    let mut new_pc = None;
-                    if r1_val < r2_val {
-                        new_pc = Some(old_pc.wrapping_add(*imm as usize));
-                        flush = true;
-                    }
-                    ExecuteResult {
-                        ex_mem: EXMEM {
-                            rd: None,
-                            calculation_result: imm << 12,
-                            memory_operation: None,
-                            operands: id_ex.operands.clone(),
-                        },
-                        flush,
-                        new_pc,
-                        trap_type: None,
-                    }
-                } else {
-                    unreachable!()
-                }
-            },
-        },
-    },
-    InstructionDefinition {
-        mask: MASK_BGE,
-        match_val: MATCH_BGE,
-        decode: |instruction, registers, address| IDEX {
-            operands: Some(extract_btype(instruction, registers)),
-            memory_operation: None,
-            address,
-            execute: |id_ex| {
-                if let Some(OperandsFormat::Btype {
-                    imm,
-                    r1_val,
-                    r2_val,
-                    ..
-                }) = &id_ex.operands
-                {
-                    let old_pc = id_ex.address;
-                    let mut flush = false;
-                    let mut new_pc = None;
-                    if r1_val >= r2_val {
-                        new_pc = Some(old_pc.wrapping_add(*imm as usize));
-                        flush = true;
-                    }
-                    ExecuteResult {
-                        ex_mem: EXMEM {
-                            rd: None,
-                            calculation_result: imm << 12,
-                            memory_operation: None,
-                            operands: id_ex.operands.clone(),
-                        },
-                        flush,
-                        new_pc,
-                        trap_type: None,
-                    }
-                } else {
-                    unreachable!()
-                }
-            },
-        },
-    },
-    InstructionDefinition {
-        mask: MASK_BLTU,
-        match_val: MATCH_BLTU,
-        decode: |instruction, registers, address| IDEX {
-            operands: Some(extract_btype(instruction, registers)),
-            memory_operation: None,
-            address,
-            execute: |id_ex| {
-                if let Some(OperandsFormat::Btype {
-                    imm,
-                    r1_val,
-                    r2_val,
-                    ..
-                }) = &id_ex.operands
-                {
-                    let old_pc = id_ex.address;
-                    let mut flush = false;
-                    let mut new_pc = None;
-                    if (*r1_val as u32) < (*r2_val as u32) {
-                        new_pc = Some(old_pc.wrapping_add(*imm as usize));
-                        flush = true;
-                    }
-                    ExecuteResult {
-                        ex_mem: EXMEM {
-                            rd: None,
-                            calculation_result: imm << 12,
-                            memory_operation: None,
-                            operands: id_ex.operands.clone(),
-                        },
-                        flush,
-                        new_pc,
-                        trap_type: None,
-                    }
-                } else {
-                    unreachable!()
-                }
-            },
-        },
-    },
-    InstructionDefinition {
-        mask: MASK_BGEU,
-        match_val: MATCH_BGEU,
-        decode: |instruction, registers, address| IDEX {
-            operands: Some(extract_btype(instruction, registers)),
-            memory_operation: None,
-            address,
-            execute: |id_ex| {
-                if let Some(OperandsFormat::Btype {
-                    imm,
-                    r1_val,
-                    r2_val,
-                    ..
-                }) = &id_ex.operands
-                {
-                    let old_pc = id_ex.address;
-                    let mut flush = false;
-                    let mut new_pc = None;
-                    if (*r1_val as u32) >= (*r2_val as u32) {
-                        new_pc = Some(old_pc.wrapping_add(*imm as usize));
-                        flush = true;
-                    }
-                    ExecuteResult {
-                        ex_mem: EXMEM {
-                            rd: None,
-                            calculation_result: imm << 12,
-                            memory_operation: None,
-                            operands: id_ex.operands.clone(),
-                        },
-                        flush,
-                        new_pc,
-                        trap_type: None,
-                    }
-                } else {
-                    unreachable!()
-                }
-            },
-        },
-    },
-];
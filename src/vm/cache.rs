@@ -0,0 +1,262 @@
+// L1 caches that only time accesses, they track which lines are present and the bytes stay in the
+// memory of the VM.
+
+/// Which way of a full set a new line replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacementPolicy {
+    /// The line that was accessed the longest time ago.
+    LeastRecentlyUsed,
+    /// The line that was filled first.
+    FirstInFirstOut,
+    /// Any line of the set, from a fixed seed so runs repeat.
+    Random,
+}
+
+/// When stores reach memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// When a dirty line is evicted, which costs another miss penalty.
+    WriteBack,
+    /// Right away through a write buffer that never stalls, a store miss doesn't fill the line.
+    WriteThrough,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Capacity in bytes, a multiple of `line_size * associativity`. With 0 every access misses.
+    pub size: usize,
+    pub line_size: usize,
+    pub associativity: usize,
+    pub replacement: ReplacementPolicy,
+    pub write_policy: WritePolicy,
+    /// Cycles to fill a line or write a dirty one back.
+    pub miss_penalty: usize,
+}
+
+impl Default for CacheConfig {
+    /// 1 KiB of 16 byte lines, 2-way, write-back with a 10 cycle miss penalty.
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            line_size: 16,
+            associativity: 2,
+            replacement: ReplacementPolicy::LeastRecentlyUsed,
+            write_policy: WritePolicy::WriteBack,
+            miss_penalty: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheConfigError {
+    ZeroLineSize,
+    ZeroAssociativity,
+    /// `size` isn't a multiple of `line_size * associativity`.
+    UnevenSets,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Evicted dirty lines.
+    pub writebacks: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    number: usize, // the address divided by the line size
+    dirty: bool,
+    stamp: u64, // when the line was filled, or last accessed under LRU
+}
+
+pub struct Cache {
+    config: CacheConfig,
+    sets: Vec<Vec<Line>>,
+    clock: u64,
+    seed: u64, // xorshift state for random replacement
+    stats: CacheStats,
+}
+
+impl Cache {
+    pub fn new(config: CacheConfig) -> Result<Self, CacheConfigError> {
+        if config.line_size == 0 {
+            return Err(CacheConfigError::ZeroLineSize);
+        }
+        if config.associativity == 0 {
+            return Err(CacheConfigError::ZeroAssociativity);
+        }
+        let set_size = config.line_size * config.associativity;
+        if !config.size.is_multiple_of(set_size) {
+            return Err(CacheConfigError::UnevenSets);
+        }
+        Ok(Self {
+            config,
+            sets: vec![Vec::with_capacity(config.associativity); config.size / set_size],
+            clock: 0,
+            seed: 0x2545_f491_4f6c_dd1d,
+            stats: CacheStats::default(),
+        })
+    }
+
+    pub fn config(&self) -> CacheConfig {
+        self.config
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Looks up the `size` bytes at `address` and returns the cycles the access stalls for.
+    pub fn access(&mut self, address: usize, size: usize, write: bool) -> usize {
+        let first = address / self.config.line_size;
+        let last = (address + size.max(1) - 1) / self.config.line_size;
+        (first..=last).map(|number| self.access_line(number, write)).sum()
+    }
+
+    /// Drops every line without writing dirty ones back.
+    pub fn invalidate(&mut self) {
+        self.sets.iter_mut().for_each(Vec::clear);
+    }
+
+    fn access_line(&mut self, number: usize, write: bool) -> usize {
+        self.clock += 1;
+        let (clock, config) = (self.clock, self.config);
+        let write_back = config.write_policy == WritePolicy::WriteBack;
+        let count = self.sets.len();
+
+        if count > 0
+            && let Some(line) = self.sets[number % count].iter_mut().find(|x| x.number == number)
+        {
+            self.stats.hits += 1;
+            if config.replacement == ReplacementPolicy::LeastRecentlyUsed {
+                line.stamp = clock;
+            }
+            line.dirty |= write && write_back;
+            return 0;
+        }
+
+        self.stats.misses += 1;
+        if write && !write_back {
+            return 0;
+        }
+        if count == 0 {
+            return config.miss_penalty;
+        }
+        let line = Line {
+            number,
+            dirty: write,
+            stamp: clock,
+        };
+        let random = self.next_random() as usize;
+        let set = &mut self.sets[number % count];
+        if set.len() < config.associativity {
+            set.push(line);
+            return config.miss_penalty;
+        }
+        let victim = match config.replacement {
+            ReplacementPolicy::Random => &mut set[random % config.associativity],
+            _ => set.iter_mut().min_by_key(|x| x.stamp).unwrap(),
+        };
+        let evicted = std::mem::replace(victim, line);
+        if evicted.dirty {
+            self.stats.writebacks += 1;
+            return 2 * config.miss_penalty;
+        }
+        config.miss_penalty
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cache, CacheConfig, CacheConfigError, CacheStats, ReplacementPolicy, WritePolicy};
+
+    // 4 lines of 16 bytes in 2 sets with a miss penalty of 10
+    fn cache(replacement: ReplacementPolicy, write_policy: WritePolicy) -> Cache {
+        Cache::new(CacheConfig {
+            size: 64,
+            line_size: 16,
+            associativity: 2,
+            replacement,
+            write_policy,
+            miss_penalty: 10,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hits_and_misses() {
+        let mut cache = cache(ReplacementPolicy::LeastRecentlyUsed, WritePolicy::WriteBack);
+        assert_eq!(cache.access(0, 4, false), 10);
+        assert_eq!(cache.access(12, 4, false), 0);
+        // a word crossing into the next line misses on it
+        assert_eq!(cache.access(14, 4, false), 10);
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2, writebacks: 0 });
+        cache.invalidate();
+        assert_eq!(cache.access(0, 4, false), 10);
+    }
+
+    #[test]
+    fn test_replacement_policies() {
+        // lines 0, 2 and 4 share set 0, line 0 is filled first and accessed last
+        for (policy, survivor, evicted) in [
+            (ReplacementPolicy::LeastRecentlyUsed, 0, 32),
+            (ReplacementPolicy::FirstInFirstOut, 32, 0),
+        ] {
+            let mut cache = cache(policy, WritePolicy::WriteBack);
+            cache.access(0, 4, false);
+            cache.access(32, 4, false);
+            cache.access(0, 4, false);
+            cache.access(64, 4, false);
+            assert_eq!(cache.access(survivor, 4, false), 0, "{policy:?}");
+            assert_eq!(cache.access(evicted, 4, false), 10, "{policy:?}");
+        }
+
+        let mut cache = cache(ReplacementPolicy::Random, WritePolicy::WriteBack);
+        for line in 0..8 {
+            cache.access(line * 32, 4, false);
+        }
+        assert_eq!(cache.stats().misses, 8);
+        assert_eq!(cache.sets[0].len(), 2);
+    }
+
+    #[test]
+    fn test_write_policies() {
+        // the dirty line 0 is written back when line 4 evicts it
+        let mut write_back = cache(ReplacementPolicy::FirstInFirstOut, WritePolicy::WriteBack);
+        assert_eq!(write_back.access(0, 4, true), 10);
+        assert_eq!(write_back.access(32, 4, false), 10);
+        assert_eq!(write_back.access(64, 4, false), 20);
+        assert_eq!(write_back.stats(), CacheStats { hits: 0, misses: 3, writebacks: 1 });
+
+        // stores don't fill lines or stall, and nothing is ever written back
+        let mut write_through = cache(ReplacementPolicy::FirstInFirstOut, WritePolicy::WriteThrough);
+        assert_eq!(write_through.access(0, 4, true), 0);
+        assert_eq!(write_through.access(0, 4, false), 10);
+        assert_eq!(write_through.access(0, 4, true), 0);
+        assert_eq!(write_through.access(32, 4, false), 10);
+        assert_eq!(write_through.access(64, 4, false), 10);
+        assert_eq!(write_through.stats(), CacheStats { hits: 1, misses: 4, writebacks: 0 });
+    }
+
+    #[test]
+    fn test_invalid_configs() {
+        let config = CacheConfig { size: 48, ..CacheConfig::default() };
+        assert_eq!(Cache::new(config).err(), Some(CacheConfigError::UnevenSets));
+        let config = CacheConfig { line_size: 0, ..CacheConfig::default() };
+        assert_eq!(Cache::new(config).err(), Some(CacheConfigError::ZeroLineSize));
+        let config = CacheConfig { associativity: 0, ..CacheConfig::default() };
+        assert_eq!(Cache::new(config).err(), Some(CacheConfigError::ZeroAssociativity));
+
+        let mut cache = Cache::new(CacheConfig { size: 0, ..CacheConfig::default() }).unwrap();
+        assert_eq!(cache.access(0, 4, false), 10);
+        assert_eq!(cache.access(0, 4, false), 10);
+    }
+}
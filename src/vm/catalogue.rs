@@ -0,0 +1,847 @@
+use crate::inst::*;
+
+use super::{isa, profile::Extension};
+use DefinedBy::{Base, Other, Profile};
+
+/// The extension that defines an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinedBy {
+    /// The base integer isa.
+    Base,
+    /// An extension a rover's isa profile can have.
+    Profile(Extension),
+    /// An extension this VM doesn't implement, like `D` or `Zabha`.
+    Other(&'static str),
+}
+
+/// An instruction the VM can name, whether or not it can execute it. Used to tell players which
+/// instruction they ran into when it isn't available on their rover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownInstruction {
+    pub mnemonic: &'static str,
+    pub defined_by: DefinedBy,
+    /// Only RV64 has the encoding.
+    pub rv64: bool,
+}
+
+impl KnownInstruction {
+    /// The extension as the spec names it, like `F` or `Zbkb`. The RV64-only encodings of an
+    /// extension are named like `RV64M`, base instructions use `I`.
+    pub fn extension_name(&self) -> String {
+        let name = match self.defined_by {
+            Base => "I".to_string(),
+            Profile(extension) => {
+                let name = extension.name();
+                name[..1].to_uppercase() + &name[1..]
+            }
+            Other(name) => name.to_string(),
+        };
+        if self.rv64 { format!("RV64{name}") } else { name }
+    }
+}
+
+// an encoding from `inst.rs` that `isa::INSTRUCTIONS` has no row for
+struct Unimplemented {
+    known: KnownInstruction,
+    match_val: u32,
+    mask: u32,
+}
+
+const fn known(mnemonic: &'static str, defined_by: DefinedBy, match_val: u32, mask: u32) -> Unimplemented {
+    Unimplemented {
+        known: KnownInstruction { mnemonic, defined_by, rv64: false },
+        match_val,
+        mask,
+    }
+}
+
+const fn known_rv64(mnemonic: &'static str, defined_by: DefinedBy, match_val: u32, mask: u32) -> Unimplemented {
+    Unimplemented {
+        known: KnownInstruction { mnemonic, defined_by, rv64: true },
+        match_val,
+        mask,
+    }
+}
+
+/// Names `instruction`, from `isa::INSTRUCTIONS` when the VM implements it and from the encodings
+/// of `inst.rs` it doesn't otherwise. Some encodings are special cases of others, like `orc.b` of
+/// the draft `gorci`, so the match with the most fixed bits wins. Compressed instructions are
+/// matched on the lower half of the word. The all-zero and all-one words are illegal in every
+/// extension and are never named.
+pub fn identify(instruction: u32) -> Option<KnownInstruction> {
+    if matches!(instruction, 0x0000_0000 | 0xffff_ffff) {
+        return None;
+    }
+    let implemented = isa::INSTRUCTIONS.iter().filter(|x| x.matches(instruction)).map(|x| {
+        let defined_by = x.extension.map_or(Base, Profile);
+        (x.mask, KnownInstruction { mnemonic: x.mnemonic, defined_by, rv64: x.xlen == Some(64) })
+    });
+    let unimplemented = UNIMPLEMENTED
+        .iter()
+        .filter(|x| instruction & x.mask == x.match_val)
+        .map(|x| (x.mask, x.known));
+    implemented
+        .chain(unimplemented)
+        .rev() // max_by_key keeps the last of equal elements, ties go to the earlier row
+        .max_by_key(|(mask, _)| mask.count_ones())
+        .map(|(_, x)| x)
+}
+
+#[rustfmt::skip]
+const UNIMPLEMENTED: &[Unimplemented] = &[
+    known("amoadd.b", Other("Zabha"), MATCH_AMOADD_B, MASK_AMOADD_B),
+    known("amoadd.h", Other("Zabha"), MATCH_AMOADD_H, MASK_AMOADD_H),
+    known("amoand.b", Other("Zabha"), MATCH_AMOAND_B, MASK_AMOAND_B),
+    known("amoand.h", Other("Zabha"), MATCH_AMOAND_H, MASK_AMOAND_H),
+    known("amocas.b", Profile(Extension::Zacas), MATCH_AMOCAS_B, MASK_AMOCAS_B),
+    known("amocas.d", Profile(Extension::Zacas), MATCH_AMOCAS_D, MASK_AMOCAS_D),
+    known("amocas.h", Profile(Extension::Zacas), MATCH_AMOCAS_H, MASK_AMOCAS_H),
+    known("amocas.q", Profile(Extension::Zacas), MATCH_AMOCAS_Q, MASK_AMOCAS_Q),
+    known("amomax.b", Other("Zabha"), MATCH_AMOMAX_B, MASK_AMOMAX_B),
+    known("amomax.h", Other("Zabha"), MATCH_AMOMAX_H, MASK_AMOMAX_H),
+    known("amomaxu.b", Other("Zabha"), MATCH_AMOMAXU_B, MASK_AMOMAXU_B),
+    known("amomaxu.h", Other("Zabha"), MATCH_AMOMAXU_H, MASK_AMOMAXU_H),
+    known("amomin.b", Other("Zabha"), MATCH_AMOMIN_B, MASK_AMOMIN_B),
+    known("amomin.h", Other("Zabha"), MATCH_AMOMIN_H, MASK_AMOMIN_H),
+    known("amominu.b", Other("Zabha"), MATCH_AMOMINU_B, MASK_AMOMINU_B),
+    known("amominu.h", Other("Zabha"), MATCH_AMOMINU_H, MASK_AMOMINU_H),
+    known("amoor.b", Other("Zabha"), MATCH_AMOOR_B, MASK_AMOOR_B),
+    known("amoor.h", Other("Zabha"), MATCH_AMOOR_H, MASK_AMOOR_H),
+    known("amoswap.b", Other("Zabha"), MATCH_AMOSWAP_B, MASK_AMOSWAP_B),
+    known("amoswap.h", Other("Zabha"), MATCH_AMOSWAP_H, MASK_AMOSWAP_H),
+    known("amoxor.b", Other("Zabha"), MATCH_AMOXOR_B, MASK_AMOXOR_B),
+    known("amoxor.h", Other("Zabha"), MATCH_AMOXOR_H, MASK_AMOXOR_H),
+    known("c.add", Other("C"), MATCH_C_ADD, MASK_C_ADD),
+    known("c.addi", Other("C"), MATCH_C_ADDI, MASK_C_ADDI),
+    known("c.addi16sp", Other("C"), MATCH_C_ADDI16SP, MASK_C_ADDI16SP),
+    known("c.addi4spn", Other("C"), MATCH_C_ADDI4SPN, MASK_C_ADDI4SPN),
+    known("c.and", Other("C"), MATCH_C_AND, MASK_C_AND),
+    known("c.andi", Other("C"), MATCH_C_ANDI, MASK_C_ANDI),
+    known("c.beqz", Other("C"), MATCH_C_BEQZ, MASK_C_BEQZ),
+    known("c.bnez", Other("C"), MATCH_C_BNEZ, MASK_C_BNEZ),
+    known("c.ebreak", Other("C"), MATCH_C_EBREAK, MASK_C_EBREAK),
+    known("c.fld", Other("Zcd"), MATCH_C_FLD, MASK_C_FLD),
+    known("c.fldsp", Other("Zcd"), MATCH_C_FLDSP, MASK_C_FLDSP),
+    known("c.flw", Other("Zcf"), MATCH_C_FLW, MASK_C_FLW),
+    known("c.flwsp", Other("Zcf"), MATCH_C_FLWSP, MASK_C_FLWSP),
+    known("c.fsd", Other("Zcd"), MATCH_C_FSD, MASK_C_FSD),
+    known("c.fsdsp", Other("Zcd"), MATCH_C_FSDSP, MASK_C_FSDSP),
+    known("c.fsw", Other("Zcf"), MATCH_C_FSW, MASK_C_FSW),
+    known("c.fswsp", Other("Zcf"), MATCH_C_FSWSP, MASK_C_FSWSP),
+    known("c.j", Other("C"), MATCH_C_J, MASK_C_J),
+    known("c.jal", Other("C"), MATCH_C_JAL, MASK_C_JAL),
+    known("c.jalr", Other("C"), MATCH_C_JALR, MASK_C_JALR),
+    known("c.jr", Other("C"), MATCH_C_JR, MASK_C_JR),
+    known("c.lbu", Other("Zcb"), MATCH_C_LBU, MASK_C_LBU),
+    known("c.lh", Other("Zcb"), MATCH_C_LH, MASK_C_LH),
+    known("c.lhu", Other("Zcb"), MATCH_C_LHU, MASK_C_LHU),
+    known("c.li", Other("C"), MATCH_C_LI, MASK_C_LI),
+    known("c.lui", Other("C"), MATCH_C_LUI, MASK_C_LUI),
+    known("c.lw", Other("C"), MATCH_C_LW, MASK_C_LW),
+    known("c.lwsp", Other("C"), MATCH_C_LWSP, MASK_C_LWSP),
+    known("c.mop.n", Other("Zcmop"), MATCH_C_MOP_N, MASK_C_MOP_N),
+    known("c.mul", Other("Zcb"), MATCH_C_MUL, MASK_C_MUL),
+    known("c.mv", Other("C"), MATCH_C_MV, MASK_C_MV),
+    known("c.nop", Other("C"), MATCH_C_NOP, MASK_C_NOP),
+    known("c.not", Other("Zcb"), MATCH_C_NOT, MASK_C_NOT),
+    known("c.or", Other("C"), MATCH_C_OR, MASK_C_OR),
+    known("c.sb", Other("Zcb"), MATCH_C_SB, MASK_C_SB),
+    known("c.sext.b", Other("Zcb"), MATCH_C_SEXT_B, MASK_C_SEXT_B),
+    known("c.sext.h", Other("Zcb"), MATCH_C_SEXT_H, MASK_C_SEXT_H),
+    known("c.sh", Other("Zcb"), MATCH_C_SH, MASK_C_SH),
+    known("c.slli", Other("C"), MATCH_C_SLLI, MASK_C_SLLI),
+    known("c.srai", Other("C"), MATCH_C_SRAI, MASK_C_SRAI),
+    known("c.srli", Other("C"), MATCH_C_SRLI, MASK_C_SRLI),
+    known("c.sub", Other("C"), MATCH_C_SUB, MASK_C_SUB),
+    known("c.sw", Other("C"), MATCH_C_SW, MASK_C_SW),
+    known("c.swsp", Other("C"), MATCH_C_SWSP, MASK_C_SWSP),
+    known("c.xor", Other("C"), MATCH_C_XOR, MASK_C_XOR),
+    known("c.zext.b", Other("Zcb"), MATCH_C_ZEXT_B, MASK_C_ZEXT_B),
+    known("c.zext.h", Other("Zcb"), MATCH_C_ZEXT_H, MASK_C_ZEXT_H),
+    known("c.zext.w", Other("Zcb"), MATCH_C_ZEXT_W, MASK_C_ZEXT_W),
+    known("cbo.clean", Other("Zicbom"), MATCH_CBO_CLEAN, MASK_CBO_CLEAN),
+    known("cbo.flush", Other("Zicbom"), MATCH_CBO_FLUSH, MASK_CBO_FLUSH),
+    known("cbo.inval", Other("Zicbom"), MATCH_CBO_INVAL, MASK_CBO_INVAL),
+    known("cbo.zero", Other("Zicboz"), MATCH_CBO_ZERO, MASK_CBO_ZERO),
+    known("clmul", Other("Zbc"), MATCH_CLMUL, MASK_CLMUL),
+    known("clmulh", Other("Zbc"), MATCH_CLMULH, MASK_CLMULH),
+    known("clmulr", Other("Zbc"), MATCH_CLMULR, MASK_CLMULR),
+    known("cm.jalt", Other("Zcmt"), MATCH_CM_JALT, MASK_CM_JALT),
+    known("cm.mva01s", Other("Zcmp"), MATCH_CM_MVA01S, MASK_CM_MVA01S),
+    known("cm.mvsa01", Other("Zcmp"), MATCH_CM_MVSA01, MASK_CM_MVSA01),
+    known("cm.pop", Other("Zcmp"), MATCH_CM_POP, MASK_CM_POP),
+    known("cm.popret", Other("Zcmp"), MATCH_CM_POPRET, MASK_CM_POPRET),
+    known("cm.popretz", Other("Zcmp"), MATCH_CM_POPRETZ, MASK_CM_POPRETZ),
+    known("cm.push", Other("Zcmp"), MATCH_CM_PUSH, MASK_CM_PUSH),
+    known("dret", Other("Sdext"), MATCH_DRET, MASK_DRET),
+    known("fadd.d", Other("D"), MATCH_FADD_D, MASK_FADD_D),
+    known("fadd.h", Other("Zfh"), MATCH_FADD_H, MASK_FADD_H),
+    known("fadd.q", Other("Q"), MATCH_FADD_Q, MASK_FADD_Q),
+    known("fclass.d", Other("D"), MATCH_FCLASS_D, MASK_FCLASS_D),
+    known("fclass.h", Other("Zfh"), MATCH_FCLASS_H, MASK_FCLASS_H),
+    known("fclass.q", Other("Q"), MATCH_FCLASS_Q, MASK_FCLASS_Q),
+    known("fcvt.bf16.s", Other("Zfbfmin"), MATCH_FCVT_BF16_S, MASK_FCVT_BF16_S),
+    known("fcvt.d.h", Other("Zfh"), MATCH_FCVT_D_H, MASK_FCVT_D_H),
+    known("fcvt.d.q", Other("Q"), MATCH_FCVT_D_Q, MASK_FCVT_D_Q),
+    known("fcvt.d.s", Other("D"), MATCH_FCVT_D_S, MASK_FCVT_D_S),
+    known("fcvt.d.w", Other("D"), MATCH_FCVT_D_W, MASK_FCVT_D_W),
+    known("fcvt.d.wu", Other("D"), MATCH_FCVT_D_WU, MASK_FCVT_D_WU),
+    known("fcvt.h.d", Other("Zfh"), MATCH_FCVT_H_D, MASK_FCVT_H_D),
+    known("fcvt.h.q", Other("Zfh"), MATCH_FCVT_H_Q, MASK_FCVT_H_Q),
+    known("fcvt.h.s", Other("Zfh"), MATCH_FCVT_H_S, MASK_FCVT_H_S),
+    known("fcvt.h.w", Other("Zfh"), MATCH_FCVT_H_W, MASK_FCVT_H_W),
+    known("fcvt.h.wu", Other("Zfh"), MATCH_FCVT_H_WU, MASK_FCVT_H_WU),
+    known("fcvt.q.d", Other("Q"), MATCH_FCVT_Q_D, MASK_FCVT_Q_D),
+    known("fcvt.q.h", Other("Zfh"), MATCH_FCVT_Q_H, MASK_FCVT_Q_H),
+    known("fcvt.q.s", Other("Q"), MATCH_FCVT_Q_S, MASK_FCVT_Q_S),
+    known("fcvt.q.w", Other("Q"), MATCH_FCVT_Q_W, MASK_FCVT_Q_W),
+    known("fcvt.q.wu", Other("Q"), MATCH_FCVT_Q_WU, MASK_FCVT_Q_WU),
+    known("fcvt.s.bf16", Other("Zfbfmin"), MATCH_FCVT_S_BF16, MASK_FCVT_S_BF16),
+    known("fcvt.s.d", Other("D"), MATCH_FCVT_S_D, MASK_FCVT_S_D),
+    known("fcvt.s.h", Other("Zfh"), MATCH_FCVT_S_H, MASK_FCVT_S_H),
+    known("fcvt.s.q", Other("Q"), MATCH_FCVT_S_Q, MASK_FCVT_S_Q),
+    known("fcvt.w.d", Other("D"), MATCH_FCVT_W_D, MASK_FCVT_W_D),
+    known("fcvt.w.h", Other("Zfh"), MATCH_FCVT_W_H, MASK_FCVT_W_H),
+    known("fcvt.w.q", Other("Q"), MATCH_FCVT_W_Q, MASK_FCVT_W_Q),
+    known("fcvt.wu.d", Other("D"), MATCH_FCVT_WU_D, MASK_FCVT_WU_D),
+    known("fcvt.wu.h", Other("Zfh"), MATCH_FCVT_WU_H, MASK_FCVT_WU_H),
+    known("fcvt.wu.q", Other("Q"), MATCH_FCVT_WU_Q, MASK_FCVT_WU_Q),
+    known("fcvtmod.w.d", Other("Zfa"), MATCH_FCVTMOD_W_D, MASK_FCVTMOD_W_D),
+    known("fdiv.d", Other("D"), MATCH_FDIV_D, MASK_FDIV_D),
+    known("fdiv.h", Other("Zfh"), MATCH_FDIV_H, MASK_FDIV_H),
+    known("fdiv.q", Other("Q"), MATCH_FDIV_Q, MASK_FDIV_Q),
+    known("feq.d", Other("D"), MATCH_FEQ_D, MASK_FEQ_D),
+    known("feq.h", Other("Zfh"), MATCH_FEQ_H, MASK_FEQ_H),
+    known("feq.q", Other("Q"), MATCH_FEQ_Q, MASK_FEQ_Q),
+    known("fld", Other("D"), MATCH_FLD, MASK_FLD),
+    known("fle.d", Other("D"), MATCH_FLE_D, MASK_FLE_D),
+    known("fle.h", Other("Zfh"), MATCH_FLE_H, MASK_FLE_H),
+    known("fle.q", Other("Q"), MATCH_FLE_Q, MASK_FLE_Q),
+    known("fleq.d", Other("Zfa"), MATCH_FLEQ_D, MASK_FLEQ_D),
+    known("fleq.h", Other("Zfa"), MATCH_FLEQ_H, MASK_FLEQ_H),
+    known("fleq.q", Other("Zfa"), MATCH_FLEQ_Q, MASK_FLEQ_Q),
+    known("fleq.s", Other("Zfa"), MATCH_FLEQ_S, MASK_FLEQ_S),
+    known("flh", Other("Zfh"), MATCH_FLH, MASK_FLH),
+    known("fli.d", Other("Zfa"), MATCH_FLI_D, MASK_FLI_D),
+    known("fli.h", Other("Zfa"), MATCH_FLI_H, MASK_FLI_H),
+    known("fli.q", Other("Zfa"), MATCH_FLI_Q, MASK_FLI_Q),
+    known("fli.s", Other("Zfa"), MATCH_FLI_S, MASK_FLI_S),
+    known("flq", Other("Q"), MATCH_FLQ, MASK_FLQ),
+    known("flt.d", Other("D"), MATCH_FLT_D, MASK_FLT_D),
+    known("flt.h", Other("Zfh"), MATCH_FLT_H, MASK_FLT_H),
+    known("flt.q", Other("Q"), MATCH_FLT_Q, MASK_FLT_Q),
+    known("fltq.d", Other("Zfa"), MATCH_FLTQ_D, MASK_FLTQ_D),
+    known("fltq.h", Other("Zfa"), MATCH_FLTQ_H, MASK_FLTQ_H),
+    known("fltq.q", Other("Zfa"), MATCH_FLTQ_Q, MASK_FLTQ_Q),
+    known("fltq.s", Other("Zfa"), MATCH_FLTQ_S, MASK_FLTQ_S),
+    known("fmadd.d", Other("D"), MATCH_FMADD_D, MASK_FMADD_D),
+    known("fmadd.h", Other("Zfh"), MATCH_FMADD_H, MASK_FMADD_H),
+    known("fmadd.q", Other("Q"), MATCH_FMADD_Q, MASK_FMADD_Q),
+    known("fmax.d", Other("D"), MATCH_FMAX_D, MASK_FMAX_D),
+    known("fmax.h", Other("Zfh"), MATCH_FMAX_H, MASK_FMAX_H),
+    known("fmax.q", Other("Q"), MATCH_FMAX_Q, MASK_FMAX_Q),
+    known("fmaxm.d", Other("Zfa"), MATCH_FMAXM_D, MASK_FMAXM_D),
+    known("fmaxm.h", Other("Zfa"), MATCH_FMAXM_H, MASK_FMAXM_H),
+    known("fmaxm.q", Other("Zfa"), MATCH_FMAXM_Q, MASK_FMAXM_Q),
+    known("fmaxm.s", Other("Zfa"), MATCH_FMAXM_S, MASK_FMAXM_S),
+    known("fmin.d", Other("D"), MATCH_FMIN_D, MASK_FMIN_D),
+    known("fmin.h", Other("Zfh"), MATCH_FMIN_H, MASK_FMIN_H),
+    known("fmin.q", Other("Q"), MATCH_FMIN_Q, MASK_FMIN_Q),
+    known("fminm.d", Other("Zfa"), MATCH_FMINM_D, MASK_FMINM_D),
+    known("fminm.h", Other("Zfa"), MATCH_FMINM_H, MASK_FMINM_H),
+    known("fminm.q", Other("Zfa"), MATCH_FMINM_Q, MASK_FMINM_Q),
+    known("fminm.s", Other("Zfa"), MATCH_FMINM_S, MASK_FMINM_S),
+    known("fmsub.d", Other("D"), MATCH_FMSUB_D, MASK_FMSUB_D),
+    known("fmsub.h", Other("Zfh"), MATCH_FMSUB_H, MASK_FMSUB_H),
+    known("fmsub.q", Other("Q"), MATCH_FMSUB_Q, MASK_FMSUB_Q),
+    known("fmul.d", Other("D"), MATCH_FMUL_D, MASK_FMUL_D),
+    known("fmul.h", Other("Zfh"), MATCH_FMUL_H, MASK_FMUL_H),
+    known("fmul.q", Other("Q"), MATCH_FMUL_Q, MASK_FMUL_Q),
+    known("fmv.h.x", Other("Zfh"), MATCH_FMV_H_X, MASK_FMV_H_X),
+    known("fmv.x.h", Other("Zfh"), MATCH_FMV_X_H, MASK_FMV_X_H),
+    known("fmvh.x.d", Other("Zfa"), MATCH_FMVH_X_D, MASK_FMVH_X_D),
+    known("fmvh.x.q", Other("Zfa"), MATCH_FMVH_X_Q, MASK_FMVH_X_Q),
+    known("fmvp.d.x", Other("Zfa"), MATCH_FMVP_D_X, MASK_FMVP_D_X),
+    known("fmvp.q.x", Other("Zfa"), MATCH_FMVP_Q_X, MASK_FMVP_Q_X),
+    known("fnmadd.d", Other("D"), MATCH_FNMADD_D, MASK_FNMADD_D),
+    known("fnmadd.h", Other("Zfh"), MATCH_FNMADD_H, MASK_FNMADD_H),
+    known("fnmadd.q", Other("Q"), MATCH_FNMADD_Q, MASK_FNMADD_Q),
+    known("fnmsub.d", Other("D"), MATCH_FNMSUB_D, MASK_FNMSUB_D),
+    known("fnmsub.h", Other("Zfh"), MATCH_FNMSUB_H, MASK_FNMSUB_H),
+    known("fnmsub.q", Other("Q"), MATCH_FNMSUB_Q, MASK_FNMSUB_Q),
+    known("fround.d", Other("Zfa"), MATCH_FROUND_D, MASK_FROUND_D),
+    known("fround.h", Other("Zfa"), MATCH_FROUND_H, MASK_FROUND_H),
+    known("fround.q", Other("Zfa"), MATCH_FROUND_Q, MASK_FROUND_Q),
+    known("fround.s", Other("Zfa"), MATCH_FROUND_S, MASK_FROUND_S),
+    known("froundnx.d", Other("Zfa"), MATCH_FROUNDNX_D, MASK_FROUNDNX_D),
+    known("froundnx.h", Other("Zfa"), MATCH_FROUNDNX_H, MASK_FROUNDNX_H),
+    known("froundnx.q", Other("Zfa"), MATCH_FROUNDNX_Q, MASK_FROUNDNX_Q),
+    known("froundnx.s", Other("Zfa"), MATCH_FROUNDNX_S, MASK_FROUNDNX_S),
+    known("fsd", Other("D"), MATCH_FSD, MASK_FSD),
+    known("fsgnj.d", Other("D"), MATCH_FSGNJ_D, MASK_FSGNJ_D),
+    known("fsgnj.h", Other("Zfh"), MATCH_FSGNJ_H, MASK_FSGNJ_H),
+    known("fsgnj.q", Other("Q"), MATCH_FSGNJ_Q, MASK_FSGNJ_Q),
+    known("fsgnjn.d", Other("D"), MATCH_FSGNJN_D, MASK_FSGNJN_D),
+    known("fsgnjn.h", Other("Zfh"), MATCH_FSGNJN_H, MASK_FSGNJN_H),
+    known("fsgnjn.q", Other("Q"), MATCH_FSGNJN_Q, MASK_FSGNJN_Q),
+    known("fsgnjx.d", Other("D"), MATCH_FSGNJX_D, MASK_FSGNJX_D),
+    known("fsgnjx.h", Other("Zfh"), MATCH_FSGNJX_H, MASK_FSGNJX_H),
+    known("fsgnjx.q", Other("Q"), MATCH_FSGNJX_Q, MASK_FSGNJX_Q),
+    known("fsh", Other("Zfh"), MATCH_FSH, MASK_FSH),
+    known("fsq", Other("Q"), MATCH_FSQ, MASK_FSQ),
+    known("fsqrt.d", Other("D"), MATCH_FSQRT_D, MASK_FSQRT_D),
+    known("fsqrt.h", Other("Zfh"), MATCH_FSQRT_H, MASK_FSQRT_H),
+    known("fsqrt.q", Other("Q"), MATCH_FSQRT_Q, MASK_FSQRT_Q),
+    known("fsub.d", Other("D"), MATCH_FSUB_D, MASK_FSUB_D),
+    known("fsub.h", Other("Zfh"), MATCH_FSUB_H, MASK_FSUB_H),
+    known("fsub.q", Other("Q"), MATCH_FSUB_Q, MASK_FSUB_Q),
+    known("gorci", Other("Zbp"), MATCH_GORCI, MASK_GORCI),
+    known("grevi", Other("Zbp"), MATCH_GREVI, MASK_GREVI),
+    known("hfence.gvma", Other("H"), MATCH_HFENCE_GVMA, MASK_HFENCE_GVMA),
+    known("hfence.vvma", Other("H"), MATCH_HFENCE_VVMA, MASK_HFENCE_VVMA),
+    known("hinval.gvma", Other("Svinval"), MATCH_HINVAL_GVMA, MASK_HINVAL_GVMA),
+    known("hinval.vvma", Other("Svinval"), MATCH_HINVAL_VVMA, MASK_HINVAL_VVMA),
+    known("hlv.b", Other("H"), MATCH_HLV_B, MASK_HLV_B),
+    known("hlv.bu", Other("H"), MATCH_HLV_BU, MASK_HLV_BU),
+    known("hlv.d", Other("H"), MATCH_HLV_D, MASK_HLV_D),
+    known("hlv.h", Other("H"), MATCH_HLV_H, MASK_HLV_H),
+    known("hlv.hu", Other("H"), MATCH_HLV_HU, MASK_HLV_HU),
+    known("hlv.w", Other("H"), MATCH_HLV_W, MASK_HLV_W),
+    known("hlv.wu", Other("H"), MATCH_HLV_WU, MASK_HLV_WU),
+    known("hlvx.hu", Other("H"), MATCH_HLVX_HU, MASK_HLVX_HU),
+    known("hlvx.wu", Other("H"), MATCH_HLVX_WU, MASK_HLVX_WU),
+    known("hsv.b", Other("H"), MATCH_HSV_B, MASK_HSV_B),
+    known("hsv.d", Other("H"), MATCH_HSV_D, MASK_HSV_D),
+    known("hsv.h", Other("H"), MATCH_HSV_H, MASK_HSV_H),
+    known("hsv.w", Other("H"), MATCH_HSV_W, MASK_HSV_W),
+    known("lb.aq", Other("Zalasr"), MATCH_LB_AQ, MASK_LB_AQ),
+    known("ld.aq", Other("Zalasr"), MATCH_LD_AQ, MASK_LD_AQ),
+    known("lh.aq", Other("Zalasr"), MATCH_LH_AQ, MASK_LH_AQ),
+    known("lw.aq", Other("Zalasr"), MATCH_LW_AQ, MASK_LW_AQ),
+    known("mnret", Other("Smrnmi"), MATCH_MNRET, MASK_MNRET),
+    known("mop.r.n", Other("Zimop"), MATCH_MOP_R_N, MASK_MOP_R_N),
+    known("mop.rr.n", Other("Zimop"), MATCH_MOP_RR_N, MASK_MOP_RR_N),
+    known("mret", Other("Sm"), MATCH_MRET, MASK_MRET),
+    known("pack", Other("Zbkb"), MATCH_PACK, MASK_PACK),
+    known("packh", Other("Zbkb"), MATCH_PACKH, MASK_PACKH),
+    known("rori", Profile(Extension::Zbb), MATCH_RORI, MASK_RORI),
+    known("sb.rl", Other("Zalasr"), MATCH_SB_RL, MASK_SB_RL),
+    known("sctrclr", Other("Smctr"), MATCH_SCTRCLR, MASK_SCTRCLR),
+    known("sd.rl", Other("Zalasr"), MATCH_SD_RL, MASK_SD_RL),
+    known("sfence.inval.ir", Other("Svinval"), MATCH_SFENCE_INVAL_IR, MASK_SFENCE_INVAL_IR),
+    known("sfence.vma", Other("S"), MATCH_SFENCE_VMA, MASK_SFENCE_VMA),
+    known("sfence.w.inval", Other("Svinval"), MATCH_SFENCE_W_INVAL, MASK_SFENCE_W_INVAL),
+    known("sh.rl", Other("Zalasr"), MATCH_SH_RL, MASK_SH_RL),
+    known("shfli", Other("Zbp"), MATCH_SHFLI, MASK_SHFLI),
+    known("sinval.vma", Other("Svinval"), MATCH_SINVAL_VMA, MASK_SINVAL_VMA),
+    known("sm3p0", Other("Zksh"), MATCH_SM3P0, MASK_SM3P0),
+    known("sm3p1", Other("Zksh"), MATCH_SM3P1, MASK_SM3P1),
+    known("sm4ed", Other("Zksed"), MATCH_SM4ED, MASK_SM4ED),
+    known("sm4ks", Other("Zksed"), MATCH_SM4KS, MASK_SM4KS),
+    known("sret", Other("S"), MATCH_SRET, MASK_SRET),
+    known("ssamoswap.d", Other("Zicfiss"), MATCH_SSAMOSWAP_D, MASK_SSAMOSWAP_D),
+    known("ssamoswap.w", Other("Zicfiss"), MATCH_SSAMOSWAP_W, MASK_SSAMOSWAP_W),
+    known("sw.rl", Other("Zalasr"), MATCH_SW_RL, MASK_SW_RL),
+    known("unshfli", Other("Zbp"), MATCH_UNSHFLI, MASK_UNSHFLI),
+    known("vaadd.vv", Profile(Extension::V), MATCH_VAADD_VV, MASK_VAADD_VV),
+    known("vaadd.vx", Profile(Extension::V), MATCH_VAADD_VX, MASK_VAADD_VX),
+    known("vaaddu.vv", Profile(Extension::V), MATCH_VAADDU_VV, MASK_VAADDU_VV),
+    known("vaaddu.vx", Profile(Extension::V), MATCH_VAADDU_VX, MASK_VAADDU_VX),
+    known("vadc.vim", Profile(Extension::V), MATCH_VADC_VIM, MASK_VADC_VIM),
+    known("vadc.vvm", Profile(Extension::V), MATCH_VADC_VVM, MASK_VADC_VVM),
+    known("vadc.vxm", Profile(Extension::V), MATCH_VADC_VXM, MASK_VADC_VXM),
+    known("vaesdf.vs", Other("Zvkned"), MATCH_VAESDF_VS, MASK_VAESDF_VS),
+    known("vaesdf.vv", Other("Zvkned"), MATCH_VAESDF_VV, MASK_VAESDF_VV),
+    known("vaesdm.vs", Other("Zvkned"), MATCH_VAESDM_VS, MASK_VAESDM_VS),
+    known("vaesdm.vv", Other("Zvkned"), MATCH_VAESDM_VV, MASK_VAESDM_VV),
+    known("vaesef.vs", Other("Zvkned"), MATCH_VAESEF_VS, MASK_VAESEF_VS),
+    known("vaesef.vv", Other("Zvkned"), MATCH_VAESEF_VV, MASK_VAESEF_VV),
+    known("vaesem.vs", Other("Zvkned"), MATCH_VAESEM_VS, MASK_VAESEM_VS),
+    known("vaesem.vv", Other("Zvkned"), MATCH_VAESEM_VV, MASK_VAESEM_VV),
+    known("vaeskf1.vi", Other("Zvkned"), MATCH_VAESKF1_VI, MASK_VAESKF1_VI),
+    known("vaeskf2.vi", Other("Zvkned"), MATCH_VAESKF2_VI, MASK_VAESKF2_VI),
+    known("vaesz.vs", Other("Zvkned"), MATCH_VAESZ_VS, MASK_VAESZ_VS),
+    known("vand.vi", Profile(Extension::V), MATCH_VAND_VI, MASK_VAND_VI),
+    known("vand.vv", Profile(Extension::V), MATCH_VAND_VV, MASK_VAND_VV),
+    known("vand.vx", Profile(Extension::V), MATCH_VAND_VX, MASK_VAND_VX),
+    known("vandn.vv", Other("Zvkb"), MATCH_VANDN_VV, MASK_VANDN_VV),
+    known("vandn.vx", Other("Zvkb"), MATCH_VANDN_VX, MASK_VANDN_VX),
+    known("vasub.vv", Profile(Extension::V), MATCH_VASUB_VV, MASK_VASUB_VV),
+    known("vasub.vx", Profile(Extension::V), MATCH_VASUB_VX, MASK_VASUB_VX),
+    known("vasubu.vv", Profile(Extension::V), MATCH_VASUBU_VV, MASK_VASUBU_VV),
+    known("vasubu.vx", Profile(Extension::V), MATCH_VASUBU_VX, MASK_VASUBU_VX),
+    known("vbrev8.v", Other("Zvkb"), MATCH_VBREV8_V, MASK_VBREV8_V),
+    known("vbrev.v", Other("Zvbb"), MATCH_VBREV_V, MASK_VBREV_V),
+    known("vclmul.vv", Other("Zvbc"), MATCH_VCLMUL_VV, MASK_VCLMUL_VV),
+    known("vclmul.vx", Other("Zvbc"), MATCH_VCLMUL_VX, MASK_VCLMUL_VX),
+    known("vclmulh.vv", Other("Zvbc"), MATCH_VCLMULH_VV, MASK_VCLMULH_VV),
+    known("vclmulh.vx", Other("Zvbc"), MATCH_VCLMULH_VX, MASK_VCLMULH_VX),
+    known("vclz.v", Other("Zvbb"), MATCH_VCLZ_V, MASK_VCLZ_V),
+    known("vcompress.vm", Profile(Extension::V), MATCH_VCOMPRESS_VM, MASK_VCOMPRESS_VM),
+    known("vcpop.m", Profile(Extension::V), MATCH_VCPOP_M, MASK_VCPOP_M),
+    known("vcpop.v", Other("Zvbb"), MATCH_VCPOP_V, MASK_VCPOP_V),
+    known("vctz.v", Other("Zvbb"), MATCH_VCTZ_V, MASK_VCTZ_V),
+    known("vdiv.vv", Profile(Extension::V), MATCH_VDIV_VV, MASK_VDIV_VV),
+    known("vdiv.vx", Profile(Extension::V), MATCH_VDIV_VX, MASK_VDIV_VX),
+    known("vdivu.vv", Profile(Extension::V), MATCH_VDIVU_VV, MASK_VDIVU_VV),
+    known("vdivu.vx", Profile(Extension::V), MATCH_VDIVU_VX, MASK_VDIVU_VX),
+    known("vfadd.vf", Profile(Extension::V), MATCH_VFADD_VF, MASK_VFADD_VF),
+    known("vfadd.vv", Profile(Extension::V), MATCH_VFADD_VV, MASK_VFADD_VV),
+    known("vfclass.v", Profile(Extension::V), MATCH_VFCLASS_V, MASK_VFCLASS_V),
+    known("vfcvt.f.x.v", Profile(Extension::V), MATCH_VFCVT_F_X_V, MASK_VFCVT_F_X_V),
+    known("vfcvt.f.xu.v", Profile(Extension::V), MATCH_VFCVT_F_XU_V, MASK_VFCVT_F_XU_V),
+    known("vfcvt.rtz.x.f.v", Profile(Extension::V), MATCH_VFCVT_RTZ_X_F_V, MASK_VFCVT_RTZ_X_F_V),
+    known("vfcvt.rtz.xu.f.v", Profile(Extension::V), MATCH_VFCVT_RTZ_XU_F_V, MASK_VFCVT_RTZ_XU_F_V),
+    known("vfcvt.x.f.v", Profile(Extension::V), MATCH_VFCVT_X_F_V, MASK_VFCVT_X_F_V),
+    known("vfcvt.xu.f.v", Profile(Extension::V), MATCH_VFCVT_XU_F_V, MASK_VFCVT_XU_F_V),
+    known("vfdiv.vf", Profile(Extension::V), MATCH_VFDIV_VF, MASK_VFDIV_VF),
+    known("vfdiv.vv", Profile(Extension::V), MATCH_VFDIV_VV, MASK_VFDIV_VV),
+    known("vfirst.m", Profile(Extension::V), MATCH_VFIRST_M, MASK_VFIRST_M),
+    known("vfmacc.vf", Profile(Extension::V), MATCH_VFMACC_VF, MASK_VFMACC_VF),
+    known("vfmacc.vv", Profile(Extension::V), MATCH_VFMACC_VV, MASK_VFMACC_VV),
+    known("vfmadd.vf", Profile(Extension::V), MATCH_VFMADD_VF, MASK_VFMADD_VF),
+    known("vfmadd.vv", Profile(Extension::V), MATCH_VFMADD_VV, MASK_VFMADD_VV),
+    known("vfmax.vf", Profile(Extension::V), MATCH_VFMAX_VF, MASK_VFMAX_VF),
+    known("vfmax.vv", Profile(Extension::V), MATCH_VFMAX_VV, MASK_VFMAX_VV),
+    known("vfmerge.vfm", Profile(Extension::V), MATCH_VFMERGE_VFM, MASK_VFMERGE_VFM),
+    known("vfmin.vf", Profile(Extension::V), MATCH_VFMIN_VF, MASK_VFMIN_VF),
+    known("vfmin.vv", Profile(Extension::V), MATCH_VFMIN_VV, MASK_VFMIN_VV),
+    known("vfmsac.vf", Profile(Extension::V), MATCH_VFMSAC_VF, MASK_VFMSAC_VF),
+    known("vfmsac.vv", Profile(Extension::V), MATCH_VFMSAC_VV, MASK_VFMSAC_VV),
+    known("vfmsub.vf", Profile(Extension::V), MATCH_VFMSUB_VF, MASK_VFMSUB_VF),
+    known("vfmsub.vv", Profile(Extension::V), MATCH_VFMSUB_VV, MASK_VFMSUB_VV),
+    known("vfmul.vf", Profile(Extension::V), MATCH_VFMUL_VF, MASK_VFMUL_VF),
+    known("vfmul.vv", Profile(Extension::V), MATCH_VFMUL_VV, MASK_VFMUL_VV),
+    known("vfmv.f.s", Profile(Extension::V), MATCH_VFMV_F_S, MASK_VFMV_F_S),
+    known("vfmv.s.f", Profile(Extension::V), MATCH_VFMV_S_F, MASK_VFMV_S_F),
+    known("vfmv.v.f", Profile(Extension::V), MATCH_VFMV_V_F, MASK_VFMV_V_F),
+    known("vfncvt.f.f.w", Profile(Extension::V), MATCH_VFNCVT_F_F_W, MASK_VFNCVT_F_F_W),
+    known("vfncvt.f.x.w", Profile(Extension::V), MATCH_VFNCVT_F_X_W, MASK_VFNCVT_F_X_W),
+    known("vfncvt.f.xu.w", Profile(Extension::V), MATCH_VFNCVT_F_XU_W, MASK_VFNCVT_F_XU_W),
+    known("vfncvt.rod.f.f.w", Profile(Extension::V), MATCH_VFNCVT_ROD_F_F_W, MASK_VFNCVT_ROD_F_F_W),
+    known("vfncvt.rtz.x.f.w", Profile(Extension::V), MATCH_VFNCVT_RTZ_X_F_W, MASK_VFNCVT_RTZ_X_F_W),
+    known("vfncvt.rtz.xu.f.w", Profile(Extension::V), MATCH_VFNCVT_RTZ_XU_F_W, MASK_VFNCVT_RTZ_XU_F_W),
+    known("vfncvt.x.f.w", Profile(Extension::V), MATCH_VFNCVT_X_F_W, MASK_VFNCVT_X_F_W),
+    known("vfncvt.xu.f.w", Profile(Extension::V), MATCH_VFNCVT_XU_F_W, MASK_VFNCVT_XU_F_W),
+    known("vfncvtbf16.f.f.q", Other("Zvfbfmin"), MATCH_VFNCVTBF16_F_F_Q, MASK_VFNCVTBF16_F_F_Q),
+    known("vfncvtbf16.f.f.w", Other("Zvfbfmin"), MATCH_VFNCVTBF16_F_F_W, MASK_VFNCVTBF16_F_F_W),
+    known("vfncvtbf16.sat.f.f.q", Other("Zvfbfmin"), MATCH_VFNCVTBF16_SAT_F_F_Q, MASK_VFNCVTBF16_SAT_F_F_Q),
+    known("vfncvtbf16.sat.f.f.w", Other("Zvfbfmin"), MATCH_VFNCVTBF16_SAT_F_F_W, MASK_VFNCVTBF16_SAT_F_F_W),
+    known("vfnmacc.vf", Profile(Extension::V), MATCH_VFNMACC_VF, MASK_VFNMACC_VF),
+    known("vfnmacc.vv", Profile(Extension::V), MATCH_VFNMACC_VV, MASK_VFNMACC_VV),
+    known("vfnmadd.vf", Profile(Extension::V), MATCH_VFNMADD_VF, MASK_VFNMADD_VF),
+    known("vfnmadd.vv", Profile(Extension::V), MATCH_VFNMADD_VV, MASK_VFNMADD_VV),
+    known("vfnmsac.vf", Profile(Extension::V), MATCH_VFNMSAC_VF, MASK_VFNMSAC_VF),
+    known("vfnmsac.vv", Profile(Extension::V), MATCH_VFNMSAC_VV, MASK_VFNMSAC_VV),
+    known("vfnmsub.vf", Profile(Extension::V), MATCH_VFNMSUB_VF, MASK_VFNMSUB_VF),
+    known("vfnmsub.vv", Profile(Extension::V), MATCH_VFNMSUB_VV, MASK_VFNMSUB_VV),
+    known("vfrdiv.vf", Profile(Extension::V), MATCH_VFRDIV_VF, MASK_VFRDIV_VF),
+    known("vfrec7.v", Profile(Extension::V), MATCH_VFREC7_V, MASK_VFREC7_V),
+    known("vfredmax.vs", Profile(Extension::V), MATCH_VFREDMAX_VS, MASK_VFREDMAX_VS),
+    known("vfredmin.vs", Profile(Extension::V), MATCH_VFREDMIN_VS, MASK_VFREDMIN_VS),
+    known("vfredosum.vs", Profile(Extension::V), MATCH_VFREDOSUM_VS, MASK_VFREDOSUM_VS),
+    known("vfredusum.vs", Profile(Extension::V), MATCH_VFREDUSUM_VS, MASK_VFREDUSUM_VS),
+    known("vfrsqrt7.v", Profile(Extension::V), MATCH_VFRSQRT7_V, MASK_VFRSQRT7_V),
+    known("vfrsub.vf", Profile(Extension::V), MATCH_VFRSUB_VF, MASK_VFRSUB_VF),
+    known("vfsgnj.vf", Profile(Extension::V), MATCH_VFSGNJ_VF, MASK_VFSGNJ_VF),
+    known("vfsgnj.vv", Profile(Extension::V), MATCH_VFSGNJ_VV, MASK_VFSGNJ_VV),
+    known("vfsgnjn.vf", Profile(Extension::V), MATCH_VFSGNJN_VF, MASK_VFSGNJN_VF),
+    known("vfsgnjn.vv", Profile(Extension::V), MATCH_VFSGNJN_VV, MASK_VFSGNJN_VV),
+    known("vfsgnjx.vf", Profile(Extension::V), MATCH_VFSGNJX_VF, MASK_VFSGNJX_VF),
+    known("vfsgnjx.vv", Profile(Extension::V), MATCH_VFSGNJX_VV, MASK_VFSGNJX_VV),
+    known("vfslide1down.vf", Profile(Extension::V), MATCH_VFSLIDE1DOWN_VF, MASK_VFSLIDE1DOWN_VF),
+    known("vfslide1up.vf", Profile(Extension::V), MATCH_VFSLIDE1UP_VF, MASK_VFSLIDE1UP_VF),
+    known("vfsqrt.v", Profile(Extension::V), MATCH_VFSQRT_V, MASK_VFSQRT_V),
+    known("vfsub.vf", Profile(Extension::V), MATCH_VFSUB_VF, MASK_VFSUB_VF),
+    known("vfsub.vv", Profile(Extension::V), MATCH_VFSUB_VV, MASK_VFSUB_VV),
+    known("vfwadd.vf", Profile(Extension::V), MATCH_VFWADD_VF, MASK_VFWADD_VF),
+    known("vfwadd.vv", Profile(Extension::V), MATCH_VFWADD_VV, MASK_VFWADD_VV),
+    known("vfwadd.wf", Profile(Extension::V), MATCH_VFWADD_WF, MASK_VFWADD_WF),
+    known("vfwadd.wv", Profile(Extension::V), MATCH_VFWADD_WV, MASK_VFWADD_WV),
+    known("vfwcvt.f.f.v", Profile(Extension::V), MATCH_VFWCVT_F_F_V, MASK_VFWCVT_F_F_V),
+    known("vfwcvt.f.x.v", Profile(Extension::V), MATCH_VFWCVT_F_X_V, MASK_VFWCVT_F_X_V),
+    known("vfwcvt.f.xu.v", Profile(Extension::V), MATCH_VFWCVT_F_XU_V, MASK_VFWCVT_F_XU_V),
+    known("vfwcvt.rtz.x.f.v", Profile(Extension::V), MATCH_VFWCVT_RTZ_X_F_V, MASK_VFWCVT_RTZ_X_F_V),
+    known("vfwcvt.rtz.xu.f.v", Profile(Extension::V), MATCH_VFWCVT_RTZ_XU_F_V, MASK_VFWCVT_RTZ_XU_F_V),
+    known("vfwcvt.x.f.v", Profile(Extension::V), MATCH_VFWCVT_X_F_V, MASK_VFWCVT_X_F_V),
+    known("vfwcvt.xu.f.v", Profile(Extension::V), MATCH_VFWCVT_XU_F_V, MASK_VFWCVT_XU_F_V),
+    known("vfwcvtbf16.f.f.v", Other("Zvfbfmin"), MATCH_VFWCVTBF16_F_F_V, MASK_VFWCVTBF16_F_F_V),
+    known("vfwmacc.vf", Profile(Extension::V), MATCH_VFWMACC_VF, MASK_VFWMACC_VF),
+    known("vfwmacc.vv", Profile(Extension::V), MATCH_VFWMACC_VV, MASK_VFWMACC_VV),
+    known("vfwmaccbf16.vf", Other("Zvfbfwma"), MATCH_VFWMACCBF16_VF, MASK_VFWMACCBF16_VF),
+    known("vfwmaccbf16.vv", Other("Zvfbfwma"), MATCH_VFWMACCBF16_VV, MASK_VFWMACCBF16_VV),
+    known("vfwmsac.vf", Profile(Extension::V), MATCH_VFWMSAC_VF, MASK_VFWMSAC_VF),
+    known("vfwmsac.vv", Profile(Extension::V), MATCH_VFWMSAC_VV, MASK_VFWMSAC_VV),
+    known("vfwmul.vf", Profile(Extension::V), MATCH_VFWMUL_VF, MASK_VFWMUL_VF),
+    known("vfwmul.vv", Profile(Extension::V), MATCH_VFWMUL_VV, MASK_VFWMUL_VV),
+    known("vfwnmacc.vf", Profile(Extension::V), MATCH_VFWNMACC_VF, MASK_VFWNMACC_VF),
+    known("vfwnmacc.vv", Profile(Extension::V), MATCH_VFWNMACC_VV, MASK_VFWNMACC_VV),
+    known("vfwnmsac.vf", Profile(Extension::V), MATCH_VFWNMSAC_VF, MASK_VFWNMSAC_VF),
+    known("vfwnmsac.vv", Profile(Extension::V), MATCH_VFWNMSAC_VV, MASK_VFWNMSAC_VV),
+    known("vfwredosum.vs", Profile(Extension::V), MATCH_VFWREDOSUM_VS, MASK_VFWREDOSUM_VS),
+    known("vfwredusum.vs", Profile(Extension::V), MATCH_VFWREDUSUM_VS, MASK_VFWREDUSUM_VS),
+    known("vfwsub.vf", Profile(Extension::V), MATCH_VFWSUB_VF, MASK_VFWSUB_VF),
+    known("vfwsub.vv", Profile(Extension::V), MATCH_VFWSUB_VV, MASK_VFWSUB_VV),
+    known("vfwsub.wf", Profile(Extension::V), MATCH_VFWSUB_WF, MASK_VFWSUB_WF),
+    known("vfwsub.wv", Profile(Extension::V), MATCH_VFWSUB_WV, MASK_VFWSUB_WV),
+    known("vghsh.vv", Other("Zvkg"), MATCH_VGHSH_VV, MASK_VGHSH_VV),
+    known("vgmul.vv", Other("Zvkg"), MATCH_VGMUL_VV, MASK_VGMUL_VV),
+    known("vid.v", Profile(Extension::V), MATCH_VID_V, MASK_VID_V),
+    known("viota.m", Profile(Extension::V), MATCH_VIOTA_M, MASK_VIOTA_M),
+    known("vl1re16.v", Profile(Extension::V), MATCH_VL1RE16_V, MASK_VL1RE16_V),
+    known("vl1re32.v", Profile(Extension::V), MATCH_VL1RE32_V, MASK_VL1RE32_V),
+    known("vl1re64.v", Profile(Extension::V), MATCH_VL1RE64_V, MASK_VL1RE64_V),
+    known("vl1re8.v", Profile(Extension::V), MATCH_VL1RE8_V, MASK_VL1RE8_V),
+    known("vl2re16.v", Profile(Extension::V), MATCH_VL2RE16_V, MASK_VL2RE16_V),
+    known("vl2re32.v", Profile(Extension::V), MATCH_VL2RE32_V, MASK_VL2RE32_V),
+    known("vl2re64.v", Profile(Extension::V), MATCH_VL2RE64_V, MASK_VL2RE64_V),
+    known("vl2re8.v", Profile(Extension::V), MATCH_VL2RE8_V, MASK_VL2RE8_V),
+    known("vl4re16.v", Profile(Extension::V), MATCH_VL4RE16_V, MASK_VL4RE16_V),
+    known("vl4re32.v", Profile(Extension::V), MATCH_VL4RE32_V, MASK_VL4RE32_V),
+    known("vl4re64.v", Profile(Extension::V), MATCH_VL4RE64_V, MASK_VL4RE64_V),
+    known("vl4re8.v", Profile(Extension::V), MATCH_VL4RE8_V, MASK_VL4RE8_V),
+    known("vl8re16.v", Profile(Extension::V), MATCH_VL8RE16_V, MASK_VL8RE16_V),
+    known("vl8re32.v", Profile(Extension::V), MATCH_VL8RE32_V, MASK_VL8RE32_V),
+    known("vl8re64.v", Profile(Extension::V), MATCH_VL8RE64_V, MASK_VL8RE64_V),
+    known("vl8re8.v", Profile(Extension::V), MATCH_VL8RE8_V, MASK_VL8RE8_V),
+    known("vle16ff.v", Profile(Extension::V), MATCH_VLE16FF_V, MASK_VLE16FF_V),
+    known("vle32ff.v", Profile(Extension::V), MATCH_VLE32FF_V, MASK_VLE32FF_V),
+    known("vle64ff.v", Profile(Extension::V), MATCH_VLE64FF_V, MASK_VLE64FF_V),
+    known("vle8ff.v", Profile(Extension::V), MATCH_VLE8FF_V, MASK_VLE8FF_V),
+    known("vlm.v", Profile(Extension::V), MATCH_VLM_V, MASK_VLM_V),
+    known("vloxei16.v", Profile(Extension::V), MATCH_VLOXEI16_V, MASK_VLOXEI16_V),
+    known("vloxei32.v", Profile(Extension::V), MATCH_VLOXEI32_V, MASK_VLOXEI32_V),
+    known("vloxei64.v", Profile(Extension::V), MATCH_VLOXEI64_V, MASK_VLOXEI64_V),
+    known("vloxei8.v", Profile(Extension::V), MATCH_VLOXEI8_V, MASK_VLOXEI8_V),
+    known("vluxei16.v", Profile(Extension::V), MATCH_VLUXEI16_V, MASK_VLUXEI16_V),
+    known("vluxei32.v", Profile(Extension::V), MATCH_VLUXEI32_V, MASK_VLUXEI32_V),
+    known("vluxei64.v", Profile(Extension::V), MATCH_VLUXEI64_V, MASK_VLUXEI64_V),
+    known("vluxei8.v", Profile(Extension::V), MATCH_VLUXEI8_V, MASK_VLUXEI8_V),
+    known("vmacc.vv", Profile(Extension::V), MATCH_VMACC_VV, MASK_VMACC_VV),
+    known("vmacc.vx", Profile(Extension::V), MATCH_VMACC_VX, MASK_VMACC_VX),
+    known("vmadc.vi", Profile(Extension::V), MATCH_VMADC_VI, MASK_VMADC_VI),
+    known("vmadc.vim", Profile(Extension::V), MATCH_VMADC_VIM, MASK_VMADC_VIM),
+    known("vmadc.vv", Profile(Extension::V), MATCH_VMADC_VV, MASK_VMADC_VV),
+    known("vmadc.vvm", Profile(Extension::V), MATCH_VMADC_VVM, MASK_VMADC_VVM),
+    known("vmadc.vx", Profile(Extension::V), MATCH_VMADC_VX, MASK_VMADC_VX),
+    known("vmadc.vxm", Profile(Extension::V), MATCH_VMADC_VXM, MASK_VMADC_VXM),
+    known("vmadd.vv", Profile(Extension::V), MATCH_VMADD_VV, MASK_VMADD_VV),
+    known("vmadd.vx", Profile(Extension::V), MATCH_VMADD_VX, MASK_VMADD_VX),
+    known("vmand.mm", Profile(Extension::V), MATCH_VMAND_MM, MASK_VMAND_MM),
+    known("vmandn.mm", Profile(Extension::V), MATCH_VMANDN_MM, MASK_VMANDN_MM),
+    known("vmerge.vim", Profile(Extension::V), MATCH_VMERGE_VIM, MASK_VMERGE_VIM),
+    known("vmerge.vvm", Profile(Extension::V), MATCH_VMERGE_VVM, MASK_VMERGE_VVM),
+    known("vmerge.vxm", Profile(Extension::V), MATCH_VMERGE_VXM, MASK_VMERGE_VXM),
+    known("vmfeq.vf", Profile(Extension::V), MATCH_VMFEQ_VF, MASK_VMFEQ_VF),
+    known("vmfeq.vv", Profile(Extension::V), MATCH_VMFEQ_VV, MASK_VMFEQ_VV),
+    known("vmfge.vf", Profile(Extension::V), MATCH_VMFGE_VF, MASK_VMFGE_VF),
+    known("vmfgt.vf", Profile(Extension::V), MATCH_VMFGT_VF, MASK_VMFGT_VF),
+    known("vmfle.vf", Profile(Extension::V), MATCH_VMFLE_VF, MASK_VMFLE_VF),
+    known("vmfle.vv", Profile(Extension::V), MATCH_VMFLE_VV, MASK_VMFLE_VV),
+    known("vmflt.vf", Profile(Extension::V), MATCH_VMFLT_VF, MASK_VMFLT_VF),
+    known("vmflt.vv", Profile(Extension::V), MATCH_VMFLT_VV, MASK_VMFLT_VV),
+    known("vmfne.vf", Profile(Extension::V), MATCH_VMFNE_VF, MASK_VMFNE_VF),
+    known("vmfne.vv", Profile(Extension::V), MATCH_VMFNE_VV, MASK_VMFNE_VV),
+    known("vmnand.mm", Profile(Extension::V), MATCH_VMNAND_MM, MASK_VMNAND_MM),
+    known("vmnor.mm", Profile(Extension::V), MATCH_VMNOR_MM, MASK_VMNOR_MM),
+    known("vmor.mm", Profile(Extension::V), MATCH_VMOR_MM, MASK_VMOR_MM),
+    known("vmorn.mm", Profile(Extension::V), MATCH_VMORN_MM, MASK_VMORN_MM),
+    known("vmsbc.vv", Profile(Extension::V), MATCH_VMSBC_VV, MASK_VMSBC_VV),
+    known("vmsbc.vvm", Profile(Extension::V), MATCH_VMSBC_VVM, MASK_VMSBC_VVM),
+    known("vmsbc.vx", Profile(Extension::V), MATCH_VMSBC_VX, MASK_VMSBC_VX),
+    known("vmsbc.vxm", Profile(Extension::V), MATCH_VMSBC_VXM, MASK_VMSBC_VXM),
+    known("vmsbf.m", Profile(Extension::V), MATCH_VMSBF_M, MASK_VMSBF_M),
+    known("vmseq.vi", Profile(Extension::V), MATCH_VMSEQ_VI, MASK_VMSEQ_VI),
+    known("vmseq.vv", Profile(Extension::V), MATCH_VMSEQ_VV, MASK_VMSEQ_VV),
+    known("vmseq.vx", Profile(Extension::V), MATCH_VMSEQ_VX, MASK_VMSEQ_VX),
+    known("vmsgt.vi", Profile(Extension::V), MATCH_VMSGT_VI, MASK_VMSGT_VI),
+    known("vmsgt.vx", Profile(Extension::V), MATCH_VMSGT_VX, MASK_VMSGT_VX),
+    known("vmsgtu.vi", Profile(Extension::V), MATCH_VMSGTU_VI, MASK_VMSGTU_VI),
+    known("vmsgtu.vx", Profile(Extension::V), MATCH_VMSGTU_VX, MASK_VMSGTU_VX),
+    known("vmsif.m", Profile(Extension::V), MATCH_VMSIF_M, MASK_VMSIF_M),
+    known("vmsle.vi", Profile(Extension::V), MATCH_VMSLE_VI, MASK_VMSLE_VI),
+    known("vmsle.vv", Profile(Extension::V), MATCH_VMSLE_VV, MASK_VMSLE_VV),
+    known("vmsle.vx", Profile(Extension::V), MATCH_VMSLE_VX, MASK_VMSLE_VX),
+    known("vmsleu.vi", Profile(Extension::V), MATCH_VMSLEU_VI, MASK_VMSLEU_VI),
+    known("vmsleu.vv", Profile(Extension::V), MATCH_VMSLEU_VV, MASK_VMSLEU_VV),
+    known("vmsleu.vx", Profile(Extension::V), MATCH_VMSLEU_VX, MASK_VMSLEU_VX),
+    known("vmslt.vv", Profile(Extension::V), MATCH_VMSLT_VV, MASK_VMSLT_VV),
+    known("vmslt.vx", Profile(Extension::V), MATCH_VMSLT_VX, MASK_VMSLT_VX),
+    known("vmsltu.vv", Profile(Extension::V), MATCH_VMSLTU_VV, MASK_VMSLTU_VV),
+    known("vmsltu.vx", Profile(Extension::V), MATCH_VMSLTU_VX, MASK_VMSLTU_VX),
+    known("vmsne.vi", Profile(Extension::V), MATCH_VMSNE_VI, MASK_VMSNE_VI),
+    known("vmsne.vv", Profile(Extension::V), MATCH_VMSNE_VV, MASK_VMSNE_VV),
+    known("vmsne.vx", Profile(Extension::V), MATCH_VMSNE_VX, MASK_VMSNE_VX),
+    known("vmsof.m", Profile(Extension::V), MATCH_VMSOF_M, MASK_VMSOF_M),
+    known("vmulh.vv", Profile(Extension::V), MATCH_VMULH_VV, MASK_VMULH_VV),
+    known("vmulh.vx", Profile(Extension::V), MATCH_VMULH_VX, MASK_VMULH_VX),
+    known("vmulhsu.vv", Profile(Extension::V), MATCH_VMULHSU_VV, MASK_VMULHSU_VV),
+    known("vmulhsu.vx", Profile(Extension::V), MATCH_VMULHSU_VX, MASK_VMULHSU_VX),
+    known("vmulhu.vv", Profile(Extension::V), MATCH_VMULHU_VV, MASK_VMULHU_VV),
+    known("vmulhu.vx", Profile(Extension::V), MATCH_VMULHU_VX, MASK_VMULHU_VX),
+    known("vmv1r.v", Profile(Extension::V), MATCH_VMV1R_V, MASK_VMV1R_V),
+    known("vmv2r.v", Profile(Extension::V), MATCH_VMV2R_V, MASK_VMV2R_V),
+    known("vmv4r.v", Profile(Extension::V), MATCH_VMV4R_V, MASK_VMV4R_V),
+    known("vmv8r.v", Profile(Extension::V), MATCH_VMV8R_V, MASK_VMV8R_V),
+    known("vmv.s.x", Profile(Extension::V), MATCH_VMV_S_X, MASK_VMV_S_X),
+    known("vmv.v.i", Profile(Extension::V), MATCH_VMV_V_I, MASK_VMV_V_I),
+    known("vmv.v.v", Profile(Extension::V), MATCH_VMV_V_V, MASK_VMV_V_V),
+    known("vmv.v.x", Profile(Extension::V), MATCH_VMV_V_X, MASK_VMV_V_X),
+    known("vmv.x.s", Profile(Extension::V), MATCH_VMV_X_S, MASK_VMV_X_S),
+    known("vmxnor.mm", Profile(Extension::V), MATCH_VMXNOR_MM, MASK_VMXNOR_MM),
+    known("vmxor.mm", Profile(Extension::V), MATCH_VMXOR_MM, MASK_VMXOR_MM),
+    known("vnclip.wi", Profile(Extension::V), MATCH_VNCLIP_WI, MASK_VNCLIP_WI),
+    known("vnclip.wv", Profile(Extension::V), MATCH_VNCLIP_WV, MASK_VNCLIP_WV),
+    known("vnclip.wx", Profile(Extension::V), MATCH_VNCLIP_WX, MASK_VNCLIP_WX),
+    known("vnclipu.wi", Profile(Extension::V), MATCH_VNCLIPU_WI, MASK_VNCLIPU_WI),
+    known("vnclipu.wv", Profile(Extension::V), MATCH_VNCLIPU_WV, MASK_VNCLIPU_WV),
+    known("vnclipu.wx", Profile(Extension::V), MATCH_VNCLIPU_WX, MASK_VNCLIPU_WX),
+    known("vnmsac.vv", Profile(Extension::V), MATCH_VNMSAC_VV, MASK_VNMSAC_VV),
+    known("vnmsac.vx", Profile(Extension::V), MATCH_VNMSAC_VX, MASK_VNMSAC_VX),
+    known("vnmsub.vv", Profile(Extension::V), MATCH_VNMSUB_VV, MASK_VNMSUB_VV),
+    known("vnmsub.vx", Profile(Extension::V), MATCH_VNMSUB_VX, MASK_VNMSUB_VX),
+    known("vnsra.wi", Profile(Extension::V), MATCH_VNSRA_WI, MASK_VNSRA_WI),
+    known("vnsra.wv", Profile(Extension::V), MATCH_VNSRA_WV, MASK_VNSRA_WV),
+    known("vnsra.wx", Profile(Extension::V), MATCH_VNSRA_WX, MASK_VNSRA_WX),
+    known("vnsrl.wi", Profile(Extension::V), MATCH_VNSRL_WI, MASK_VNSRL_WI),
+    known("vnsrl.wv", Profile(Extension::V), MATCH_VNSRL_WV, MASK_VNSRL_WV),
+    known("vnsrl.wx", Profile(Extension::V), MATCH_VNSRL_WX, MASK_VNSRL_WX),
+    known("vor.vi", Profile(Extension::V), MATCH_VOR_VI, MASK_VOR_VI),
+    known("vor.vv", Profile(Extension::V), MATCH_VOR_VV, MASK_VOR_VV),
+    known("vor.vx", Profile(Extension::V), MATCH_VOR_VX, MASK_VOR_VX),
+    known("vqdot.vv", Other("Zvqdotq"), MATCH_VQDOT_VV, MASK_VQDOT_VV),
+    known("vqdot.vx", Other("Zvqdotq"), MATCH_VQDOT_VX, MASK_VQDOT_VX),
+    known("vqdotsu.vv", Other("Zvqdotq"), MATCH_VQDOTSU_VV, MASK_VQDOTSU_VV),
+    known("vqdotsu.vx", Other("Zvqdotq"), MATCH_VQDOTSU_VX, MASK_VQDOTSU_VX),
+    known("vqdotu.vv", Other("Zvqdotq"), MATCH_VQDOTU_VV, MASK_VQDOTU_VV),
+    known("vqdotu.vx", Other("Zvqdotq"), MATCH_VQDOTU_VX, MASK_VQDOTU_VX),
+    known("vqdotus.vx", Other("Zvqdotq"), MATCH_VQDOTUS_VX, MASK_VQDOTUS_VX),
+    known("vredand.vs", Profile(Extension::V), MATCH_VREDAND_VS, MASK_VREDAND_VS),
+    known("vredor.vs", Profile(Extension::V), MATCH_VREDOR_VS, MASK_VREDOR_VS),
+    known("vredxor.vs", Profile(Extension::V), MATCH_VREDXOR_VS, MASK_VREDXOR_VS),
+    known("vrem.vv", Profile(Extension::V), MATCH_VREM_VV, MASK_VREM_VV),
+    known("vrem.vx", Profile(Extension::V), MATCH_VREM_VX, MASK_VREM_VX),
+    known("vremu.vv", Profile(Extension::V), MATCH_VREMU_VV, MASK_VREMU_VV),
+    known("vremu.vx", Profile(Extension::V), MATCH_VREMU_VX, MASK_VREMU_VX),
+    known("vrev8.v", Other("Zvkb"), MATCH_VREV8_V, MASK_VREV8_V),
+    known("vrgather.vi", Profile(Extension::V), MATCH_VRGATHER_VI, MASK_VRGATHER_VI),
+    known("vrgather.vv", Profile(Extension::V), MATCH_VRGATHER_VV, MASK_VRGATHER_VV),
+    known("vrgather.vx", Profile(Extension::V), MATCH_VRGATHER_VX, MASK_VRGATHER_VX),
+    known("vrgatherei16.vv", Profile(Extension::V), MATCH_VRGATHEREI16_VV, MASK_VRGATHEREI16_VV),
+    known("vrol.vv", Other("Zvkb"), MATCH_VROL_VV, MASK_VROL_VV),
+    known("vrol.vx", Other("Zvkb"), MATCH_VROL_VX, MASK_VROL_VX),
+    known("vror.vi", Other("Zvkb"), MATCH_VROR_VI, MASK_VROR_VI),
+    known("vror.vv", Other("Zvkb"), MATCH_VROR_VV, MASK_VROR_VV),
+    known("vror.vx", Other("Zvkb"), MATCH_VROR_VX, MASK_VROR_VX),
+    known("vrsub.vi", Profile(Extension::V), MATCH_VRSUB_VI, MASK_VRSUB_VI),
+    known("vrsub.vx", Profile(Extension::V), MATCH_VRSUB_VX, MASK_VRSUB_VX),
+    known("vs1r.v", Profile(Extension::V), MATCH_VS1R_V, MASK_VS1R_V),
+    known("vs2r.v", Profile(Extension::V), MATCH_VS2R_V, MASK_VS2R_V),
+    known("vs4r.v", Profile(Extension::V), MATCH_VS4R_V, MASK_VS4R_V),
+    known("vs8r.v", Profile(Extension::V), MATCH_VS8R_V, MASK_VS8R_V),
+    known("vsadd.vi", Profile(Extension::V), MATCH_VSADD_VI, MASK_VSADD_VI),
+    known("vsadd.vv", Profile(Extension::V), MATCH_VSADD_VV, MASK_VSADD_VV),
+    known("vsadd.vx", Profile(Extension::V), MATCH_VSADD_VX, MASK_VSADD_VX),
+    known("vsaddu.vi", Profile(Extension::V), MATCH_VSADDU_VI, MASK_VSADDU_VI),
+    known("vsaddu.vv", Profile(Extension::V), MATCH_VSADDU_VV, MASK_VSADDU_VV),
+    known("vsaddu.vx", Profile(Extension::V), MATCH_VSADDU_VX, MASK_VSADDU_VX),
+    known("vsbc.vvm", Profile(Extension::V), MATCH_VSBC_VVM, MASK_VSBC_VVM),
+    known("vsbc.vxm", Profile(Extension::V), MATCH_VSBC_VXM, MASK_VSBC_VXM),
+    known("vsetivli", Profile(Extension::V), MATCH_VSETIVLI, MASK_VSETIVLI),
+    known("vsetvl", Profile(Extension::V), MATCH_VSETVL, MASK_VSETVL),
+    known("vsext.vf2", Profile(Extension::V), MATCH_VSEXT_VF2, MASK_VSEXT_VF2),
+    known("vsext.vf4", Profile(Extension::V), MATCH_VSEXT_VF4, MASK_VSEXT_VF4),
+    known("vsext.vf8", Profile(Extension::V), MATCH_VSEXT_VF8, MASK_VSEXT_VF8),
+    known("vsha2ch.vv", Other("Zvknh"), MATCH_VSHA2CH_VV, MASK_VSHA2CH_VV),
+    known("vsha2cl.vv", Other("Zvknh"), MATCH_VSHA2CL_VV, MASK_VSHA2CL_VV),
+    known("vsha2ms.vv", Other("Zvknh"), MATCH_VSHA2MS_VV, MASK_VSHA2MS_VV),
+    known("vslide1down.vx", Profile(Extension::V), MATCH_VSLIDE1DOWN_VX, MASK_VSLIDE1DOWN_VX),
+    known("vslide1up.vx", Profile(Extension::V), MATCH_VSLIDE1UP_VX, MASK_VSLIDE1UP_VX),
+    known("vslidedown.vi", Profile(Extension::V), MATCH_VSLIDEDOWN_VI, MASK_VSLIDEDOWN_VI),
+    known("vslidedown.vx", Profile(Extension::V), MATCH_VSLIDEDOWN_VX, MASK_VSLIDEDOWN_VX),
+    known("vslideup.vi", Profile(Extension::V), MATCH_VSLIDEUP_VI, MASK_VSLIDEUP_VI),
+    known("vslideup.vx", Profile(Extension::V), MATCH_VSLIDEUP_VX, MASK_VSLIDEUP_VX),
+    known("vsll.vi", Profile(Extension::V), MATCH_VSLL_VI, MASK_VSLL_VI),
+    known("vsll.vv", Profile(Extension::V), MATCH_VSLL_VV, MASK_VSLL_VV),
+    known("vsll.vx", Profile(Extension::V), MATCH_VSLL_VX, MASK_VSLL_VX),
+    known("vsm3c.vi", Other("Zvksh"), MATCH_VSM3C_VI, MASK_VSM3C_VI),
+    known("vsm3me.vv", Other("Zvksh"), MATCH_VSM3ME_VV, MASK_VSM3ME_VV),
+    known("vsm4k.vi", Other("Zvksed"), MATCH_VSM4K_VI, MASK_VSM4K_VI),
+    known("vsm4r.vs", Other("Zvksed"), MATCH_VSM4R_VS, MASK_VSM4R_VS),
+    known("vsm4r.vv", Other("Zvksed"), MATCH_VSM4R_VV, MASK_VSM4R_VV),
+    known("vsm.v", Profile(Extension::V), MATCH_VSM_V, MASK_VSM_V),
+    known("vsmul.vv", Profile(Extension::V), MATCH_VSMUL_VV, MASK_VSMUL_VV),
+    known("vsmul.vx", Profile(Extension::V), MATCH_VSMUL_VX, MASK_VSMUL_VX),
+    known("vsoxei16.v", Profile(Extension::V), MATCH_VSOXEI16_V, MASK_VSOXEI16_V),
+    known("vsoxei32.v", Profile(Extension::V), MATCH_VSOXEI32_V, MASK_VSOXEI32_V),
+    known("vsoxei64.v", Profile(Extension::V), MATCH_VSOXEI64_V, MASK_VSOXEI64_V),
+    known("vsoxei8.v", Profile(Extension::V), MATCH_VSOXEI8_V, MASK_VSOXEI8_V),
+    known("vsra.vi", Profile(Extension::V), MATCH_VSRA_VI, MASK_VSRA_VI),
+    known("vsra.vv", Profile(Extension::V), MATCH_VSRA_VV, MASK_VSRA_VV),
+    known("vsra.vx", Profile(Extension::V), MATCH_VSRA_VX, MASK_VSRA_VX),
+    known("vsrl.vi", Profile(Extension::V), MATCH_VSRL_VI, MASK_VSRL_VI),
+    known("vsrl.vv", Profile(Extension::V), MATCH_VSRL_VV, MASK_VSRL_VV),
+    known("vsrl.vx", Profile(Extension::V), MATCH_VSRL_VX, MASK_VSRL_VX),
+    known("vssra.vi", Profile(Extension::V), MATCH_VSSRA_VI, MASK_VSSRA_VI),
+    known("vssra.vv", Profile(Extension::V), MATCH_VSSRA_VV, MASK_VSSRA_VV),
+    known("vssra.vx", Profile(Extension::V), MATCH_VSSRA_VX, MASK_VSSRA_VX),
+    known("vssrl.vi", Profile(Extension::V), MATCH_VSSRL_VI, MASK_VSSRL_VI),
+    known("vssrl.vv", Profile(Extension::V), MATCH_VSSRL_VV, MASK_VSSRL_VV),
+    known("vssrl.vx", Profile(Extension::V), MATCH_VSSRL_VX, MASK_VSSRL_VX),
+    known("vssub.vv", Profile(Extension::V), MATCH_VSSUB_VV, MASK_VSSUB_VV),
+    known("vssub.vx", Profile(Extension::V), MATCH_VSSUB_VX, MASK_VSSUB_VX),
+    known("vssubu.vv", Profile(Extension::V), MATCH_VSSUBU_VV, MASK_VSSUBU_VV),
+    known("vssubu.vx", Profile(Extension::V), MATCH_VSSUBU_VX, MASK_VSSUBU_VX),
+    known("vsub.vv", Profile(Extension::V), MATCH_VSUB_VV, MASK_VSUB_VV),
+    known("vsub.vx", Profile(Extension::V), MATCH_VSUB_VX, MASK_VSUB_VX),
+    known("vsuxei16.v", Profile(Extension::V), MATCH_VSUXEI16_V, MASK_VSUXEI16_V),
+    known("vsuxei32.v", Profile(Extension::V), MATCH_VSUXEI32_V, MASK_VSUXEI32_V),
+    known("vsuxei64.v", Profile(Extension::V), MATCH_VSUXEI64_V, MASK_VSUXEI64_V),
+    known("vsuxei8.v", Profile(Extension::V), MATCH_VSUXEI8_V, MASK_VSUXEI8_V),
+    known("vwadd.vv", Profile(Extension::V), MATCH_VWADD_VV, MASK_VWADD_VV),
+    known("vwadd.vx", Profile(Extension::V), MATCH_VWADD_VX, MASK_VWADD_VX),
+    known("vwadd.wv", Profile(Extension::V), MATCH_VWADD_WV, MASK_VWADD_WV),
+    known("vwadd.wx", Profile(Extension::V), MATCH_VWADD_WX, MASK_VWADD_WX),
+    known("vwaddu.vv", Profile(Extension::V), MATCH_VWADDU_VV, MASK_VWADDU_VV),
+    known("vwaddu.vx", Profile(Extension::V), MATCH_VWADDU_VX, MASK_VWADDU_VX),
+    known("vwaddu.wv", Profile(Extension::V), MATCH_VWADDU_WV, MASK_VWADDU_WV),
+    known("vwaddu.wx", Profile(Extension::V), MATCH_VWADDU_WX, MASK_VWADDU_WX),
+    known("vwmacc.vv", Profile(Extension::V), MATCH_VWMACC_VV, MASK_VWMACC_VV),
+    known("vwmacc.vx", Profile(Extension::V), MATCH_VWMACC_VX, MASK_VWMACC_VX),
+    known("vwmaccsu.vv", Profile(Extension::V), MATCH_VWMACCSU_VV, MASK_VWMACCSU_VV),
+    known("vwmaccsu.vx", Profile(Extension::V), MATCH_VWMACCSU_VX, MASK_VWMACCSU_VX),
+    known("vwmaccu.vv", Profile(Extension::V), MATCH_VWMACCU_VV, MASK_VWMACCU_VV),
+    known("vwmaccu.vx", Profile(Extension::V), MATCH_VWMACCU_VX, MASK_VWMACCU_VX),
+    known("vwmaccus.vx", Profile(Extension::V), MATCH_VWMACCUS_VX, MASK_VWMACCUS_VX),
+    known("vwmul.vv", Profile(Extension::V), MATCH_VWMUL_VV, MASK_VWMUL_VV),
+    known("vwmul.vx", Profile(Extension::V), MATCH_VWMUL_VX, MASK_VWMUL_VX),
+    known("vwmulsu.vv", Profile(Extension::V), MATCH_VWMULSU_VV, MASK_VWMULSU_VV),
+    known("vwmulsu.vx", Profile(Extension::V), MATCH_VWMULSU_VX, MASK_VWMULSU_VX),
+    known("vwmulu.vv", Profile(Extension::V), MATCH_VWMULU_VV, MASK_VWMULU_VV),
+    known("vwmulu.vx", Profile(Extension::V), MATCH_VWMULU_VX, MASK_VWMULU_VX),
+    known("vwredsum.vs", Profile(Extension::V), MATCH_VWREDSUM_VS, MASK_VWREDSUM_VS),
+    known("vwredsumu.vs", Profile(Extension::V), MATCH_VWREDSUMU_VS, MASK_VWREDSUMU_VS),
+    known("vwsll.vi", Other("Zvbb"), MATCH_VWSLL_VI, MASK_VWSLL_VI),
+    known("vwsll.vv", Other("Zvbb"), MATCH_VWSLL_VV, MASK_VWSLL_VV),
+    known("vwsll.vx", Other("Zvbb"), MATCH_VWSLL_VX, MASK_VWSLL_VX),
+    known("vwsub.vv", Profile(Extension::V), MATCH_VWSUB_VV, MASK_VWSUB_VV),
+    known("vwsub.vx", Profile(Extension::V), MATCH_VWSUB_VX, MASK_VWSUB_VX),
+    known("vwsub.wv", Profile(Extension::V), MATCH_VWSUB_WV, MASK_VWSUB_WV),
+    known("vwsub.wx", Profile(Extension::V), MATCH_VWSUB_WX, MASK_VWSUB_WX),
+    known("vwsubu.vv", Profile(Extension::V), MATCH_VWSUBU_VV, MASK_VWSUBU_VV),
+    known("vwsubu.vx", Profile(Extension::V), MATCH_VWSUBU_VX, MASK_VWSUBU_VX),
+    known("vwsubu.wv", Profile(Extension::V), MATCH_VWSUBU_WV, MASK_VWSUBU_WV),
+    known("vwsubu.wx", Profile(Extension::V), MATCH_VWSUBU_WX, MASK_VWSUBU_WX),
+    known("vxor.vi", Profile(Extension::V), MATCH_VXOR_VI, MASK_VXOR_VI),
+    known("vxor.vv", Profile(Extension::V), MATCH_VXOR_VV, MASK_VXOR_VV),
+    known("vxor.vx", Profile(Extension::V), MATCH_VXOR_VX, MASK_VXOR_VX),
+    known("vzext.vf2", Profile(Extension::V), MATCH_VZEXT_VF2, MASK_VZEXT_VF2),
+    known("vzext.vf4", Profile(Extension::V), MATCH_VZEXT_VF4, MASK_VZEXT_VF4),
+    known("vzext.vf8", Profile(Extension::V), MATCH_VZEXT_VF8, MASK_VZEXT_VF8),
+    known("wrs.nto", Other("Zawrs"), MATCH_WRS_NTO, MASK_WRS_NTO),
+    known("wrs.sto", Other("Zawrs"), MATCH_WRS_STO, MASK_WRS_STO),
+    known("xperm16", Other("Zbp"), MATCH_XPERM16, MASK_XPERM16),
+    known("xperm32", Other("Zbp"), MATCH_XPERM32, MASK_XPERM32),
+    known("xperm4", Other("Zbkx"), MATCH_XPERM4, MASK_XPERM4),
+    known("xperm8", Other("Zbkx"), MATCH_XPERM8, MASK_XPERM8),
+
+    // RV64 only, listed last so the RV32 meaning of a reused encoding wins
+    known_rv64("add.uw", Profile(Extension::Zba), MATCH_ADD_UW, MASK_ADD_UW),
+    known_rv64("aes64ds", Profile(Extension::Zknd), MATCH_AES64DS, MASK_AES64DS),
+    known_rv64("aes64dsm", Profile(Extension::Zknd), MATCH_AES64DSM, MASK_AES64DSM),
+    known_rv64("aes64es", Profile(Extension::Zkne), MATCH_AES64ES, MASK_AES64ES),
+    known_rv64("aes64esm", Profile(Extension::Zkne), MATCH_AES64ESM, MASK_AES64ESM),
+    known_rv64("aes64im", Profile(Extension::Zknd), MATCH_AES64IM, MASK_AES64IM),
+    known_rv64("aes64ks1i", Profile(Extension::Zkne), MATCH_AES64KS1I, MASK_AES64KS1I),
+    known_rv64("aes64ks2", Profile(Extension::Zkne), MATCH_AES64KS2, MASK_AES64KS2),
+    known_rv64("amoadd.d", Profile(Extension::A), MATCH_AMOADD_D, MASK_AMOADD_D),
+    known_rv64("amoand.d", Profile(Extension::A), MATCH_AMOAND_D, MASK_AMOAND_D),
+    known_rv64("amomax.d", Profile(Extension::A), MATCH_AMOMAX_D, MASK_AMOMAX_D),
+    known_rv64("amomaxu.d", Profile(Extension::A), MATCH_AMOMAXU_D, MASK_AMOMAXU_D),
+    known_rv64("amomin.d", Profile(Extension::A), MATCH_AMOMIN_D, MASK_AMOMIN_D),
+    known_rv64("amominu.d", Profile(Extension::A), MATCH_AMOMINU_D, MASK_AMOMINU_D),
+    known_rv64("amoor.d", Profile(Extension::A), MATCH_AMOOR_D, MASK_AMOOR_D),
+    known_rv64("amoswap.d", Profile(Extension::A), MATCH_AMOSWAP_D, MASK_AMOSWAP_D),
+    known_rv64("amoxor.d", Profile(Extension::A), MATCH_AMOXOR_D, MASK_AMOXOR_D),
+    known_rv64("bclri", Profile(Extension::Zbs), MATCH_BCLRI, MASK_BCLRI),
+    known_rv64("bexti", Profile(Extension::Zbs), MATCH_BEXTI, MASK_BEXTI),
+    known_rv64("binvi", Profile(Extension::Zbs), MATCH_BINVI, MASK_BINVI),
+    known_rv64("bseti", Profile(Extension::Zbs), MATCH_BSETI, MASK_BSETI),
+    known_rv64("c.addiw", Other("C"), MATCH_C_ADDIW, MASK_C_ADDIW),
+    known_rv64("c.addw", Other("C"), MATCH_C_ADDW, MASK_C_ADDW),
+    known_rv64("c.ld", Other("C"), MATCH_C_LD, MASK_C_LD),
+    known_rv64("c.ldsp", Other("C"), MATCH_C_LDSP, MASK_C_LDSP),
+    known_rv64("c.sd", Other("C"), MATCH_C_SD, MASK_C_SD),
+    known_rv64("c.sdsp", Other("C"), MATCH_C_SDSP, MASK_C_SDSP),
+    known_rv64("c.subw", Other("C"), MATCH_C_SUBW, MASK_C_SUBW),
+    known_rv64("clzw", Profile(Extension::Zbb), MATCH_CLZW, MASK_CLZW),
+    known_rv64("cpopw", Profile(Extension::Zbb), MATCH_CPOPW, MASK_CPOPW),
+    known_rv64("ctzw", Profile(Extension::Zbb), MATCH_CTZW, MASK_CTZW),
+    known_rv64("fcvt.d.l", Other("D"), MATCH_FCVT_D_L, MASK_FCVT_D_L),
+    known_rv64("fcvt.d.lu", Other("D"), MATCH_FCVT_D_LU, MASK_FCVT_D_LU),
+    known_rv64("fcvt.h.l", Other("Zfh"), MATCH_FCVT_H_L, MASK_FCVT_H_L),
+    known_rv64("fcvt.h.lu", Other("Zfh"), MATCH_FCVT_H_LU, MASK_FCVT_H_LU),
+    known_rv64("fcvt.l.d", Other("D"), MATCH_FCVT_L_D, MASK_FCVT_L_D),
+    known_rv64("fcvt.l.h", Other("Zfh"), MATCH_FCVT_L_H, MASK_FCVT_L_H),
+    known_rv64("fcvt.l.q", Other("Q"), MATCH_FCVT_L_Q, MASK_FCVT_L_Q),
+    known_rv64("fcvt.l.s", Profile(Extension::F), MATCH_FCVT_L_S, MASK_FCVT_L_S),
+    known_rv64("fcvt.lu.d", Other("D"), MATCH_FCVT_LU_D, MASK_FCVT_LU_D),
+    known_rv64("fcvt.lu.h", Other("Zfh"), MATCH_FCVT_LU_H, MASK_FCVT_LU_H),
+    known_rv64("fcvt.lu.q", Other("Q"), MATCH_FCVT_LU_Q, MASK_FCVT_LU_Q),
+    known_rv64("fcvt.lu.s", Profile(Extension::F), MATCH_FCVT_LU_S, MASK_FCVT_LU_S),
+    known_rv64("fcvt.q.l", Other("Q"), MATCH_FCVT_Q_L, MASK_FCVT_Q_L),
+    known_rv64("fcvt.q.lu", Other("Q"), MATCH_FCVT_Q_LU, MASK_FCVT_Q_LU),
+    known_rv64("fcvt.s.l", Profile(Extension::F), MATCH_FCVT_S_L, MASK_FCVT_S_L),
+    known_rv64("fcvt.s.lu", Profile(Extension::F), MATCH_FCVT_S_LU, MASK_FCVT_S_LU),
+    known_rv64("fmv.d.x", Other("D"), MATCH_FMV_D_X, MASK_FMV_D_X),
+    known_rv64("fmv.x.d", Other("D"), MATCH_FMV_X_D, MASK_FMV_X_D),
+    known_rv64("lr.d", Profile(Extension::A), MATCH_LR_D, MASK_LR_D),
+    known_rv64("packw", Other("Zbkb"), MATCH_PACKW, MASK_PACKW),
+    known_rv64("rolw", Profile(Extension::Zbb), MATCH_ROLW, MASK_ROLW),
+    known_rv64("roriw", Profile(Extension::Zbb), MATCH_RORIW, MASK_RORIW),
+    known_rv64("rorw", Profile(Extension::Zbb), MATCH_RORW, MASK_RORW),
+    known_rv64("sc.d", Profile(Extension::A), MATCH_SC_D, MASK_SC_D),
+    known_rv64("sh1add.uw", Profile(Extension::Zba), MATCH_SH1ADD_UW, MASK_SH1ADD_UW),
+    known_rv64("sh2add.uw", Profile(Extension::Zba), MATCH_SH2ADD_UW, MASK_SH2ADD_UW),
+    known_rv64("sh3add.uw", Profile(Extension::Zba), MATCH_SH3ADD_UW, MASK_SH3ADD_UW),
+    known_rv64("sha512sig0", Profile(Extension::Zknh), MATCH_SHA512SIG0, MASK_SHA512SIG0),
+    known_rv64("sha512sig1", Profile(Extension::Zknh), MATCH_SHA512SIG1, MASK_SHA512SIG1),
+    known_rv64("sha512sum0", Profile(Extension::Zknh), MATCH_SHA512SUM0, MASK_SHA512SUM0),
+    known_rv64("sha512sum1", Profile(Extension::Zknh), MATCH_SHA512SUM1, MASK_SHA512SUM1),
+    known_rv64("slli.uw", Profile(Extension::Zba), MATCH_SLLI_UW, MASK_SLLI_UW),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{DefinedBy, UNIMPLEMENTED, identify};
+    use crate::vm::{isa, profile::Extension};
+
+    #[test]
+    fn test_identify() {
+        let name = |instruction| identify(instruction).map(|x| (x.mnemonic, x.extension_name()));
+        assert_eq!(name(0x0220_81b3), Some(("mul", "M".to_string())));
+        assert_eq!(name(0x2870_5093), Some(("orc.b", "Zbb".to_string()))); // not the draft gorci it is a case of
+        assert_eq!(name(0x0010_1093), Some(("slli", "I".to_string()))); // not the RV64 slli
+        assert_eq!(name(0x0000_3083), Some(("ld", "RV64I".to_string())));
+        assert_eq!(name(0x0200_0053), Some(("fadd.d", "D".to_string())));
+        assert_eq!(name(0x0000_2005), Some(("c.jal", "C".to_string()))); // c.addiw on RV64
+
+        // zeroed memory isn't c.addi4spn
+        assert_eq!(name(0x0000_0000), None);
+        assert_eq!(name(0xffff_ffff), None);
+
+        let known = identify(0x1000_20af).unwrap(); // LR.W x1, (x0)
+        assert_eq!(known.defined_by, DefinedBy::Profile(Extension::A));
+        assert!(!known.rv64);
+    }
+
+    #[test]
+    fn test_catalogue_skips_implemented_encodings() {
+        for row in UNIMPLEMENTED {
+            let duplicate = isa::INSTRUCTIONS
+                .iter()
+                .find(|x| x.mask == row.mask && x.match_val == row.match_val);
+            assert_eq!(duplicate.map(|x| x.mnemonic), None, "{}", row.known.mnemonic);
+        }
+    }
+}
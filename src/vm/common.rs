@@ -1,3 +1,15 @@
+use super::{ras::ReturnStackChange, units::Booking};
+
+/// Integer registers x0..x31 followed by the floating point registers f0..f31, which hold the raw
+/// bits of their single-precision value. Sharing one index space lets hazard detection and
+/// forwarding treat both register files the same way.
+///
+/// Every register is 64 bits wide. On RV32 the values are kept sign-extended from bit 31, so the
+/// upper half never carries information of its own.
+pub const REGISTER_COUNT: usize = 64;
+pub const FP_REGISTER_OFFSET: usize = 32;
+
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Hash)]
 pub enum Opcode {
     Add,
@@ -25,27 +37,27 @@ pub enum OperandsFormat {
         rd: usize,
         r1: usize,
         r2: usize,
-        r1_val: i32,
-        r2_val: i32,
+        r1_val: i64,
+        r2_val: i64,
     },
     Itype {
         rd: usize,
         r1: usize,
-        r1_val: i32,
+        r1_val: i64,
         imm: i32,
     },
     Stype {
         r1: usize,
         r2: usize,
-        r1_val: i32,
-        r2_val: i32,
+        r1_val: i64,
+        r2_val: i64,
         imm: i32,
     },
     Btype {
         r1: usize,
         r2: usize,
-        r1_val: i32,
-        r2_val: i32,
+        r1_val: i64,
+        r2_val: i64,
         imm: i32,
     },
     Utype {
@@ -56,19 +68,166 @@ pub enum OperandsFormat {
         rd: usize,
         imm: i32,
     },
+    Ftype {
+        rd: usize,
+        r1: usize,
+        r2: usize,
+        r1_val: i64,
+        r2_val: i64,
+        rm: u8,
+    },
+    R4type {
+        rd: usize,
+        r1: usize,
+        r2: usize,
+        r3: usize,
+        r1_val: i64,
+        r2_val: i64,
+        r3_val: i64,
+        rm: u8,
+    },
+    Csrtype {
+        rd: usize,
+        r1: usize,
+        r1_val: i64, // holds the zero-extended immediate for the csrr*i variants, r1 is then 0
+        csr: u16,
+        instruction: u32, // reported when the access traps
+    },
+    Atype {
+        rd: usize,
+        r1: usize,
+        r2: usize,
+        r1_val: i64,
+        r2_val: i64,
+        rd_val: i64, // only amocas.w reads rd, it holds the expected value
+        operation: AtomicOperation,
+        aq: bool,
+        rl: bool,
+    },
+    Illegal {
+        instruction: u32, // kept for the fault report
+    },
+    Vtype {
+        rd: usize, // vsetvli writes the new vl to rd, 0 for every other vector instruction
+        r1: usize, // 0 when the rs1 field names a vector register or holds an immediate
+        r2: usize, // the stride register of the strided accesses, otherwise 0
+        r1_val: i64,
+        r2_val: i64,
+        vd: usize, // vs3 for the stores
+        vs1: usize,
+        vs2: usize,
+        imm: i32, // the vtype of vsetvli or the simm5 of the .vi forms
+        masked: bool,
+        operation: VectorOperation,
+        instruction: u32, // kept for the fault report
+    },
+    Custom {
+        rd: usize,
+        r1: usize,
+        r2: usize,
+        r1_val: i64,
+        r2_val: i64,
+        instruction: u32,
+        execute: fn(u32, i32, i32) -> i32,
+    },
+}
+
+/// The read-modify-write an A extension instruction performs in the memory stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtomicOperation {
+    LoadReserved,
+    StoreConditional,
+    Swap,
+    Add,
+    Xor,
+    And,
+    Or,
+    Min,
+    Max,
+    MinUnsigned,
+    MaxUnsigned,
+    CompareAndSwap,
+}
+
+/// What a V extension instruction does, the VM performs it on the vector registers in the
+/// execute stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorOperation {
+    SetVl,
+    /// Loads `vl` elements of `width` bits, from consecutive addresses or `rs2` bytes apart.
+    Load { width: usize, strided: bool },
+    Store { width: usize, strided: bool },
+    Elementwise(VectorArithmetic, VectorSource),
+    /// Folds the elements of `vs2` into element 0 of `vs1` and writes the result to element 0 of
+    /// `vd`.
+    Reduction(VectorArithmetic),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorArithmetic {
+    Add,
+    Mul,
+    Min,
+    MinUnsigned,
+    Max,
+    MaxUnsigned,
+}
+
+/// The second operand of an elementwise instruction, `.vv`, `.vx` or `.vi`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorSource {
+    Vector,
+    Scalar,
+    Immediate,
 }
 
 #[derive(Debug, Clone)]
 pub enum TrapType{
     Ecall,
-    Ebreak
+    Ebreak,
+    Wfi,
+    FenceI, // not a trap, tells the VM to invalidate the instruction cache
+    IllegalInstruction(u32),
+    // a store reaching past the end of memory, nothing from `addr` on is written
+    StoreOutOfRange { addr: usize, size: usize },
+    LoadOutOfRange { addr: usize, size: usize },
+    FetchOutOfRange,
+}
+
+impl TrapType {
+    /// Whether the trap stops the VM, the instruction doesn't retire.
+    pub fn is_fault(&self) -> bool {
+        matches!(
+            self,
+            TrapType::IllegalInstruction(_)
+                | TrapType::StoreOutOfRange { .. }
+                | TrapType::LoadOutOfRange { .. }
+                | TrapType::FetchOutOfRange
+        )
+    }
 }
 
 #[derive(Clone)]
 pub struct InstructionDefinition {
     pub mask: u32,
     pub match_val: u32,
-    pub decode: fn(u32, &[i32; 32], usize) -> IDEX,
+    pub decode: fn(u32, &[i64; REGISTER_COUNT], usize) -> IDEX,
+}
+
+#[derive(Debug, Clone)]
+pub enum CsrAccess {
+    Write,
+    Set,
+    Clear,
+}
+
+#[derive(Debug, Clone)]
+pub struct CsrOperation {
+    pub csr: u16,
+    pub access: CsrAccess,
+    pub value: i64,
+    pub write: bool, // csrrs/csrrc with x0 (or a zero immediate) only read
+    pub instruction: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -76,7 +235,9 @@ pub struct ExecuteResult {
     pub ex_mem: EXMEM,
     pub flush: bool,
     pub new_pc: Option<usize>,
-    pub trap_type: Option<TrapType>
+    pub trap_type: Option<TrapType>,
+    pub fflags: u8, // accrued into fcsr by the execute stage
+    pub csr_operation: Option<CsrOperation>,
 }
 
 #[derive(Debug, Clone)]
@@ -86,6 +247,20 @@ pub enum MemoryRange {
     Half,
     HalfUnsigned,
     Word,
+    WordUnsigned,
+    Double,
+}
+
+impl MemoryRange {
+    /// The number of bytes accessed.
+    pub fn size(&self) -> usize {
+        match self {
+            MemoryRange::Byte | MemoryRange::ByteUnsigned => 1,
+            MemoryRange::Half | MemoryRange::HalfUnsigned => 2,
+            MemoryRange::Word | MemoryRange::WordUnsigned => 4,
+            MemoryRange::Double => 8,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -98,6 +273,9 @@ pub struct MemoryOperation {
 pub struct IFID {
     pub instruction: u32,
     pub address: usize,
+    pub predicted_pc: usize, // where fetch continued after this instruction
+    pub return_stack_change: Option<ReturnStackChange>, // undone when the instruction is flushed
+    pub outside_memory: bool, // the word couldn't be fetched, it traps once it reaches execute
 }
 
 #[derive(Debug)]
@@ -105,13 +283,19 @@ pub struct IDEX {
     pub operands: Option<OperandsFormat>,
     pub memory_operation: Option<MemoryOperation>,
     pub address: usize,
+    pub latency: usize, // cycles spent in the execute stage
+    pub energy: u64,    // charged to `VM::energy` once the instruction executes
+    pub predicted_pc: usize, // where fetch continued after this instruction, set by the VM in decode
+    pub return_stack_change: Option<ReturnStackChange>, // of its IFID, for the flushes after decode
+    pub booking: Option<Booking>, // released when the instruction is flushed before it executes
+    pub fused: Option<EXMEM>, // the result of the instruction decode fused in front of this one
     pub execute: fn(&IDEX) -> ExecuteResult,
 }
 
 #[derive(Debug, Clone)]
 pub struct EXMEM {
     pub rd: Option<usize>,
-    pub calculation_result: i32,
+    pub calculation_result: i64,
     pub operands: Option<OperandsFormat>,
     pub memory_operation: Option<MemoryOperation>,
 }
@@ -119,5 +303,12 @@ pub struct EXMEM {
 #[derive(Debug)]
 pub struct MEMWB {
     pub rd: usize,
-    pub value: i32,
+    pub value: i64,
+}
+
+/// The MEM1/MEM2 latch of the seven stage pipeline, a load only has its value once it leaves MEM2.
+#[derive(Debug)]
+pub struct MEM1MEM2 {
+    pub mem_wb: MEMWB,
+    pub is_load: bool,
 }
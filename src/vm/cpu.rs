@@ -0,0 +1,98 @@
+// The CPU models `VM::step` can clock. They all run the same instruction table and differ in how
+// many instructions are in flight, which decides the hazards and branch penalties they pay and how
+// long their clock period has to be.
+
+const OPCODE_LOAD: u32 = 0b0000011;
+const OPCODE_LOAD_FP: u32 = 0b0000111;
+const OPCODE_STORE: u32 = 0b0100011;
+const OPCODE_STORE_FP: u32 = 0b0100111;
+const OPCODE_AMO: u32 = 0b0101111;
+const OPCODE_MISC_MEM: u32 = 0b0001111;
+const OPCODE_BRANCH: u32 = 0b1100011;
+const OPCODE_JALR: u32 = 0b1100111;
+const OPCODE_JAL: u32 = 0b1101111;
+const OPCODE_SYSTEM: u32 = 0b1110011;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuModel {
+    /// Every instruction goes through the whole datapath in one long cycle. There is nothing to
+    /// forward or flush, but the clock can't be faster than the slowest instruction.
+    SingleCycle,
+    /// One instruction at a time, taking one short cycle for each step it needs, see
+    /// `multi_cycle_cpi`.
+    MultiCycle,
+    /// IF ID EX MEM WB, a load followed by a use of its result stalls a cycle and a mispredicted
+    /// branch or jump costs one bubble.
+    FiveStage,
+    /// IF1 IF2 ID EX MEM1 MEM2 WB, fetch and the memory access are split over two stages each for a
+    /// faster clock. A load followed by a use of its result stalls two cycles, one more instruction
+    /// is between it and the use, and a mispredicted branch or jump costs two bubbles.
+    SevenStage,
+    /// The five stage pipeline issuing up to two instructions a cycle in order, see `can_pair`.
+    DualIssue,
+}
+
+impl CpuModel {
+    /// Whether instructions overlap, only then fetch predicts and decode checks for hazards.
+    pub fn is_pipelined(self) -> bool {
+        matches!(self, CpuModel::FiveStage | CpuModel::SevenStage | CpuModel::DualIssue)
+    }
+
+    /// The length of a cycle, in units where fetch and the memory access take 2 and decode, execute
+    /// and writeback take 1. `cycle() * cycle_time()` compares the models in time.
+    pub fn cycle_time(self) -> usize {
+        match self {
+            CpuModel::SingleCycle => 7,
+            CpuModel::MultiCycle | CpuModel::FiveStage | CpuModel::DualIssue => 2,
+            CpuModel::SevenStage => 1,
+        }
+    }
+}
+
+/// The cycles the multi-cycle CPU spends on `instruction`: loads and atomics go through all 5
+/// steps, stores skip writeback and everything else the memory access. Branches are done once
+/// execute compared their operands.
+pub fn multi_cycle_cpi(instruction: u32) -> usize {
+    match instruction & 0x7f {
+        OPCODE_LOAD | OPCODE_LOAD_FP | OPCODE_AMO => 5,
+        OPCODE_STORE | OPCODE_STORE_FP => 4,
+        OPCODE_BRANCH => 3,
+        _ => 4,
+    }
+}
+
+/// Whether `second` can issue in the same cycle as `first`, the instruction before it. A bundle has
+/// one memory access and one control transfer, system instructions issue alone.
+pub fn can_pair(first: u32, second: u32) -> bool {
+    let opcodes = [first & 0x7f, second & 0x7f];
+    let count = |class: &[u32]| opcodes.iter().filter(|x| class.contains(x)).count();
+    count(&[OPCODE_LOAD, OPCODE_LOAD_FP, OPCODE_STORE, OPCODE_STORE_FP, OPCODE_AMO]) < 2
+        && count(&[OPCODE_BRANCH, OPCODE_JAL, OPCODE_JALR]) < 2
+        && count(&[OPCODE_SYSTEM, OPCODE_MISC_MEM]) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{can_pair, multi_cycle_cpi};
+
+    #[test]
+    fn test_multi_cycle_cpi() {
+        assert_eq!(multi_cycle_cpi(0x0080_2083), 5); // LW x1, 8(x0)
+        assert_eq!(multi_cycle_cpi(0x0010_2423), 4); // SW x1, 8(x0)
+        assert_eq!(multi_cycle_cpi(0x0020_81b3), 4); // ADD x3, x1, x2
+        assert_eq!(multi_cycle_cpi(0x0020_8463), 3); // BEQ x1, x2, 8
+        assert_eq!(multi_cycle_cpi(0x0080_00ef), 4); // JAL ra, 8
+    }
+
+    #[test]
+    fn test_can_pair() {
+        let (lw, sw, add, beq, jal) = (0x0080_2083, 0x0010_2423, 0x0020_81b3, 0x0020_8463, 0x0080_00ef);
+        assert!(can_pair(lw, add));
+        assert!(can_pair(add, sw));
+        assert!(can_pair(beq, lw));
+        assert!(!can_pair(lw, sw));
+        assert!(!can_pair(beq, jal));
+        assert!(!can_pair(add, 0x0000_0073)); // ECALL
+        assert!(!can_pair(0x0000_100f, add)); // FENCE.I
+    }
+}
@@ -0,0 +1,292 @@
+// The RV32 scalar cryptography instructions of Zkne, Zknd and Zknh. The aes32 instructions do one
+// byte of an AES round each: byte `bs` of rs2 goes through the (inverse) S-box, optionally through
+// its column of the (inverse) MixColumns matrix, and is xored into rs1 rotated back to its row.
+
+const fn xtime(x: u8) -> u8 {
+    (x << 1) ^ if x & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+// multiplication in GF(2^8) modulo the AES polynomial
+const fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+// the multiplicative inverse followed by the affine transformation, 0 has no inverse and maps to 0x63
+const fn sbox() -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut x = 0;
+    while x < 256 {
+        // x^254 is the inverse of x, and 0 for 0
+        let mut inverse = 1;
+        let mut i = 0;
+        while i < 254 {
+            inverse = gf_mul(inverse, x as u8);
+            i += 1;
+        }
+        let b = if x == 0 { 0 } else { inverse };
+        table[x] = b ^ b.rotate_left(1) ^ b.rotate_left(2) ^ b.rotate_left(3) ^ b.rotate_left(4) ^ 0x63;
+        x += 1;
+    }
+    table
+}
+
+const fn inverse_sbox(sbox: &[u8; 256]) -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut x = 0;
+    while x < 256 {
+        table[sbox[x] as usize] = x as u8;
+        x += 1;
+    }
+    table
+}
+
+const SBOX: [u8; 256] = sbox();
+const INVERSE_SBOX: [u8; 256] = inverse_sbox(&SBOX);
+
+// the column of the MixColumns matrix a byte in row 0 is multiplied with, from row 3 down to row 0
+fn mix_column(x: u8) -> u32 {
+    u32::from_be_bytes([gf_mul(x, 3), x, x, gf_mul(x, 2)])
+}
+
+fn inverse_mix_column(x: u8) -> u32 {
+    u32::from_be_bytes([gf_mul(x, 0xb), gf_mul(x, 0xd), gf_mul(x, 0x9), gf_mul(x, 0xe)])
+}
+
+fn aes32(bs: u32, rs1: i32, rs2: i32, transform: impl Fn(u8) -> u32) -> i32 {
+    let shift = (bs & 3) * 8;
+    let byte = (rs2 as u32 >> shift) as u8;
+    rs1 ^ transform(byte).rotate_left(shift) as i32
+}
+
+pub fn aes32esi(bs: u32, rs1: i32, rs2: i32) -> i32 {
+    aes32(bs, rs1, rs2, |x| SBOX[x as usize] as u32)
+}
+
+pub fn aes32esmi(bs: u32, rs1: i32, rs2: i32) -> i32 {
+    aes32(bs, rs1, rs2, |x| mix_column(SBOX[x as usize]))
+}
+
+pub fn aes32dsi(bs: u32, rs1: i32, rs2: i32) -> i32 {
+    aes32(bs, rs1, rs2, |x| INVERSE_SBOX[x as usize] as u32)
+}
+
+pub fn aes32dsmi(bs: u32, rs1: i32, rs2: i32) -> i32 {
+    aes32(bs, rs1, rs2, |x| inverse_mix_column(INVERSE_SBOX[x as usize]))
+}
+
+pub fn sha256sig0(rs1: i32) -> i32 {
+    let x = rs1 as u32;
+    (x.rotate_right(7) ^ x.rotate_right(18) ^ x >> 3) as i32
+}
+
+pub fn sha256sig1(rs1: i32) -> i32 {
+    let x = rs1 as u32;
+    (x.rotate_right(17) ^ x.rotate_right(19) ^ x >> 10) as i32
+}
+
+pub fn sha256sum0(rs1: i32) -> i32 {
+    let x = rs1 as u32;
+    (x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)) as i32
+}
+
+pub fn sha256sum1(rs1: i32) -> i32 {
+    let x = rs1 as u32;
+    (x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)) as i32
+}
+
+// RV32 computes the 64 bit SHA-512 functions a half at a time, the sigmas take the half they
+// compute in rs1 and the other half in rs2, the sums return the low half from the low half in rs1
+// (or the high half with the halves swapped)
+
+pub fn sha512sig0h(rs1: i32, rs2: i32) -> i32 {
+    let (a, b) = (rs1 as u32, rs2 as u32);
+    (a >> 1 ^ a >> 7 ^ a >> 8 ^ b << 31 ^ b << 24) as i32
+}
+
+pub fn sha512sig0l(rs1: i32, rs2: i32) -> i32 {
+    let (a, b) = (rs1 as u32, rs2 as u32);
+    (a >> 1 ^ a >> 7 ^ a >> 8 ^ b << 31 ^ b << 25 ^ b << 24) as i32
+}
+
+pub fn sha512sig1h(rs1: i32, rs2: i32) -> i32 {
+    let (a, b) = (rs1 as u32, rs2 as u32);
+    (a << 3 ^ a >> 6 ^ a >> 19 ^ b >> 29 ^ b << 13) as i32
+}
+
+pub fn sha512sig1l(rs1: i32, rs2: i32) -> i32 {
+    let (a, b) = (rs1 as u32, rs2 as u32);
+    (a << 3 ^ a >> 6 ^ a >> 19 ^ b >> 29 ^ b << 26 ^ b << 13) as i32
+}
+
+pub fn sha512sum0r(rs1: i32, rs2: i32) -> i32 {
+    let (a, b) = (rs1 as u32, rs2 as u32);
+    (a << 25 ^ a << 30 ^ a >> 28 ^ b >> 7 ^ b >> 2 ^ b << 4) as i32
+}
+
+pub fn sha512sum1r(rs1: i32, rs2: i32) -> i32 {
+    let (a, b) = (rs1 as u32, rs2 as u32);
+    (a << 23 ^ a >> 14 ^ a >> 18 ^ b >> 9 ^ b << 18 ^ b << 14) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // AES-128 with the four aes32 instructions per output column, the way the scalar crypto spec
+    // sketches it. Columns are little-endian words, so byte `bs` of a word is row `bs`.
+    fn expand_key(key: [u8; 16]) -> [i32; 44] {
+        let mut words = [0; 44];
+        for (word, bytes) in words.iter_mut().zip(key.chunks(4)) {
+            *word = i32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        let mut rcon = 1;
+        for i in 4..44 {
+            let mut t = words[i - 1];
+            if i % 4 == 0 {
+                let rotated = t.rotate_right(8);
+                t = (0..4).fold(rcon as i32, |acc, bs| aes32esi(bs, acc, rotated));
+                rcon = xtime(rcon);
+            }
+            words[i] = words[i - 4] ^ t;
+        }
+        words
+    }
+
+    fn round(state: [i32; 4], key: &[i32], byte: fn(u32, i32, i32) -> i32, step: usize) -> [i32; 4] {
+        std::array::from_fn(|column| {
+            (0..4).fold(key[column], |acc, bs| byte(bs as u32, acc, state[(column + step * bs) % 4]))
+        })
+    }
+
+    fn encrypt(key: [u8; 16], block: [u8; 16]) -> [u8; 16] {
+        let keys = expand_key(key);
+        let mut state: [i32; 4] = std::array::from_fn(|i| i32::from_le_bytes(block[i * 4..][..4].try_into().unwrap()));
+        state = std::array::from_fn(|i| state[i] ^ keys[i]);
+        for r in 1..10 {
+            state = round(state, &keys[r * 4..], aes32esmi, 1);
+        }
+        state = round(state, &keys[40..], aes32esi, 1);
+        let mut out = [0; 16];
+        for (bytes, word) in out.chunks_mut(4).zip(state) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    // the equivalent inverse cipher, the middle round keys go through InvMixColumns, which is
+    // aes32dsmi undoing the S-box aes32esi applied first
+    fn decrypt(key: [u8; 16], block: [u8; 16]) -> [u8; 16] {
+        let mut keys = expand_key(key);
+        for word in &mut keys[4..40] {
+            let substituted = (0..4).fold(0, |acc, bs| aes32esi(bs, acc, *word));
+            *word = (0..4).fold(0, |acc, bs| aes32dsmi(bs, acc, substituted));
+        }
+        let mut state: [i32; 4] = std::array::from_fn(|i| i32::from_le_bytes(block[i * 4..][..4].try_into().unwrap()));
+        state = std::array::from_fn(|i| state[i] ^ keys[40 + i]);
+        for r in (1..10).rev() {
+            state = round(state, &keys[r * 4..], aes32dsmi, 3);
+        }
+        state = round(state, &keys[..4], aes32dsi, 3);
+        let mut out = [0; 16];
+        for (bytes, word) in out.chunks_mut(4).zip(state) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn test_sbox() {
+        assert_eq!(SBOX[0x00], 0x63);
+        assert_eq!(SBOX[0x53], 0xed);
+        assert_eq!(SBOX[0xff], 0x16);
+        assert_eq!(INVERSE_SBOX[0x63], 0x00);
+    }
+
+    #[test]
+    fn test_aes128_fips197() {
+        // FIPS-197 appendix C.1
+        let key = std::array::from_fn(|i| i as u8);
+        let plaintext = std::array::from_fn(|i| (i as u8) << 4 | i as u8);
+        let ciphertext = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a,
+        ];
+        assert_eq!(encrypt(key, plaintext), ciphertext);
+        assert_eq!(decrypt(key, ciphertext), plaintext);
+    }
+
+    const SHA256_K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+    ];
+
+    #[test]
+    fn test_sha256_fips180() {
+        // FIPS-180 "abc", a single padded block
+        let mut block = [0u8; 64];
+        block[..3].copy_from_slice(b"abc");
+        block[3] = 0x80;
+        block[63] = 24;
+
+        let mut w = [0u32; 64];
+        for (i, bytes) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for i in 16..64 {
+            w[i] = (sha256sig1(w[i - 2] as i32) as u32)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(sha256sig0(w[i - 15] as i32) as u32)
+                .wrapping_add(w[i - 16]);
+        }
+
+        let initial = [
+            0x6a09e667u32, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+        ];
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = initial;
+        for i in 0..64 {
+            let t1 = h
+                .wrapping_add(sha256sum1(e as i32) as u32)
+                .wrapping_add((e & f) ^ (!e & g))
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let t2 = (sha256sum0(a as i32) as u32).wrapping_add((a & b) ^ (a & c) ^ (b & c));
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        let digest: Vec<u32> = [a, b, c, d, e, f, g, h].iter().zip(initial).map(|(x, y)| x.wrapping_add(y)).collect();
+        assert_eq!(
+            digest,
+            [0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61, 0xf20015ad]
+        );
+    }
+
+    #[test]
+    fn test_sha512_halves() {
+        // the 64 bit functions of FIPS-180
+        let sig0 = |x: u64| x.rotate_right(1) ^ x.rotate_right(8) ^ x >> 7;
+        let sig1 = |x: u64| x.rotate_right(19) ^ x.rotate_right(61) ^ x >> 6;
+        let sum0 = |x: u64| x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39);
+        let sum1 = |x: u64| x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41);
+
+        for x in [0x6a09e667f3bcc908u64, 0x428a2f98d728ae22, 0x8000000000000001, u64::MAX] {
+            let (high, low) = ((x >> 32) as i32, x as i32);
+            let join = |high: i32, low: i32| (high as u32 as u64) << 32 | low as u32 as u64;
+            assert_eq!(join(sha512sig0h(high, low), sha512sig0l(low, high)), sig0(x), "{x:#x}");
+            assert_eq!(join(sha512sig1h(high, low), sha512sig1l(low, high)), sig1(x), "{x:#x}");
+            assert_eq!(join(sha512sum0r(high, low), sha512sum0r(low, high)), sum0(x), "{x:#x}");
+            assert_eq!(join(sha512sum1r(high, low), sha512sum1r(low, high)), sum1(x), "{x:#x}");
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::common::{
+    CsrAccess, CsrOperation, EXMEM, ExecuteResult, IDEX, OperandsFormat, REGISTER_COUNT,
+};
+
+pub fn extract_csrtype(instruction: u32, registers: &[i64; REGISTER_COUNT]) -> OperandsFormat {
+    let r1 = ((instruction >> 15) & 0x1f) as usize;
+
+    OperandsFormat::Csrtype {
+        rd: ((instruction >> 7) & 0x1f) as usize,
+        r1_val: registers[r1],
+        csr: (instruction >> 20) as u16,
+        r1,
+        instruction,
+    }
+}
+
+// the csrr*i variants reuse the rs1 field as a 5 bit zero-extended immediate
+pub fn extract_csrtype_immediate(instruction: u32) -> OperandsFormat {
+    OperandsFormat::Csrtype {
+        rd: ((instruction >> 7) & 0x1f) as usize,
+        r1: 0,
+        r1_val: ((instruction >> 15) & 0x1f) as i64,
+        csr: (instruction >> 20) as u16,
+        instruction,
+    }
+}
+
+// the csr itself is read and written by the vm in the execute stage, the old value ends up in rd
+pub fn csr_result(
+    id_ex: &IDEX,
+    rd: usize,
+    csr: u16,
+    access: CsrAccess,
+    value: i64,
+    write: bool,
+    instruction: u32,
+) -> ExecuteResult {
+    ExecuteResult {
+        ex_mem: EXMEM {
+            rd: Some(rd),
+            calculation_result: 0,
+            memory_operation: None,
+            operands: id_ex.operands.clone(),
+        },
+        flush: false,
+        new_pc: None,
+        trap_type: None,
+        fflags: 0,
+        csr_operation: Some(CsrOperation {
+            csr,
+            access,
+            value,
+            write,
+            instruction,
+        }),
+    }
+}
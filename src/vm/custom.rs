@@ -0,0 +1,111 @@
+use super::common::{EXMEM, ExecuteResult, IDEX, OperandsFormat, REGISTER_COUNT};
+
+pub const OPCODE_CUSTOM_0: u32 = 0b0001011;
+pub const OPCODE_CUSTOM_1: u32 = 0b0101011;
+
+/// A game specific instruction in the custom-0 or custom-1 opcode space. It is decoded like an
+/// R-type instruction, the funct3/funct7 bits that `mask` leaves open can carry extra immediates.
+#[derive(Debug, Clone)]
+pub struct CustomInstruction {
+    pub name: &'static str,
+    pub mask: u32,
+    pub match_val: u32,
+    /// Computes rd from the raw instruction and the values of rs1 and rs2. On RV64 it sees their
+    /// low 32 bits and the result is sign-extended.
+    pub execute: fn(u32, i32, i32) -> i32,
+    /// Cycles spent in the execute stage, 1 behaves like any other alu instruction.
+    pub latency: usize,
+    /// Charged to `VM::energy` every time the instruction executes.
+    pub energy: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CustomInstructionError {
+    /// `match_val` sets bits that `mask` doesn't compare.
+    MatchOutsideMask,
+    /// The opcode isn't custom-0 or custom-1, or `mask` doesn't fix all of its bits.
+    NotInCustomSpace,
+    /// Some installed instruction has an encoding that also matches.
+    Overlap { mask: u32, match_val: u32 },
+    /// A latency of 0 would let the instruction finish before it started.
+    ZeroLatency,
+}
+
+impl CustomInstruction {
+    pub fn matches(&self, instruction: u32) -> bool {
+        instruction & self.mask == self.match_val
+    }
+
+    /// Checks the instruction on its own, `VM::register_custom_instruction` also checks it
+    /// against the installed encodings.
+    pub fn validate(&self) -> Result<(), CustomInstructionError> {
+        if self.match_val & !self.mask != 0 {
+            return Err(CustomInstructionError::MatchOutsideMask);
+        }
+        let opcode = self.match_val & 0x7f;
+        if self.mask & 0x7f != 0x7f || (opcode != OPCODE_CUSTOM_0 && opcode != OPCODE_CUSTOM_1) {
+            return Err(CustomInstructionError::NotInCustomSpace);
+        }
+        if self.latency == 0 {
+            return Err(CustomInstructionError::ZeroLatency);
+        }
+        Ok(())
+    }
+
+    pub(super) fn decode(&self, instruction: u32, registers: &[i64; REGISTER_COUNT], address: usize) -> IDEX {
+        let r1 = ((instruction >> 15) & 0x1f) as usize;
+        let r2 = ((instruction >> 20) & 0x1f) as usize;
+
+        IDEX {
+            operands: Some(OperandsFormat::Custom {
+                rd: ((instruction >> 7) & 0x1f) as usize,
+                r1_val: registers[r1],
+                r2_val: registers[r2],
+                execute: self.execute,
+                instruction,
+                r1,
+                r2,
+            }),
+            memory_operation: None,
+            address,
+            latency: self.latency,
+            energy: self.energy,
+            predicted_pc: address.wrapping_add(4),
+            return_stack_change: None,
+            booking: None,
+            fused: None,
+            execute: |id_ex| {
+                if let Some(OperandsFormat::Custom {
+                    rd,
+                    r1_val,
+                    r2_val,
+                    instruction,
+                    execute,
+                    ..
+                }) = &id_ex.operands
+                {
+                    ExecuteResult {
+                        ex_mem: EXMEM {
+                            rd: Some(*rd),
+                            calculation_result: execute(*instruction, *r1_val as i32, *r2_val as i32) as i64,
+                            memory_operation: None,
+                            operands: id_ex.operands.clone(),
+                        },
+                        flush: false,
+                        new_pc: None,
+                        trap_type: None,
+                        fflags: 0,
+                        csr_operation: None,
+                    }
+                } else {
+                    unreachable!()
+                }
+            },
+        }
+    }
+}
+
+/// Whether some instruction word matches both encodings.
+pub fn encodings_overlap(mask: u32, match_val: u32, other_mask: u32, other_match_val: u32) -> bool {
+    (match_val ^ other_match_val) & mask & other_mask == 0
+}
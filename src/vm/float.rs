@@ -0,0 +1,414 @@
+// Single-precision arithmetic for the F extension. Operands and results are raw `u32` bit
+// patterns, every operation also returns the fflags it raised.
+//
+// f32 operands are exact in f64, so each operation is computed in double precision together with
+// the exact error of that computation (two-sum, fma remainders). `round` then uses the sign of the
+// error to round correctly to single precision in every rounding mode.
+
+pub const FFLAG_INEXACT: u8 = 0x01;
+pub const FFLAG_UNDERFLOW: u8 = 0x02;
+pub const FFLAG_OVERFLOW: u8 = 0x04;
+pub const FFLAG_DIVIDE_BY_ZERO: u8 = 0x08;
+pub const FFLAG_INVALID: u8 = 0x10;
+
+pub const ROUNDING_MODE_DYNAMIC: u8 = 0b111;
+
+pub const CANONICAL_NAN: u32 = 0x7fc0_0000;
+
+// 2^128, the first magnitude that no longer fits into an f32
+const OVERFLOW_THRESHOLD: f64 = 340282366920938463463374607431768211456.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    NearestEven,
+    TowardZero,
+    Down,
+    Up,
+    NearestMaxMagnitude,
+}
+
+impl RoundingMode {
+    /// Maps the `rm` field of an instruction or `frm`, `None` for the reserved encodings 0b101 and
+    /// 0b110 and for the dynamic mode, which isn't a rounding mode of its own.
+    pub fn from_bits(rm: u8) -> Option<Self> {
+        match rm {
+            0b000 => Some(RoundingMode::NearestEven),
+            0b001 => Some(RoundingMode::TowardZero),
+            0b010 => Some(RoundingMode::Down),
+            0b011 => Some(RoundingMode::Up),
+            0b100 => Some(RoundingMode::NearestMaxMagnitude),
+            _ => None,
+        }
+    }
+}
+
+fn is_nan(bits: u32) -> bool {
+    f32::from_bits(bits).is_nan()
+}
+
+fn is_signaling_nan(bits: u32) -> bool {
+    is_nan(bits) && bits & 0x0040_0000 == 0
+}
+
+fn nan_result(operands: &[u32]) -> (u32, u8) {
+    if operands.iter().any(|x| is_signaling_nan(*x)) {
+        (CANONICAL_NAN, FFLAG_INVALID)
+    } else {
+        (CANONICAL_NAN, 0)
+    }
+}
+
+fn two_sum_error(a: f64, b: f64, sum: f64) -> f64 {
+    let b_virtual = sum - a;
+    (a - (sum - b_virtual)) + (b - b_virtual)
+}
+
+// exact cancellation gives +0, except when rounding down, the sum of two equally signed zeros
+// keeps their sign
+fn zero_sum(a: f64, b: f64, rm: RoundingMode) -> (u32, u8) {
+    let zero = if a.is_sign_negative() == b.is_sign_negative() {
+        if a.is_sign_negative() { -0.0f32 } else { 0.0f32 }
+    } else if rm == RoundingMode::Down {
+        -0.0f32
+    } else {
+        0.0f32
+    };
+    (zero.to_bits(), 0)
+}
+
+fn overflow(negative: bool, rm: RoundingMode) -> (u32, u8) {
+    let to_infinity = match rm {
+        RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => true,
+        RoundingMode::TowardZero => false,
+        RoundingMode::Down => negative,
+        RoundingMode::Up => !negative,
+    };
+    let magnitude = if to_infinity { f32::INFINITY } else { f32::MAX };
+    let result = if negative { -magnitude } else { magnitude };
+    (result.to_bits(), FFLAG_OVERFLOW | FFLAG_INEXACT)
+}
+
+fn next_up(x: f32) -> f64 {
+    if x == f32::MAX { OVERFLOW_THRESHOLD } else { x.next_up() as f64 }
+}
+
+fn next_down(x: f32) -> f64 {
+    if x == -f32::MAX { -OVERFLOW_THRESHOLD } else { x.next_down() as f64 }
+}
+
+fn is_even(x: f64) -> bool {
+    x.abs() >= OVERFLOW_THRESHOLD || (x as f32).to_bits() & 1 == 0
+}
+
+/// Rounds the exact result `value + error` to single precision. `value` is the double-precision
+/// approximation and `error` what it is off by; `error` is tiny compared to `value` so only its
+/// sign matters.
+fn round(value: f64, error: f64, rm: RoundingMode) -> (u32, u8) {
+    if value.is_infinite() {
+        return ((value as f32).to_bits(), 0);
+    }
+
+    let magnitude = value.abs();
+    let error_away_from_zero = error != 0.0 && (error > 0.0) == (value > 0.0);
+    if magnitude > OVERFLOW_THRESHOLD
+        || (magnitude == OVERFLOW_THRESHOLD && (error == 0.0 || error_away_from_zero))
+    {
+        return overflow(value.is_sign_negative(), rm);
+    }
+
+    // the two neighbouring single-precision values around the exact result
+    let nearest = value as f32;
+    let (lower, upper) = if nearest as f64 == value {
+        if error == 0.0 {
+            return (nearest.to_bits(), 0);
+        }
+        if error > 0.0 {
+            (value, next_up(nearest))
+        } else {
+            (next_down(nearest), value)
+        }
+    } else if (nearest as f64) < value {
+        (nearest as f64, next_up(nearest))
+    } else {
+        (next_down(nearest), nearest as f64)
+    };
+
+    let rounded = match rm {
+        RoundingMode::Down => lower,
+        RoundingMode::Up => upper,
+        RoundingMode::TowardZero => {
+            if value > 0.0 { lower } else { upper }
+        }
+        RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => {
+            let midpoint = lower + (upper - lower) / 2.0;
+            if nearest as f64 == value {
+                // the error is far smaller than half a single-precision ulp
+                value
+            } else if value < midpoint || (value == midpoint && error < 0.0) {
+                lower
+            } else if value > midpoint || error > 0.0 {
+                upper
+            } else if rm == RoundingMode::NearestEven {
+                if is_even(lower) { lower } else { upper }
+            } else if lower.abs() > upper.abs() {
+                lower
+            } else {
+                upper
+            }
+        }
+    };
+
+    if rounded.abs() >= OVERFLOW_THRESHOLD {
+        return overflow(value.is_sign_negative(), rm);
+    }
+
+    let mut flags = FFLAG_INEXACT;
+    if rounded.abs() < f32::MIN_POSITIVE as f64 {
+        flags |= FFLAG_UNDERFLOW;
+    }
+    ((rounded as f32).to_bits(), flags)
+}
+
+pub fn add(a: u32, b: u32, rm: RoundingMode) -> (u32, u8) {
+    if is_nan(a) || is_nan(b) {
+        return nan_result(&[a, b]);
+    }
+    let x = f32::from_bits(a) as f64;
+    let y = f32::from_bits(b) as f64;
+    if x.is_infinite() && y.is_infinite() && x != y {
+        return (CANONICAL_NAN, FFLAG_INVALID);
+    }
+
+    let sum = x + y;
+    if sum == 0.0 {
+        return zero_sum(x, y, rm);
+    }
+    round(sum, two_sum_error(x, y, sum), rm)
+}
+
+pub fn sub(a: u32, b: u32, rm: RoundingMode) -> (u32, u8) {
+    if is_nan(a) || is_nan(b) {
+        return nan_result(&[a, b]);
+    }
+    add(a, b ^ 0x8000_0000, rm)
+}
+
+pub fn mul(a: u32, b: u32, rm: RoundingMode) -> (u32, u8) {
+    if is_nan(a) || is_nan(b) {
+        return nan_result(&[a, b]);
+    }
+    let x = f32::from_bits(a) as f64;
+    let y = f32::from_bits(b) as f64;
+    if (x.is_infinite() && y == 0.0) || (x == 0.0 && y.is_infinite()) {
+        return (CANONICAL_NAN, FFLAG_INVALID);
+    }
+
+    // 24 bit by 24 bit significands, the product is exact
+    round(x * y, 0.0, rm)
+}
+
+pub fn div(a: u32, b: u32, rm: RoundingMode) -> (u32, u8) {
+    if is_nan(a) || is_nan(b) {
+        return nan_result(&[a, b]);
+    }
+    let x = f32::from_bits(a) as f64;
+    let y = f32::from_bits(b) as f64;
+    if (x == 0.0 && y == 0.0) || (x.is_infinite() && y.is_infinite()) {
+        return (CANONICAL_NAN, FFLAG_INVALID);
+    }
+    if y == 0.0 && x.is_finite() {
+        let negative = x.is_sign_negative() != y.is_sign_negative();
+        let result = if negative { f32::NEG_INFINITY } else { f32::INFINITY };
+        return (result.to_bits(), FFLAG_DIVIDE_BY_ZERO);
+    }
+
+    let quotient = x / y;
+    if quotient.is_infinite() || quotient == 0.0 {
+        return round(quotient, 0.0, rm);
+    }
+    let remainder = (-quotient).mul_add(y, x);
+    round(quotient, remainder / y, rm)
+}
+
+pub fn sqrt(a: u32, rm: RoundingMode) -> (u32, u8) {
+    if is_nan(a) {
+        return nan_result(&[a]);
+    }
+    let x = f32::from_bits(a) as f64;
+    if x == 0.0 {
+        return (a, 0);
+    }
+    if x < 0.0 {
+        return (CANONICAL_NAN, FFLAG_INVALID);
+    }
+
+    let root = x.sqrt();
+    if root.is_infinite() {
+        return round(root, 0.0, rm);
+    }
+    let remainder = (-root).mul_add(root, x);
+    round(root, remainder, rm)
+}
+
+/// Computes `(a * b) + c` with a single rounding, `negate_product` and `negate_addend` select the
+/// fmsub, fnmsub and fnmadd variants.
+pub fn fused_multiply_add(
+    a: u32,
+    b: u32,
+    c: u32,
+    negate_product: bool,
+    negate_addend: bool,
+    rm: RoundingMode,
+) -> (u32, u8) {
+    let x = f32::from_bits(a) as f64;
+    let y = f32::from_bits(b) as f64;
+    // infinity times zero is invalid even when the addend is a quiet NaN
+    if (x.is_infinite() && y == 0.0) || (x == 0.0 && y.is_infinite()) {
+        return (CANONICAL_NAN, FFLAG_INVALID);
+    }
+    if is_nan(a) || is_nan(b) || is_nan(c) {
+        return nan_result(&[a, b, c]);
+    }
+
+    let mut product = x * y;
+    if negate_product {
+        product = -product;
+    }
+    let mut addend = f32::from_bits(c) as f64;
+    if negate_addend {
+        addend = -addend;
+    }
+    if product.is_infinite() && addend.is_infinite() && product != addend {
+        return (CANONICAL_NAN, FFLAG_INVALID);
+    }
+
+    let sum = product + addend;
+    if sum == 0.0 {
+        return zero_sum(product, addend, rm);
+    }
+    if sum.is_infinite() {
+        return round(sum, 0.0, rm);
+    }
+    round(sum, two_sum_error(product, addend, sum), rm)
+}
+
+fn min_max(a: u32, b: u32, minimum: bool) -> (u32, u8) {
+    let flags = if is_signaling_nan(a) || is_signaling_nan(b) {
+        FFLAG_INVALID
+    } else {
+        0
+    };
+
+    match (is_nan(a), is_nan(b)) {
+        (true, true) => (CANONICAL_NAN, flags),
+        (true, false) => (b, flags),
+        (false, true) => (a, flags),
+        (false, false) => {
+            let x = f32::from_bits(a);
+            let y = f32::from_bits(b);
+            // -0.0 is considered smaller than +0.0
+            let a_smaller = x < y || (x == y && x.is_sign_negative());
+            if a_smaller == minimum { (a, flags) } else { (b, flags) }
+        }
+    }
+}
+
+pub fn min(a: u32, b: u32) -> (u32, u8) {
+    min_max(a, b, true)
+}
+
+pub fn max(a: u32, b: u32) -> (u32, u8) {
+    min_max(a, b, false)
+}
+
+/// `feq` only signals on signaling NaNs
+pub fn eq(a: u32, b: u32) -> (i32, u8) {
+    if is_nan(a) || is_nan(b) {
+        let (_, flags) = nan_result(&[a, b]);
+        return (0, flags);
+    }
+    ((f32::from_bits(a) == f32::from_bits(b)) as i32, 0)
+}
+
+/// `flt` and `fle` signal on any NaN
+pub fn lt(a: u32, b: u32) -> (i32, u8) {
+    if is_nan(a) || is_nan(b) {
+        return (0, FFLAG_INVALID);
+    }
+    ((f32::from_bits(a) < f32::from_bits(b)) as i32, 0)
+}
+
+pub fn le(a: u32, b: u32) -> (i32, u8) {
+    if is_nan(a) || is_nan(b) {
+        return (0, FFLAG_INVALID);
+    }
+    ((f32::from_bits(a) <= f32::from_bits(b)) as i32, 0)
+}
+
+pub fn classify(a: u32) -> i32 {
+    let x = f32::from_bits(a);
+    let negative = x.is_sign_negative();
+    let bit = if is_signaling_nan(a) {
+        8
+    } else if x.is_nan() {
+        9
+    } else if x.is_infinite() {
+        if negative { 0 } else { 7 }
+    } else if x == 0.0 {
+        if negative { 3 } else { 4 }
+    } else if x.is_subnormal() {
+        if negative { 2 } else { 5 }
+    } else if negative {
+        1
+    } else {
+        6
+    };
+    1 << bit
+}
+
+/// fcvt.w.s and fcvt.wu.s, out of range values and NaN saturate and raise the invalid flag
+pub fn to_int(a: u32, rm: RoundingMode, signed: bool) -> (i32, u8) {
+    let (lowest, highest) = if signed {
+        (i32::MIN as f64, i32::MAX as f64)
+    } else {
+        (0.0, u32::MAX as f64)
+    };
+    let saturated_high = if signed { i32::MAX } else { u32::MAX as i32 };
+
+    let x = f32::from_bits(a) as f64;
+    if x.is_nan() {
+        return (saturated_high, FFLAG_INVALID);
+    }
+
+    let rounded = match rm {
+        RoundingMode::NearestEven => x.round_ties_even(),
+        RoundingMode::TowardZero => x.trunc(),
+        RoundingMode::Down => x.floor(),
+        RoundingMode::Up => x.ceil(),
+        RoundingMode::NearestMaxMagnitude => x.round(),
+    };
+    if rounded < lowest {
+        return (if signed { i32::MIN } else { 0 }, FFLAG_INVALID);
+    }
+    if rounded > highest {
+        return (saturated_high, FFLAG_INVALID);
+    }
+
+    let value = if signed {
+        rounded as i32
+    } else {
+        rounded as u32 as i32
+    };
+    let flags = if rounded != x { FFLAG_INEXACT } else { 0 };
+    (value, flags)
+}
+
+/// fcvt.s.w and fcvt.s.wu
+pub fn from_int(value: i32, rm: RoundingMode, signed: bool) -> (u32, u8) {
+    let x = if signed {
+        value as f64
+    } else {
+        value as u32 as f64
+    };
+    round(x, 0.0, rm)
+}
@@ -0,0 +1,80 @@
+use super::{
+    common::{FP_REGISTER_OFFSET, OperandsFormat, REGISTER_COUNT},
+    isa::RegisterClass,
+};
+
+fn register_index(field: u32, class: RegisterClass) -> usize {
+    match class {
+        RegisterClass::Integer => field as usize,
+        RegisterClass::Float => FP_REGISTER_OFFSET + field as usize,
+        // x0 is never forwarded, so unused fields don't cause false hazards
+        RegisterClass::Unused => 0,
+    }
+}
+
+pub fn extract_ftype(
+    instruction: u32,
+    registers: &[i64; REGISTER_COUNT],
+    rd: RegisterClass,
+    r1: RegisterClass,
+    r2: RegisterClass,
+) -> OperandsFormat {
+    let r1 = register_index((instruction >> 15) & 0x1f, r1);
+    let r2 = register_index((instruction >> 20) & 0x1f, r2);
+
+    OperandsFormat::Ftype {
+        rd: register_index((instruction >> 7) & 0x1f, rd),
+        r1_val: registers[r1],
+        r2_val: registers[r2],
+        rm: ((instruction >> 12) & 0x7) as u8,
+        r1,
+        r2,
+    }
+}
+
+pub fn extract_r4type(instruction: u32, registers: &[i64; REGISTER_COUNT]) -> OperandsFormat {
+    let r1 = FP_REGISTER_OFFSET + ((instruction >> 15) & 0x1f) as usize;
+    let r2 = FP_REGISTER_OFFSET + ((instruction >> 20) & 0x1f) as usize;
+    let r3 = FP_REGISTER_OFFSET + ((instruction >> 27) & 0x1f) as usize;
+
+    OperandsFormat::R4type {
+        rd: FP_REGISTER_OFFSET + ((instruction >> 7) & 0x1f) as usize,
+        r1_val: registers[r1],
+        r2_val: registers[r2],
+        r3_val: registers[r3],
+        rm: ((instruction >> 12) & 0x7) as u8,
+        r1,
+        r2,
+        r3,
+    }
+}
+
+// flw, an I-type load with a floating point destination
+pub fn extract_flw(instruction: u32, registers: &[i64; REGISTER_COUNT]) -> OperandsFormat {
+    let r1 = ((instruction >> 15) & 0x1f) as usize;
+
+    OperandsFormat::Itype {
+        rd: FP_REGISTER_OFFSET + ((instruction >> 7) & 0x1f) as usize,
+        r1_val: registers[r1],
+        imm: (instruction as i32) >> 20,
+        r1,
+    }
+}
+
+// fsw, an S-type store of a floating point register
+pub fn extract_fsw(instruction: u32, registers: &[i64; REGISTER_COUNT]) -> OperandsFormat {
+    let r1 = ((instruction >> 15) & 0x1f) as usize;
+    let r2 = FP_REGISTER_OFFSET + ((instruction >> 20) & 0x1f) as usize;
+
+    let imm_11_5 = ((instruction >> 25) & 0x7f) as i32;
+    let imm_4_0 = ((instruction >> 7) & 0x1f) as i32;
+    let imm = (((imm_11_5 << 5) | imm_4_0) << 20) >> 20;
+
+    OperandsFormat::Stype {
+        r1_val: registers[r1],
+        r2_val: registers[r2],
+        imm,
+        r1,
+        r2,
+    }
+}
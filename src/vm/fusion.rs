@@ -0,0 +1,135 @@
+// Macro-op fusion of common instruction pairs into one pipeline slot. The tail overwrites the
+// head's result or writes nothing, so a pair still writes a single register.
+
+const OPCODE_LUI: u32 = 0b0110111;
+const OPCODE_AUIPC: u32 = 0b0010111;
+const OPCODE_OP_IMM: u32 = 0b0010011;
+const OPCODE_JALR: u32 = 0b1100111;
+const OPCODE_BRANCH: u32 = 0b1100011;
+const FUNCT3_ADDI: u32 = 0b000;
+const FUNCT3_SLLI: u32 = 0b001;
+const FUNCT3_SRLI: u32 = 0b101;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FusedPair {
+    /// `lui rd, hi` `addi rd, rd, lo`, a 32 bit constant.
+    LuiAddi,
+    /// `auipc rd, hi` `jalr rd, lo(rd)` or `jalr x0, lo(rd)`, a far call or tail call.
+    AuipcJalr,
+    /// `slli rd, rs, n` `srli rd, rd, n`, a zero-extension.
+    SlliSrli,
+    /// `addi rd, x0, imm` and a branch comparing `rd` against it.
+    LiBranch,
+}
+
+/// The pairs decode fuses, the VM starts out without fusion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FusionRules {
+    pub lui_addi: bool,
+    pub auipc_jalr: bool,
+    pub slli_srli: bool,
+    pub li_branch: bool,
+}
+
+impl FusionRules {
+    /// Fuses every pair.
+    pub fn all() -> Self {
+        Self {
+            lui_addi: true,
+            auipc_jalr: true,
+            slli_srli: true,
+            li_branch: true,
+        }
+    }
+
+    pub fn allows(&self, pair: FusedPair) -> bool {
+        match pair {
+            FusedPair::LuiAddi => self.lui_addi,
+            FusedPair::AuipcJalr => self.auipc_jalr,
+            FusedPair::SlliSrli => self.slli_srli,
+            FusedPair::LiBranch => self.li_branch,
+        }
+    }
+
+    /// Whether `instruction` can start a pair the rules allow.
+    pub fn can_head(&self, instruction: u32) -> bool {
+        head_of(instruction).is_some_and(|x| self.allows(x))
+    }
+
+    /// The pair `head` and the `tail` right behind it fuse into.
+    pub fn fuse(&self, head: u32, tail: u32) -> Option<FusedPair> {
+        let pair = head_of(head).filter(|&x| self.allows(x))?;
+        let field = |instruction: u32, shift: u32| (instruction >> shift) & 0x1f;
+        let rd = field(head, 7);
+        let (tail_rd, rs1, rs2) = (field(tail, 7), field(tail, 15), field(tail, 20));
+        let (opcode, funct3) = (tail & 0x7f, (tail >> 12) & 0x7);
+        let lines_up = match pair {
+            FusedPair::LuiAddi => opcode == OPCODE_OP_IMM && funct3 == FUNCT3_ADDI && tail_rd == rd && rs1 == rd,
+            FusedPair::AuipcJalr => {
+                opcode == OPCODE_JALR && funct3 == 0 && rs1 == rd && (tail_rd == rd || tail_rd == 0)
+            }
+            // both shift by the same amount, the 6 bit shamt of RV64
+            FusedPair::SlliSrli => {
+                opcode == OPCODE_OP_IMM
+                    && funct3 == FUNCT3_SRLI
+                    && tail >> 26 == 0
+                    && (tail >> 20) & 0x3f == (head >> 20) & 0x3f
+                    && tail_rd == rd
+                    && rs1 == rd
+            }
+            // funct3 2 and 3 aren't branches
+            FusedPair::LiBranch => opcode == OPCODE_BRANCH && !matches!(funct3, 2 | 3) && (rs1 == rd || rs2 == rd),
+        };
+        lines_up.then_some(pair)
+    }
+}
+
+// the pair `instruction` can start, its rd must be a register other than x0
+fn head_of(instruction: u32) -> Option<FusedPair> {
+    let rd = (instruction >> 7) & 0x1f;
+    let rs1 = (instruction >> 15) & 0x1f;
+    let funct3 = (instruction >> 12) & 0x7;
+    if rd == 0 {
+        return None;
+    }
+    match instruction & 0x7f {
+        OPCODE_LUI => Some(FusedPair::LuiAddi),
+        OPCODE_AUIPC => Some(FusedPair::AuipcJalr),
+        OPCODE_OP_IMM if funct3 == FUNCT3_SLLI && instruction >> 26 == 0 => Some(FusedPair::SlliSrli),
+        OPCODE_OP_IMM if funct3 == FUNCT3_ADDI && rs1 == 0 => Some(FusedPair::LiBranch),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FusedPair, FusionRules};
+
+    #[test]
+    fn test_fused_pairs() {
+        let rules = FusionRules::all();
+        assert_eq!(rules.fuse(0x1234_50b7, 0x6780_8093), Some(FusedPair::LuiAddi)); // LUI x1; ADDI x1, x1, 0x678
+        assert_eq!(rules.fuse(0x0000_0097, 0x0100_80e7), Some(FusedPair::AuipcJalr)); // AUIPC ra, 0; JALR ra, 16(ra)
+        assert_eq!(rules.fuse(0x0000_0317, 0x0103_0067), Some(FusedPair::AuipcJalr)); // AUIPC x6, 0; JALR x0, 16(x6)
+        assert_eq!(rules.fuse(0x0200_9113, 0x0201_5113), Some(FusedPair::SlliSrli)); // SLLI x2, x1, 32; SRLI x2, x2, 32
+        assert_eq!(rules.fuse(0x0050_0113, 0xfe20_9ee3), Some(FusedPair::LiBranch)); // ADDI x2, x0, 5; BNE x1, x2, -4
+
+        // the tail has to read the result of the head and overwrite it, and an ADDI only heads a pair
+        // when it loads a constant
+        assert_eq!(rules.fuse(0x1234_50b7, 0x6780_8113), None); // LUI x1, 0x12345; ADDI x2, x1, 0x678
+        assert_eq!(rules.fuse(0x0000_0097, 0x0103_00e7), None); // AUIPC ra, 0; JALR ra, 16(x6)
+        assert_eq!(rules.fuse(0x0200_9113, 0x0101_5113), None); // SLLI x2, x1, 32; SRLI x2, x2, 16
+        assert_eq!(rules.fuse(0x0050_0113, 0xfe30_9ee3), None); // ADDI x2, x0, 5; BNE x1, x3, -4
+        assert_eq!(rules.fuse(0x0051_0113, 0xfe20_9ee3), None); // ADDI x2, x2, 5; BNE x1, x2, -4
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules = FusionRules { li_branch: true, ..FusionRules::default() };
+        assert!(rules.can_head(0x0050_0113)); // ADDI x2, x0, 5
+        assert!(!rules.can_head(0x1234_50b7)); // LUI x1, 0x12345
+        assert!(!rules.can_head(0x0000_0037)); // LUI x0, 0
+        assert_eq!(rules.fuse(0x1234_50b7, 0x6780_8093), None);
+        assert!(!FusionRules::default().can_head(0x0050_0113));
+    }
+}
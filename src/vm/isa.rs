@@ -0,0 +1,944 @@
+use crate::inst::*;
+
+use super::{
+    atypes::extract_atype,
+    bitmanip::or_combine_bytes,
+    btypes::extract_btype,
+    common::{
+        AtomicOperation, CsrAccess, EXMEM, ExecuteResult, IDEX, InstructionDefinition, MemoryOperation,
+        MemoryRange, OperandsFormat, TrapType, VectorArithmetic, VectorOperation,
+    },
+    crypto::{
+        aes32dsi, aes32dsmi, aes32esi, aes32esmi, sha256sig0, sha256sig1, sha256sum0, sha256sum1, sha512sig0h,
+        sha512sig0l, sha512sig1h, sha512sig1l, sha512sum0r, sha512sum1r,
+    },
+    csrtypes::{csr_result, extract_csrtype, extract_csrtype_immediate},
+    float::{self, RoundingMode},
+    ftypes::{extract_flw, extract_fsw, extract_ftype, extract_r4type},
+    itypes::extract_itype,
+    jtypes::extract_jtype,
+    mtypes::{
+        divide, divide_unsigned, divide_unsigned_word, divide_word, remainder, remainder_unsigned,
+        remainder_unsigned_word, remainder_word,
+    },
+    profile::Extension,
+    rtypes::{extract_rtype, extract_rtype_byte_select},
+    stypes::extract_stype,
+    utypes::extract_utype,
+    vtypes::{extract_vtype, vector_result},
+};
+
+pub use super::common::VectorSource;
+
+/// Which register file an operand field refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterClass {
+    Integer,
+    Float,
+    /// The field is fixed by the encoding or ignored.
+    Unused,
+}
+
+/// Where the operand fields of an instruction are and how it is written in assembly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `rd, rs1, rs2`
+    R,
+    /// `rd, rs1, rs2, bs`, the byte select of the aes32 instructions in bits 31:30.
+    ByteSelect,
+    /// `rd, rs1`, any other field is fixed by the encoding.
+    Unary,
+    /// `rd, rs1, imm`
+    I,
+    /// `rd, rs1, shamt`, the shift amount is 5 bits wide on RV32 and for the `*w` shifts, 6 bits
+    /// for the RV64 shifts.
+    Shift { shamt_bits: u32 },
+    /// `rd, imm(rs1)`, the loads and `jalr`.
+    Load { rd: RegisterClass },
+    /// `rs2, imm(rs1)`
+    Store { rs2: RegisterClass },
+    /// `rs1, rs2, offset`
+    Branch,
+    /// `rd, imm`, the upper 20 bits.
+    U,
+    /// `rd, offset`
+    J,
+    /// No operands.
+    System,
+    /// `pred, succ`, the sets of `iorw` accesses ordered before and after the fence.
+    Fence,
+    /// `rd, csr, rs1`
+    Csr,
+    /// `rd, csr, uimm`, the immediate sits in the rs1 field.
+    CsrImmediate,
+    /// OP-FP, the `Unused` fields are left out of the assembly.
+    Float {
+        rd: RegisterClass,
+        rs1: RegisterClass,
+        rs2: RegisterClass,
+    },
+    /// `rd, rs1, rs2, rs3`, all floating point.
+    R4,
+    /// `rd, (rs1)`
+    LoadReserved,
+    /// `rd, rs2, (rs1)`
+    Atomic,
+    /// `rd, rs1, vtype`, the vtype is written like `e32, m1, ta, ma` or as a number.
+    VectorConfig,
+    /// `vd, (rs1)`, or `vd, (rs1), rs2` for the strided accesses. The stores have vs3 in the vd
+    /// field. Like all vector formats a masked instruction ends with `v0.t`.
+    VectorMemory { strided: bool },
+    /// `vd, vs2, vs1`, `vd, vs2, rs1` or `vd, vs2, simm5`.
+    VectorArithmetic { source: VectorSource },
+}
+
+impl Format {
+    /// The immediate of `instruction` the way assembly writes it. The csr formats return the csr
+    /// number, formats without an immediate return 0.
+    pub fn immediate(self, instruction: u32) -> i32 {
+        match self {
+            Format::I | Format::Load { .. } => (instruction as i32) >> 20,
+            Format::Shift { shamt_bits } => ((instruction >> 20) & ((1 << shamt_bits) - 1)) as i32,
+            Format::Store { .. } => (((instruction >> 25) << 5 | (instruction >> 7) & 0x1f) as i32) << 20 >> 20,
+            Format::Branch => {
+                let imm = (instruction >> 31) << 12
+                    | ((instruction >> 7) & 0x1) << 11
+                    | ((instruction >> 25) & 0x3f) << 5
+                    | ((instruction >> 8) & 0xf) << 1;
+                (imm as i32) << 19 >> 19
+            }
+            Format::U => (instruction >> 12) as i32,
+            Format::J => {
+                let imm = (instruction >> 31) << 20
+                    | ((instruction >> 12) & 0xff) << 12
+                    | ((instruction >> 20) & 0x1) << 11
+                    | ((instruction >> 21) & 0x3ff) << 1;
+                (imm as i32) << 11 >> 11
+            }
+            Format::Csr | Format::CsrImmediate => (instruction >> 20) as i32,
+            Format::ByteSelect => (instruction >> 30) as i32,
+            Format::VectorConfig => ((instruction >> 20) & 0x7ff) as i32,
+            Format::VectorArithmetic { source: VectorSource::Immediate } => (instruction as i32) << 12 >> 27,
+            _ => 0,
+        }
+    }
+
+    /// The instruction bits holding `immediate`, the inverse of `immediate`.
+    pub fn encode_immediate(self, immediate: i32) -> u32 {
+        let imm = immediate as u32;
+        match self {
+            Format::I | Format::Load { .. } | Format::Csr | Format::CsrImmediate => (imm & 0xfff) << 20,
+            Format::Shift { shamt_bits } => (imm & ((1 << shamt_bits) - 1)) << 20,
+            Format::Store { .. } => ((imm >> 5) & 0x7f) << 25 | (imm & 0x1f) << 7,
+            Format::Branch => {
+                ((imm >> 12) & 0x1) << 31
+                    | ((imm >> 5) & 0x3f) << 25
+                    | ((imm >> 1) & 0xf) << 8
+                    | ((imm >> 11) & 0x1) << 7
+            }
+            Format::U => (imm & 0xfffff) << 12,
+            Format::J => {
+                ((imm >> 20) & 0x1) << 31
+                    | ((imm >> 1) & 0x3ff) << 21
+                    | ((imm >> 11) & 0x1) << 20
+                    | ((imm >> 12) & 0xff) << 12
+            }
+            Format::ByteSelect => (imm & 0x3) << 30,
+            Format::VectorConfig => (imm & 0x7ff) << 20,
+            Format::VectorArithmetic { source: VectorSource::Immediate } => (imm & 0x1f) << 15,
+            _ => 0,
+        }
+    }
+
+    /// The immediates the format can encode, offsets also have to be even.
+    pub fn immediate_range(self) -> (i64, i64) {
+        match self {
+            Format::I | Format::Load { .. } | Format::Store { .. } => (-2048, 2047),
+            Format::Shift { shamt_bits } => (0, (1 << shamt_bits) - 1),
+            Format::Branch => (-4096, 4094),
+            Format::U => (0, 0xfffff),
+            Format::J => (-(1 << 20), (1 << 20) - 2),
+            Format::Csr | Format::CsrImmediate => (0, 0xfff),
+            Format::ByteSelect => (0, 3),
+            Format::VectorConfig => (0, 0x7ff),
+            Format::VectorArithmetic { source: VectorSource::Immediate } => (-16, 15),
+            _ => (0, 0),
+        }
+    }
+}
+
+/// One row of the instruction table.
+#[derive(Clone)]
+pub struct Instruction {
+    pub mnemonic: &'static str,
+    pub mask: u32,
+    pub match_val: u32,
+    /// `None` for the instructions of the base isa.
+    pub extension: Option<Extension>,
+    /// `Some` for the rows that only exist for one register width, either 32 or 64.
+    pub xlen: Option<u32>,
+    pub format: Format,
+    /// Cycles spent in the execute stage.
+    pub latency: usize,
+    /// Charged to `VM::energy` every time the instruction executes.
+    pub energy: u64,
+    pub(super) definition: InstructionDefinition,
+}
+
+impl Instruction {
+    pub fn matches(&self, instruction: u32) -> bool {
+        instruction & self.mask == self.match_val
+    }
+}
+
+/// The row whose encoding matches `instruction`, whether its extension is enabled or not. Where
+/// RV32 and RV64 have separate rows with the same encoding the RV32 one is returned.
+pub fn find(instruction: u32) -> Option<&'static Instruction> {
+    INSTRUCTIONS.iter().find(|x| x.matches(instruction))
+}
+
+/// The row of `mnemonic` that exists for the register width `xlen`.
+pub fn find_mnemonic(mnemonic: &str, xlen: u32) -> Option<&'static Instruction> {
+    INSTRUCTIONS
+        .iter()
+        .find(|x| x.mnemonic == mnemonic && x.xlen.is_none_or(|x| x == xlen))
+}
+
+/// The csrs the VM implements, by their assembly name.
+pub const CSR_NAMES: [(&str, u16); 8] = [
+    ("fflags", CSR_FFLAGS),
+    ("frm", CSR_FRM),
+    ("fcsr", CSR_FCSR),
+    ("cycle", CSR_CYCLE),
+    ("cycleh", CSR_CYCLEH),
+    ("vl", CSR_VL),
+    ("vtype", CSR_VTYPE),
+    ("vlenb", CSR_VLENB),
+];
+
+/// The rounding modes an `rm` field can name in assembly, `dyn` (0b111) is left out.
+pub const ROUNDING_MODE_NAMES: [&str; 5] = ["rne", "rtz", "rdn", "rup", "rmm"];
+
+/// The element widths of a vtype in assembly, indexed by vsew.
+pub const SEW_NAMES: [&str; 4] = ["e8", "e16", "e32", "e64"];
+
+/// The register group sizes of a vtype in assembly, indexed by vlmul. 0b100 is reserved.
+pub const LMUL_NAMES: [&str; 8] = ["m1", "m2", "m4", "m8", "", "mf8", "mf4", "mf2"];
+
+fn write_rd(id_ex: &IDEX, rd: usize, value: i64, fflags: u8) -> ExecuteResult {
+    ExecuteResult {
+        ex_mem: EXMEM {
+            rd: Some(rd),
+            calculation_result: value,
+            memory_operation: None,
+            operands: id_ex.operands.clone(),
+        },
+        flush: false,
+        new_pc: None,
+        trap_type: None,
+        fflags,
+        csr_operation: None,
+    }
+}
+
+// loads, stores and atomics compute their address here, the access happens in the memory stage
+fn memory_access(id_ex: &IDEX, rd: Option<usize>, address: i64) -> ExecuteResult {
+    ExecuteResult {
+        ex_mem: EXMEM {
+            rd,
+            calculation_result: address,
+            memory_operation: id_ex.memory_operation.clone(),
+            operands: id_ex.operands.clone(),
+        },
+        flush: false,
+        new_pc: None,
+        trap_type: None,
+        fflags: 0,
+        csr_operation: None,
+    }
+}
+
+// fetch already follows the predicted path, a wrong prediction flushes whatever was fetched behind
+// the branch or jump and redirects fetch
+fn control_transfer(id_ex: &IDEX, rd: Option<usize>, new_pc: Option<usize>) -> ExecuteResult {
+    let next_pc = new_pc.unwrap_or(id_ex.address.wrapping_add(4));
+    let mispredicted = next_pc != id_ex.predicted_pc;
+    ExecuteResult {
+        ex_mem: EXMEM {
+            rd,
+            calculation_result: id_ex.address.wrapping_add(4) as i64,
+            memory_operation: None,
+            operands: id_ex.operands.clone(),
+        },
+        flush: mispredicted,
+        new_pc: mispredicted.then_some(next_pc),
+        trap_type: None,
+        fflags: 0,
+        csr_operation: None,
+    }
+}
+
+// refetches the next instruction, so it sees every store that executed before the fence.i. The
+// VM drops the lines of the instruction cache too
+fn refetch(id_ex: &IDEX) -> ExecuteResult {
+    ExecuteResult {
+        ex_mem: EXMEM {
+            rd: None,
+            calculation_result: 0,
+            memory_operation: None,
+            operands: id_ex.operands.clone(),
+        },
+        flush: true,
+        new_pc: Some(id_ex.address.wrapping_add(4)),
+        trap_type: Some(TrapType::FenceI),
+        fflags: 0,
+        csr_operation: None,
+    }
+}
+
+fn trap(id_ex: &IDEX, trap_type: TrapType) -> ExecuteResult {
+    // wfi resumes with the next instruction once it wakes up
+    let new_pc = matches!(trap_type, TrapType::Wfi).then(|| id_ex.address.wrapping_add(4));
+    ExecuteResult {
+        ex_mem: EXMEM {
+            rd: None,
+            calculation_result: 0,
+            memory_operation: None,
+            operands: id_ex.operands.clone(),
+        },
+        flush: true,
+        new_pc,
+        trap_type: Some(trap_type),
+        fflags: 0,
+        csr_operation: None,
+    }
+}
+
+// decode resolved the dynamic rounding mode and made the reserved ones illegal instructions
+fn rounding_mode(rm: u8) -> RoundingMode {
+    RoundingMode::from_bits(rm).expect("reserved rounding mode past decode")
+}
+
+fn memory_operation(is_load: bool, memory_range: MemoryRange) -> Option<MemoryOperation> {
+    Some(MemoryOperation { is_load, memory_range })
+}
+
+// the float helpers return raw bits, the comparisons return integers and some results raise no
+// flags at all
+trait FloatResult {
+    fn register_value(self) -> (i32, u8);
+}
+
+impl FloatResult for (u32, u8) {
+    fn register_value(self) -> (i32, u8) {
+        (self.0 as i32, self.1)
+    }
+}
+
+impl FloatResult for (i32, u8) {
+    fn register_value(self) -> (i32, u8) {
+        self
+    }
+}
+
+impl FloatResult for u32 {
+    fn register_value(self) -> (i32, u8) {
+        (self as i32, 0)
+    }
+}
+
+impl FloatResult for i32 {
+    fn register_value(self) -> (i32, u8) {
+        (self, 0)
+    }
+}
+
+/// Generates `INSTRUCTIONS` from one row per instruction:
+///
+/// `mnemonic mask match extension [rv32|rv64] latency energy format(arguments);`
+///
+/// The format decides how the operand fields are decoded and what its arguments are, usually the
+/// semantics as a closure over the operand values. `R`, `Unary`, `I` and `Shift` compute on the
+/// full 64 bit register values, their `W` variants on the low 32 bits with the result sign-extended
+/// like the RV64 `*w` instructions. Rows without an xlen serve both widths, the VM sign-extends
+/// every RV32 result from bit 31. The vector rows take their latency for every element, the VM
+/// performs them on its vector registers.
+macro_rules! instruction_table {
+    (@extension Base) => { None };
+    (@extension $extension:ident) => { Some(Extension::$extension) };
+
+    (@xlen) => { None };
+    (@xlen rv32) => { Some(32) };
+    (@xlen rv64) => { Some(64) };
+
+    (@format R($($arguments:tt)*)) => { Format::R };
+    (@format RW($($arguments:tt)*)) => { Format::R };
+    (@format ByteSelect($($arguments:tt)*)) => { Format::ByteSelect };
+    (@format Unary($($arguments:tt)*)) => { Format::Unary };
+    (@format UnaryW($($arguments:tt)*)) => { Format::Unary };
+    (@format I($($arguments:tt)*)) => { Format::I };
+    (@format IW($($arguments:tt)*)) => { Format::I };
+    (@format Shift($($arguments:tt)*)) => { Format::Shift { shamt_bits: 6 } };
+    (@format ShiftW($($arguments:tt)*)) => { Format::Shift { shamt_bits: 5 } };
+    (@format Load($($arguments:tt)*)) => { Format::Load { rd: RegisterClass::Integer } };
+    (@format FloatLoad) => { Format::Load { rd: RegisterClass::Float } };
+    (@format Jalr) => { Format::Load { rd: RegisterClass::Integer } };
+    (@format Store($($arguments:tt)*)) => { Format::Store { rs2: RegisterClass::Integer } };
+    (@format FloatStore) => { Format::Store { rs2: RegisterClass::Float } };
+    (@format Branch($($arguments:tt)*)) => { Format::Branch };
+    (@format U($($arguments:tt)*)) => { Format::U };
+    (@format J) => { Format::J };
+    (@format System($($arguments:tt)*)) => { Format::System };
+    (@format Fence) => { Format::Fence };
+    (@format FenceI) => { Format::System };
+    (@format Csr($($arguments:tt)*)) => { Format::Csr };
+    (@format CsrImmediate($($arguments:tt)*)) => { Format::CsrImmediate };
+    (@format Float($rd:ident, $rs1:ident, $rs2:ident, $($semantics:tt)*)) => {
+        Format::Float {
+            rd: RegisterClass::$rd,
+            rs1: RegisterClass::$rs1,
+            rs2: RegisterClass::$rs2,
+        }
+    };
+    (@format R4($($arguments:tt)*)) => { Format::R4 };
+    (@format LoadReserved) => { Format::LoadReserved };
+    (@format Atomic($($arguments:tt)*)) => { Format::Atomic };
+    (@format SetVl) => { Format::VectorConfig };
+    (@format VectorLoad($width:literal, $strided:literal)) => { Format::VectorMemory { strided: $strided } };
+    (@format VectorStore($width:literal, $strided:literal)) => { Format::VectorMemory { strided: $strided } };
+    (@format Vector($operation:ident, $source:ident)) => {
+        Format::VectorArithmetic { source: VectorSource::$source }
+    };
+    (@format VectorReduction($operation:ident)) => { Format::VectorArithmetic { source: VectorSource::Vector } };
+
+    // (operands, memory operation, execute) of a decoded instruction
+    (@decode $instruction:ident, $registers:ident, R($semantics:expr)) => {
+        (extract_rtype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i64, i64) -> i64 = $semantics;
+            let Some(OperandsFormat::Rtype { rd, r1_val, r2_val, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val, *r2_val), 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, RW($semantics:expr)) => {
+        (extract_rtype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i32, i32) -> i32 = $semantics;
+            let Some(OperandsFormat::Rtype { rd, r1_val, r2_val, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val as i32, *r2_val as i32) as i64, 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, ByteSelect($semantics:expr)) => {
+        (
+            extract_rtype_byte_select($instruction, $registers, |instruction, a, b| {
+                let semantics: fn(u32, i32, i32) -> i32 = $semantics;
+                semantics(instruction >> 30, a, b)
+            }),
+            None,
+            |id_ex| {
+                let Some(OperandsFormat::Custom { rd, r1_val, r2_val, instruction, execute, .. }) = &id_ex.operands
+                else {
+                    unreachable!()
+                };
+                write_rd(id_ex, *rd, execute(*instruction, *r1_val as i32, *r2_val as i32) as i64, 0)
+            },
+        )
+    };
+    (@decode $instruction:ident, $registers:ident, Unary($semantics:expr)) => {
+        (extract_itype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i64) -> i64 = $semantics;
+            let Some(OperandsFormat::Itype { rd, r1_val, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val), 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, UnaryW($semantics:expr)) => {
+        (extract_itype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i32) -> i32 = $semantics;
+            let Some(OperandsFormat::Itype { rd, r1_val, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val as i32) as i64, 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, I($semantics:expr)) => {
+        (extract_itype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i64, i64) -> i64 = $semantics;
+            let Some(OperandsFormat::Itype { rd, r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val, *imm as i64), 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, IW($semantics:expr)) => {
+        (extract_itype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i32, i32) -> i32 = $semantics;
+            let Some(OperandsFormat::Itype { rd, r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val as i32, *imm) as i64, 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, Shift($semantics:expr)) => {
+        (extract_itype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i64, u32) -> i64 = $semantics;
+            let Some(OperandsFormat::Itype { rd, r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val, (imm & 0x3f) as u32), 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, ShiftW($semantics:expr)) => {
+        (extract_itype($instruction, $registers), None, |id_ex| {
+            let semantics: fn(i32, u32) -> i32 = $semantics;
+            let Some(OperandsFormat::Itype { rd, r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(*r1_val as i32, (imm & 0x1f) as u32) as i64, 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, Load($range:ident)) => {
+        (extract_itype($instruction, $registers), memory_operation(true, MemoryRange::$range), |id_ex| {
+            let Some(OperandsFormat::Itype { rd, r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            memory_access(id_ex, Some(*rd), r1_val.wrapping_add(*imm as i64))
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, FloatLoad) => {
+        (extract_flw($instruction, $registers), memory_operation(true, MemoryRange::Word), |id_ex| {
+            let Some(OperandsFormat::Itype { rd, r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            memory_access(id_ex, Some(*rd), r1_val.wrapping_add(*imm as i64))
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, Jalr) => {
+        (extract_itype($instruction, $registers), None, |id_ex| {
+            let Some(OperandsFormat::Itype { rd, r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            let target = r1_val.wrapping_add(*imm as i64) & !1;
+            control_transfer(id_ex, Some(*rd), Some(target as usize))
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, Store($range:ident)) => {
+        (extract_stype($instruction, $registers), memory_operation(false, MemoryRange::$range), |id_ex| {
+            let Some(OperandsFormat::Stype { r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            memory_access(id_ex, None, r1_val.wrapping_add(*imm as i64))
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, FloatStore) => {
+        (extract_fsw($instruction, $registers), memory_operation(false, MemoryRange::Word), |id_ex| {
+            let Some(OperandsFormat::Stype { r1_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            memory_access(id_ex, None, r1_val.wrapping_add(*imm as i64))
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, Branch($condition:expr)) => {
+        (extract_btype($instruction, $registers), None, |id_ex| {
+            let condition: fn(i64, i64) -> bool = $condition;
+            let Some(OperandsFormat::Btype { r1_val, r2_val, imm, .. }) = &id_ex.operands else { unreachable!() };
+            let new_pc = condition(*r1_val, *r2_val).then(|| id_ex.address.wrapping_add(*imm as usize));
+            control_transfer(id_ex, None, new_pc)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, U($semantics:expr)) => {
+        (extract_utype($instruction), None, |id_ex| {
+            let semantics: fn(i64, i32) -> i64 = $semantics;
+            let Some(OperandsFormat::Utype { rd, imm }) = &id_ex.operands else { unreachable!() };
+            write_rd(id_ex, *rd, semantics(id_ex.address as i64, *imm), 0)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, J) => {
+        (extract_jtype($instruction), None, |id_ex| {
+            let Some(OperandsFormat::Jtype { rd, imm }) = &id_ex.operands else { unreachable!() };
+            control_transfer(id_ex, Some(*rd), Some(id_ex.address.wrapping_add(*imm as usize)))
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, System($trap:ident)) => {
+        (extract_itype($instruction, $registers), None, |id_ex| trap(id_ex, TrapType::$trap))
+    };
+    // a single hart performs its memory accesses in order, there is nothing for fence to wait for
+    (@decode $instruction:ident, $registers:ident, Fence) => {
+        (extract_itype($instruction, $registers), None, |id_ex| control_transfer(id_ex, None, None))
+    };
+    (@decode $instruction:ident, $registers:ident, FenceI) => {
+        (extract_itype($instruction, $registers), None, refetch)
+    };
+    (@decode $instruction:ident, $registers:ident, Csr($access:ident)) => {
+        (extract_csrtype($instruction, $registers), None, |id_ex| {
+            let Some(OperandsFormat::Csrtype { rd, r1, r1_val, csr, instruction }) = &id_ex.operands else {
+                unreachable!()
+            };
+            // csrrs and csrrc with x0 only read
+            let write = matches!(CsrAccess::$access, CsrAccess::Write) || *r1 != 0;
+            csr_result(id_ex, *rd, *csr, CsrAccess::$access, *r1_val, write, *instruction)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, CsrImmediate($access:ident)) => {
+        (extract_csrtype_immediate($instruction), None, |id_ex| {
+            let Some(OperandsFormat::Csrtype { rd, r1_val, csr, instruction, .. }) = &id_ex.operands else {
+                unreachable!()
+            };
+            let write = matches!(CsrAccess::$access, CsrAccess::Write) || *r1_val != 0;
+            csr_result(id_ex, *rd, *csr, CsrAccess::$access, *r1_val, write, *instruction)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, Float($rd:ident, $rs1:ident, $rs2:ident, $semantics:expr)) => {
+        (
+            extract_ftype($instruction, $registers, RegisterClass::$rd, RegisterClass::$rs1, RegisterClass::$rs2),
+            None,
+            |id_ex| {
+                let semantics: fn(u32, u32, RoundingMode) -> _ = $semantics;
+                let Some(OperandsFormat::Ftype { rd, r1_val, r2_val, rm, .. }) = &id_ex.operands else { unreachable!() };
+                let (value, fflags) =
+                    semantics(*r1_val as u32, *r2_val as u32, rounding_mode(*rm)).register_value();
+                write_rd(id_ex, *rd, value as i64, fflags)
+            },
+        )
+    };
+    (@decode $instruction:ident, $registers:ident, R4($negate_product:literal, $negate_addend:literal)) => {
+        (extract_r4type($instruction, $registers), None, |id_ex| {
+            let Some(OperandsFormat::R4type { rd, r1_val, r2_val, r3_val, rm, .. }) = &id_ex.operands else {
+                unreachable!()
+            };
+            let (value, fflags) = float::fused_multiply_add(
+                *r1_val as u32,
+                *r2_val as u32,
+                *r3_val as u32,
+                $negate_product,
+                $negate_addend,
+                rounding_mode(*rm),
+            );
+            write_rd(id_ex, *rd, value as i32 as i64, fflags)
+        })
+    };
+    (@decode $instruction:ident, $registers:ident, LoadReserved) => {
+        instruction_table!(@decode $instruction, $registers, Atomic(LoadReserved))
+    };
+    // the atomic itself happens in the memory stage, like a load its result is only known there
+    (@decode $instruction:ident, $registers:ident, Atomic($operation:ident)) => {
+        (
+            extract_atype($instruction, $registers, AtomicOperation::$operation),
+            memory_operation(true, MemoryRange::Word),
+            |id_ex| {
+                let Some(OperandsFormat::Atype { rd, r1_val, .. }) = &id_ex.operands else { unreachable!() };
+                memory_access(id_ex, Some(*rd), *r1_val)
+            },
+        )
+    };
+
+    (@decode $instruction:ident, $registers:ident, SetVl) => {
+        (extract_vtype($instruction, $registers, VectorOperation::SetVl), None, vector_result)
+    };
+    (@decode $instruction:ident, $registers:ident, VectorLoad($width:literal, $strided:literal)) => {
+        (
+            extract_vtype($instruction, $registers, VectorOperation::Load { width: $width, strided: $strided }),
+            None,
+            vector_result,
+        )
+    };
+    (@decode $instruction:ident, $registers:ident, VectorStore($width:literal, $strided:literal)) => {
+        (
+            extract_vtype($instruction, $registers, VectorOperation::Store { width: $width, strided: $strided }),
+            None,
+            vector_result,
+        )
+    };
+    (@decode $instruction:ident, $registers:ident, Vector($operation:ident, $source:ident)) => {
+        (
+            extract_vtype(
+                $instruction,
+                $registers,
+                VectorOperation::Elementwise(VectorArithmetic::$operation, VectorSource::$source),
+            ),
+            None,
+            vector_result,
+        )
+    };
+    (@decode $instruction:ident, $registers:ident, VectorReduction($operation:ident)) => {
+        (
+            extract_vtype($instruction, $registers, VectorOperation::Reduction(VectorArithmetic::$operation)),
+            None,
+            vector_result,
+        )
+    };
+
+    ($($mnemonic:literal $mask:ident $match_val:ident $extension:ident $($xlen:ident)? $latency:literal
+        $energy:literal $format:ident $(($($arguments:tt)*))?;)*) => {
+        /// Every instruction the VM can execute, the profiles install the rows of their extensions.
+        #[allow(unused_variables)] // U, J and the csrr*i formats don't read the register file
+        pub const INSTRUCTIONS: &[Instruction] = &[$(
+            Instruction {
+                mnemonic: $mnemonic,
+                mask: $mask,
+                match_val: $match_val,
+                extension: instruction_table!(@extension $extension),
+                xlen: instruction_table!(@xlen $($xlen)?),
+                format: instruction_table!(@format $format $(($($arguments)*))?),
+                latency: $latency,
+                energy: $energy,
+                definition: InstructionDefinition {
+                    mask: $mask,
+                    match_val: $match_val,
+                    decode: |instruction, registers, address| {
+                        let (operands, memory_operation, execute): (_, _, fn(&IDEX) -> ExecuteResult) =
+                            instruction_table!(@decode instruction, registers, $format $(($($arguments)*))?);
+                        IDEX {
+                            operands: Some(operands),
+                            memory_operation,
+                            address,
+                            latency: $latency,
+                            energy: $energy,
+                            predicted_pc: address.wrapping_add(4),
+                            return_stack_change: None,
+                            booking: None,
+                            fused: None,
+                            execute,
+                        }
+                    },
+                },
+            },
+        )*];
+    };
+}
+
+instruction_table! {
+    // mnemonic   mask              match              extension xlen latency energy format
+    "add"         MASK_ADD          MATCH_ADD          Base          1 1 R(|a, b| a.wrapping_add(b));
+    "sub"         MASK_SUB          MATCH_SUB          Base          1 1 R(|a, b| a.wrapping_sub(b));
+    "xor"         MASK_XOR          MATCH_XOR          Base          1 1 R(|a, b| a ^ b);
+    "or"          MASK_OR           MATCH_OR           Base          1 1 R(|a, b| a | b);
+    "and"         MASK_AND          MATCH_AND          Base          1 1 R(|a, b| a & b);
+    "sll"         MASK_SLL          MATCH_SLL          Base     rv32 1 1 RW(|a, b| ((a as u32) << (b & 0x1f)) as i32);
+    "sll"         MASK_SLL          MATCH_SLL          Base     rv64 1 1 R(|a, b| ((a as u64) << (b & 0x3f)) as i64);
+    "srl"         MASK_SRL          MATCH_SRL          Base     rv32 1 1 RW(|a, b| ((a as u32) >> (b & 0x1f)) as i32);
+    "srl"         MASK_SRL          MATCH_SRL          Base     rv64 1 1 R(|a, b| ((a as u64) >> (b & 0x3f)) as i64);
+    "sra"         MASK_SRA          MATCH_SRA          Base     rv32 1 1 RW(|a, b| a >> (b & 0x1f));
+    "sra"         MASK_SRA          MATCH_SRA          Base     rv64 1 1 R(|a, b| a >> (b & 0x3f));
+    "slt"         MASK_SLT          MATCH_SLT          Base          1 1 R(|a, b| (a < b) as i64);
+    "sltu"        MASK_SLTU         MATCH_SLTU         Base          1 1 R(|a, b| ((a as u64) < (b as u64)) as i64);
+    "addi"        MASK_ADDI         MATCH_ADDI         Base          1 1 I(|a, imm| a.wrapping_add(imm));
+    "xori"        MASK_XORI         MATCH_XORI         Base          1 1 I(|a, imm| a ^ imm);
+    "ori"         MASK_ORI          MATCH_ORI          Base          1 1 I(|a, imm| a | imm);
+    "andi"        MASK_ANDI         MATCH_ANDI         Base          1 1 I(|a, imm| a & imm);
+    "slti"        MASK_SLTI         MATCH_SLTI         Base          1 1 I(|a, imm| (a < imm) as i64);
+    "sltiu"       MASK_SLTIU        MATCH_SLTIU        Base          1 1 I(|a, imm| ((a as u64) < (imm as u64)) as i64);
+    "slli"        MASK_SLLI_RV32    MATCH_SLLI_RV32    Base     rv32 1 1 ShiftW(|a, shamt| ((a as u32) << shamt) as i32);
+    "slli"        MASK_SLLI         MATCH_SLLI         Base     rv64 1 1 Shift(|a, shamt| ((a as u64) << shamt) as i64);
+    "srli"        MASK_SRLI_RV32    MATCH_SRLI_RV32    Base     rv32 1 1 ShiftW(|a, shamt| ((a as u32) >> shamt) as i32);
+    "srli"        MASK_SRLI         MATCH_SRLI         Base     rv64 1 1 Shift(|a, shamt| ((a as u64) >> shamt) as i64);
+    "srai"        MASK_SRAI_RV32    MATCH_SRAI_RV32    Base     rv32 1 1 ShiftW(|a, shamt| a >> shamt);
+    "srai"        MASK_SRAI         MATCH_SRAI         Base     rv64 1 1 Shift(|a, shamt| a >> shamt);
+    "lb"          MASK_LB           MATCH_LB           Base          1 2 Load(Byte);
+    "lh"          MASK_LH           MATCH_LH           Base          1 2 Load(Half);
+    "lw"          MASK_LW           MATCH_LW           Base          1 2 Load(Word);
+    "lbu"         MASK_LBU          MATCH_LBU          Base          1 2 Load(ByteUnsigned);
+    "lhu"         MASK_LHU          MATCH_LHU          Base          1 2 Load(HalfUnsigned);
+    "sb"          MASK_SB           MATCH_SB           Base          1 2 Store(Byte);
+    "sh"          MASK_SH           MATCH_SH           Base          1 2 Store(Half);
+    "sw"          MASK_SW           MATCH_SW           Base          1 2 Store(Word);
+    "beq"         MASK_BEQ          MATCH_BEQ          Base          1 1 Branch(|a, b| a == b);
+    "bne"         MASK_BNE          MATCH_BNE          Base          1 1 Branch(|a, b| a != b);
+    "blt"         MASK_BLT          MATCH_BLT          Base          1 1 Branch(|a, b| a < b);
+    "bge"         MASK_BGE          MATCH_BGE          Base          1 1 Branch(|a, b| a >= b);
+    "bltu"        MASK_BLTU         MATCH_BLTU         Base          1 1 Branch(|a, b| (a as u64) < (b as u64));
+    "bgeu"        MASK_BGEU         MATCH_BGEU         Base          1 1 Branch(|a, b| (a as u64) >= (b as u64));
+    "lui"         MASK_LUI          MATCH_LUI          Base          1 1 U(|_, imm| (imm << 12) as i64);
+    "auipc"       MASK_AUIPC        MATCH_AUIPC        Base          1 1 U(|pc, imm| pc.wrapping_add((imm << 12) as i64));
+    "jal"         MASK_JAL          MATCH_JAL          Base          1 1 J;
+    "jalr"        MASK_JALR         MATCH_JALR         Base          1 1 Jalr;
+    "ecall"       MASK_ECALL        MATCH_ECALL        Base          1 1 System(Ecall);
+    "ebreak"      MASK_EBREAK       MATCH_EBREAK       Base          1 1 System(Ebreak);
+    "wfi"         MASK_WFI          MATCH_WFI          Base          1 1 System(Wfi);
+    "fence"       MASK_FENCE        MATCH_FENCE        Base          1 1 Fence;
+
+    "lwu"         MASK_LWU          MATCH_LWU          Base     rv64 1 2 Load(WordUnsigned);
+    "ld"          MASK_LD           MATCH_LD           Base     rv64 1 2 Load(Double);
+    "sd"          MASK_SD           MATCH_SD           Base     rv64 1 2 Store(Double);
+    "addiw"       MASK_ADDIW        MATCH_ADDIW        Base     rv64 1 1 IW(|a, imm| a.wrapping_add(imm));
+    "slliw"       MASK_SLLIW        MATCH_SLLIW        Base     rv64 1 1 ShiftW(|a, shamt| ((a as u32) << shamt) as i32);
+    "srliw"       MASK_SRLIW        MATCH_SRLIW        Base     rv64 1 1 ShiftW(|a, shamt| ((a as u32) >> shamt) as i32);
+    "sraiw"       MASK_SRAIW        MATCH_SRAIW        Base     rv64 1 1 ShiftW(|a, shamt| a >> shamt);
+    "addw"        MASK_ADDW         MATCH_ADDW         Base     rv64 1 1 RW(|a, b| a.wrapping_add(b));
+    "subw"        MASK_SUBW         MATCH_SUBW         Base     rv64 1 1 RW(|a, b| a.wrapping_sub(b));
+    "sllw"        MASK_SLLW         MATCH_SLLW         Base     rv64 1 1 RW(|a, b| ((a as u32) << (b & 0x1f)) as i32);
+    "srlw"        MASK_SRLW         MATCH_SRLW         Base     rv64 1 1 RW(|a, b| ((a as u32) >> (b & 0x1f)) as i32);
+    "sraw"        MASK_SRAW         MATCH_SRAW         Base     rv64 1 1 RW(|a, b| a >> (b & 0x1f));
+
+    "mul"         MASK_MUL          MATCH_MUL          M             1 3 R(|a, b| a.wrapping_mul(b));
+    "mulh"        MASK_MULH         MATCH_MULH         M        rv32 1 3 RW(|a, b| ((a as i64 * b as i64) >> 32) as i32);
+    "mulh"        MASK_MULH         MATCH_MULH         M        rv64 1 3 R(|a, b| ((a as i128 * b as i128) >> 64) as i64);
+    "mulhsu"      MASK_MULHSU       MATCH_MULHSU       M        rv32 1 3 RW(|a, b| ((a as i64 * b as u32 as i64) >> 32) as i32);
+    "mulhsu"      MASK_MULHSU       MATCH_MULHSU       M        rv64 1 3 R(|a, b| ((a as i128 * b as u64 as i128) >> 64) as i64);
+    "mulhu"       MASK_MULHU        MATCH_MULHU        M        rv32 1 3 RW(|a, b| ((a as u32 as u64 * b as u32 as u64) >> 32) as i32);
+    "mulhu"       MASK_MULHU        MATCH_MULHU        M        rv64 1 3 R(|a, b| ((a as u64 as u128 * b as u64 as u128) >> 64) as i64);
+    "div"         MASK_DIV          MATCH_DIV          M             1 8 R(divide);
+    "divu"        MASK_DIVU         MATCH_DIVU         M        rv32 1 8 RW(divide_unsigned_word);
+    "divu"        MASK_DIVU         MATCH_DIVU         M        rv64 1 8 R(divide_unsigned);
+    "rem"         MASK_REM          MATCH_REM          M             1 8 R(remainder);
+    "remu"        MASK_REMU         MATCH_REMU         M        rv32 1 8 RW(remainder_unsigned_word);
+    "remu"        MASK_REMU         MATCH_REMU         M        rv64 1 8 R(remainder_unsigned);
+    "mulw"        MASK_MULW         MATCH_MULW         M        rv64 1 3 RW(|a, b| a.wrapping_mul(b));
+    "divw"        MASK_DIVW         MATCH_DIVW         M        rv64 1 8 RW(divide_word);
+    "divuw"       MASK_DIVUW        MATCH_DIVUW        M        rv64 1 8 RW(divide_unsigned_word);
+    "remw"        MASK_REMW         MATCH_REMW         M        rv64 1 8 RW(remainder_word);
+    "remuw"       MASK_REMUW        MATCH_REMUW        M        rv64 1 8 RW(remainder_unsigned_word);
+
+    "lr.w"        MASK_LR_W         MATCH_LR_W         A             1 3 LoadReserved;
+    "sc.w"        MASK_SC_W         MATCH_SC_W         A             1 3 Atomic(StoreConditional);
+    "amoswap.w"   MASK_AMOSWAP_W    MATCH_AMOSWAP_W    A             1 4 Atomic(Swap);
+    "amoadd.w"    MASK_AMOADD_W     MATCH_AMOADD_W     A             1 4 Atomic(Add);
+    "amoxor.w"    MASK_AMOXOR_W     MATCH_AMOXOR_W     A             1 4 Atomic(Xor);
+    "amoand.w"    MASK_AMOAND_W     MATCH_AMOAND_W     A             1 4 Atomic(And);
+    "amoor.w"     MASK_AMOOR_W      MATCH_AMOOR_W      A             1 4 Atomic(Or);
+    "amomin.w"    MASK_AMOMIN_W     MATCH_AMOMIN_W     A             1 4 Atomic(Min);
+    "amomax.w"    MASK_AMOMAX_W     MATCH_AMOMAX_W     A             1 4 Atomic(Max);
+    "amominu.w"   MASK_AMOMINU_W    MATCH_AMOMINU_W    A             1 4 Atomic(MinUnsigned);
+    "amomaxu.w"   MASK_AMOMAXU_W    MATCH_AMOMAXU_W    A             1 4 Atomic(MaxUnsigned);
+    "amocas.w"    MASK_AMOCAS_W     MATCH_AMOCAS_W     Zacas         1 4 Atomic(CompareAndSwap);
+
+    "flw"         MASK_FLW          MATCH_FLW          F             1 2 FloatLoad;
+    "fsw"         MASK_FSW          MATCH_FSW          F             1 2 FloatStore;
+    "fmadd.s"     MASK_FMADD_S      MATCH_FMADD_S      F             1 4 R4(false, false);
+    "fmsub.s"     MASK_FMSUB_S      MATCH_FMSUB_S      F             1 4 R4(false, true);
+    "fnmsub.s"    MASK_FNMSUB_S     MATCH_FNMSUB_S     F             1 4 R4(true, false);
+    "fnmadd.s"    MASK_FNMADD_S     MATCH_FNMADD_S     F             1 4 R4(true, true);
+    "fadd.s"      MASK_FADD_S       MATCH_FADD_S       F             1 2 Float(Float, Float, Float, |a, b, rm| float::add(a, b, rm));
+    "fsub.s"      MASK_FSUB_S       MATCH_FSUB_S       F             1 2 Float(Float, Float, Float, |a, b, rm| float::sub(a, b, rm));
+    "fmul.s"      MASK_FMUL_S       MATCH_FMUL_S       F             1 3 Float(Float, Float, Float, |a, b, rm| float::mul(a, b, rm));
+    "fdiv.s"      MASK_FDIV_S       MATCH_FDIV_S       F             1 8 Float(Float, Float, Float, |a, b, rm| float::div(a, b, rm));
+    "fsqrt.s"     MASK_FSQRT_S      MATCH_FSQRT_S      F             1 8 Float(Float, Float, Unused, |a, _, rm| float::sqrt(a, rm));
+    "fsgnj.s"     MASK_FSGNJ_S      MATCH_FSGNJ_S      F             1 1 Float(Float, Float, Float, |a, b, _| a & 0x7fff_ffff | b & 0x8000_0000);
+    "fsgnjn.s"    MASK_FSGNJN_S     MATCH_FSGNJN_S     F             1 1 Float(Float, Float, Float, |a, b, _| a & 0x7fff_ffff | !b & 0x8000_0000);
+    "fsgnjx.s"    MASK_FSGNJX_S     MATCH_FSGNJX_S     F             1 1 Float(Float, Float, Float, |a, b, _| a ^ b & 0x8000_0000);
+    "fmin.s"      MASK_FMIN_S       MATCH_FMIN_S       F             1 2 Float(Float, Float, Float, |a, b, _| float::min(a, b));
+    "fmax.s"      MASK_FMAX_S       MATCH_FMAX_S       F             1 2 Float(Float, Float, Float, |a, b, _| float::max(a, b));
+    "fcvt.w.s"    MASK_FCVT_W_S     MATCH_FCVT_W_S     F             1 2 Float(Integer, Float, Unused, |a, _, rm| float::to_int(a, rm, true));
+    "fcvt.wu.s"   MASK_FCVT_WU_S    MATCH_FCVT_WU_S    F             1 2 Float(Integer, Float, Unused, |a, _, rm| float::to_int(a, rm, false));
+    "fmv.x.w"     MASK_FMV_X_W      MATCH_FMV_X_W      F             1 1 Float(Integer, Float, Unused, |a, _, _| a);
+    "feq.s"       MASK_FEQ_S        MATCH_FEQ_S        F             1 2 Float(Integer, Float, Float, |a, b, _| float::eq(a, b));
+    "flt.s"       MASK_FLT_S        MATCH_FLT_S        F             1 2 Float(Integer, Float, Float, |a, b, _| float::lt(a, b));
+    "fle.s"       MASK_FLE_S        MATCH_FLE_S        F             1 2 Float(Integer, Float, Float, |a, b, _| float::le(a, b));
+    "fclass.s"    MASK_FCLASS_S     MATCH_FCLASS_S     F             1 1 Float(Integer, Float, Unused, |a, _, _| float::classify(a));
+    "fcvt.s.w"    MASK_FCVT_S_W     MATCH_FCVT_S_W     F             1 2 Float(Float, Integer, Unused, |a, _, rm| float::from_int(a as i32, rm, true));
+    "fcvt.s.wu"   MASK_FCVT_S_WU    MATCH_FCVT_S_WU    F             1 2 Float(Float, Integer, Unused, |a, _, rm| float::from_int(a as i32, rm, false));
+    "fmv.w.x"     MASK_FMV_W_X      MATCH_FMV_W_X      F             1 1 Float(Float, Integer, Unused, |a, _, _| a);
+
+    "vsetvli"     MASK_VSETVLI      MATCH_VSETVLI      V             1 1 SetVl;
+    "vle8.v"      MASK_VLE8_V       MATCH_VLE8_V       V             1 2 VectorLoad(8, false);
+    "vle16.v"     MASK_VLE16_V      MATCH_VLE16_V      V             1 2 VectorLoad(16, false);
+    "vle32.v"     MASK_VLE32_V      MATCH_VLE32_V      V             1 2 VectorLoad(32, false);
+    "vle64.v"     MASK_VLE64_V      MATCH_VLE64_V      V             1 2 VectorLoad(64, false);
+    "vlse8.v"     MASK_VLSE8_V      MATCH_VLSE8_V      V             1 2 VectorLoad(8, true);
+    "vlse16.v"    MASK_VLSE16_V     MATCH_VLSE16_V     V             1 2 VectorLoad(16, true);
+    "vlse32.v"    MASK_VLSE32_V     MATCH_VLSE32_V     V             1 2 VectorLoad(32, true);
+    "vlse64.v"    MASK_VLSE64_V     MATCH_VLSE64_V     V             1 2 VectorLoad(64, true);
+    "vse8.v"      MASK_VSE8_V       MATCH_VSE8_V       V             1 2 VectorStore(8, false);
+    "vse16.v"     MASK_VSE16_V      MATCH_VSE16_V      V             1 2 VectorStore(16, false);
+    "vse32.v"     MASK_VSE32_V      MATCH_VSE32_V      V             1 2 VectorStore(32, false);
+    "vse64.v"     MASK_VSE64_V      MATCH_VSE64_V      V             1 2 VectorStore(64, false);
+    "vsse8.v"     MASK_VSSE8_V      MATCH_VSSE8_V      V             1 2 VectorStore(8, true);
+    "vsse16.v"    MASK_VSSE16_V     MATCH_VSSE16_V     V             1 2 VectorStore(16, true);
+    "vsse32.v"    MASK_VSSE32_V     MATCH_VSSE32_V     V             1 2 VectorStore(32, true);
+    "vsse64.v"    MASK_VSSE64_V     MATCH_VSSE64_V     V             1 2 VectorStore(64, true);
+    "vadd.vv"     MASK_VADD_VV      MATCH_VADD_VV      V             1 1 Vector(Add, Vector);
+    "vadd.vx"     MASK_VADD_VX      MATCH_VADD_VX      V             1 1 Vector(Add, Scalar);
+    "vadd.vi"     MASK_VADD_VI      MATCH_VADD_VI      V             1 1 Vector(Add, Immediate);
+    "vmul.vv"     MASK_VMUL_VV      MATCH_VMUL_VV      V             1 3 Vector(Mul, Vector);
+    "vmul.vx"     MASK_VMUL_VX      MATCH_VMUL_VX      V             1 3 Vector(Mul, Scalar);
+    "vmin.vv"     MASK_VMIN_VV      MATCH_VMIN_VV      V             1 1 Vector(Min, Vector);
+    "vmin.vx"     MASK_VMIN_VX      MATCH_VMIN_VX      V             1 1 Vector(Min, Scalar);
+    "vminu.vv"    MASK_VMINU_VV     MATCH_VMINU_VV     V             1 1 Vector(MinUnsigned, Vector);
+    "vminu.vx"    MASK_VMINU_VX     MATCH_VMINU_VX     V             1 1 Vector(MinUnsigned, Scalar);
+    "vmax.vv"     MASK_VMAX_VV      MATCH_VMAX_VV      V             1 1 Vector(Max, Vector);
+    "vmax.vx"     MASK_VMAX_VX      MATCH_VMAX_VX      V             1 1 Vector(Max, Scalar);
+    "vmaxu.vv"    MASK_VMAXU_VV     MATCH_VMAXU_VV     V             1 1 Vector(MaxUnsigned, Vector);
+    "vmaxu.vx"    MASK_VMAXU_VX     MATCH_VMAXU_VX     V             1 1 Vector(MaxUnsigned, Scalar);
+    "vredsum.vs"  MASK_VREDSUM_VS   MATCH_VREDSUM_VS   V             1 1 VectorReduction(Add);
+    "vredmin.vs"  MASK_VREDMIN_VS   MATCH_VREDMIN_VS   V             1 1 VectorReduction(Min);
+    "vredminu.vs" MASK_VREDMINU_VS  MATCH_VREDMINU_VS  V             1 1 VectorReduction(MinUnsigned);
+    "vredmax.vs"  MASK_VREDMAX_VS   MATCH_VREDMAX_VS   V             1 1 VectorReduction(Max);
+    "vredmaxu.vs" MASK_VREDMAXU_VS  MATCH_VREDMAXU_VS  V             1 1 VectorReduction(MaxUnsigned);
+
+    "czero.eqz"   MASK_CZERO_EQZ    MATCH_CZERO_EQZ    Zicond        1 1 R(|a, b| if b == 0 { 0 } else { a });
+    "czero.nez"   MASK_CZERO_NEZ    MATCH_CZERO_NEZ    Zicond        1 1 R(|a, b| if b != 0 { 0 } else { a });
+
+    "csrrw"       MASK_CSRRW        MATCH_CSRRW        Zicsr         1 1 Csr(Write);
+    "csrrs"       MASK_CSRRS        MATCH_CSRRS        Zicsr         1 1 Csr(Set);
+    "csrrc"       MASK_CSRRC        MATCH_CSRRC        Zicsr         1 1 Csr(Clear);
+    "csrrwi"      MASK_CSRRWI       MATCH_CSRRWI       Zicsr         1 1 CsrImmediate(Write);
+    "csrrsi"      MASK_CSRRSI       MATCH_CSRRSI       Zicsr         1 1 CsrImmediate(Set);
+    "csrrci"      MASK_CSRRCI       MATCH_CSRRCI       Zicsr         1 1 CsrImmediate(Clear);
+
+    "fence.i"     MASK_FENCE_I      MATCH_FENCE_I      Zifencei      1 1 FenceI;
+
+    "sh1add"      MASK_SH1ADD       MATCH_SH1ADD       Zba           1 1 R(|a, b| (a << 1).wrapping_add(b));
+    "sh2add"      MASK_SH2ADD       MATCH_SH2ADD       Zba           1 1 R(|a, b| (a << 2).wrapping_add(b));
+    "sh3add"      MASK_SH3ADD       MATCH_SH3ADD       Zba           1 1 R(|a, b| (a << 3).wrapping_add(b));
+
+    "andn"        MASK_ANDN         MATCH_ANDN         Zbb           1 1 R(|a, b| a & !b);
+    "orn"         MASK_ORN          MATCH_ORN          Zbb           1 1 R(|a, b| a | !b);
+    "xnor"        MASK_XNOR         MATCH_XNOR         Zbb           1 1 R(|a, b| !(a ^ b));
+    "clz"         MASK_CLZ          MATCH_CLZ          Zbb      rv32 1 1 UnaryW(|a| a.leading_zeros() as i32);
+    "ctz"         MASK_CTZ          MATCH_CTZ          Zbb      rv32 1 1 UnaryW(|a| a.trailing_zeros() as i32);
+    "cpop"        MASK_CPOP         MATCH_CPOP         Zbb      rv32 1 1 UnaryW(|a| a.count_ones() as i32);
+    "max"         MASK_MAX          MATCH_MAX          Zbb           1 1 R(|a, b| a.max(b));
+    "maxu"        MASK_MAXU         MATCH_MAXU         Zbb           1 1 R(|a, b| (a as u64).max(b as u64) as i64);
+    "min"         MASK_MIN          MATCH_MIN          Zbb           1 1 R(|a, b| a.min(b));
+    "minu"        MASK_MINU         MATCH_MINU         Zbb           1 1 R(|a, b| (a as u64).min(b as u64) as i64);
+    "sext.b"      MASK_SEXT_B       MATCH_SEXT_B       Zbb           1 1 Unary(|a| a as i8 as i64);
+    "sext.h"      MASK_SEXT_H       MATCH_SEXT_H       Zbb           1 1 Unary(|a| a as i16 as i64);
+    "zext.h"      MASK_ZEXT_H_RV32  MATCH_ZEXT_H_RV32  Zbb      rv32 1 1 Unary(|a| a as u16 as i64);
+    "rol"         MASK_ROL          MATCH_ROL          Zbb      rv32 1 1 RW(|a, b| a.rotate_left(b as u32 & 0x1f));
+    "ror"         MASK_ROR          MATCH_ROR          Zbb      rv32 1 1 RW(|a, b| a.rotate_right(b as u32 & 0x1f));
+    "rori"        MASK_RORI_RV32    MATCH_RORI_RV32    Zbb      rv32 1 1 ShiftW(|a, shamt| a.rotate_right(shamt));
+    "orc.b"       MASK_ORC_B        MATCH_ORC_B        Zbb      rv32 1 1 UnaryW(or_combine_bytes);
+    "rev8"        MASK_REV8_RV32    MATCH_REV8_RV32    Zbb      rv32 1 1 UnaryW(|a| a.swap_bytes());
+
+    "bclr"        MASK_BCLR         MATCH_BCLR         Zbs      rv32 1 1 RW(|a, b| a & !(1 << (b & 0x1f)));
+    "bclri"       MASK_BCLRI_RV32   MATCH_BCLRI_RV32   Zbs      rv32 1 1 ShiftW(|a, shamt| a & !(1 << shamt));
+    "bext"        MASK_BEXT         MATCH_BEXT         Zbs      rv32 1 1 RW(|a, b| (a >> (b & 0x1f)) & 1);
+    "bexti"       MASK_BEXTI_RV32   MATCH_BEXTI_RV32   Zbs      rv32 1 1 ShiftW(|a, shamt| (a >> shamt) & 1);
+    "binv"        MASK_BINV         MATCH_BINV         Zbs      rv32 1 1 RW(|a, b| a ^ (1 << (b & 0x1f)));
+    "binvi"       MASK_BINVI_RV32   MATCH_BINVI_RV32   Zbs      rv32 1 1 ShiftW(|a, shamt| a ^ (1 << shamt));
+    "bset"        MASK_BSET         MATCH_BSET         Zbs      rv32 1 1 RW(|a, b| a | (1 << (b & 0x1f)));
+    "bseti"       MASK_BSETI_RV32   MATCH_BSETI_RV32   Zbs      rv32 1 1 ShiftW(|a, shamt| a | (1 << shamt));
+
+    "aes32esi"    MASK_AES32ESI     MATCH_AES32ESI     Zkne     rv32 1 2 ByteSelect(aes32esi);
+    "aes32esmi"   MASK_AES32ESMI    MATCH_AES32ESMI    Zkne     rv32 1 2 ByteSelect(aes32esmi);
+    "aes32dsi"    MASK_AES32DSI     MATCH_AES32DSI     Zknd     rv32 1 2 ByteSelect(aes32dsi);
+    "aes32dsmi"   MASK_AES32DSMI    MATCH_AES32DSMI    Zknd     rv32 1 2 ByteSelect(aes32dsmi);
+    "sha256sig0"  MASK_SHA256SIG0   MATCH_SHA256SIG0   Zknh     rv32 1 1 UnaryW(sha256sig0);
+    "sha256sig1"  MASK_SHA256SIG1   MATCH_SHA256SIG1   Zknh     rv32 1 1 UnaryW(sha256sig1);
+    "sha256sum0"  MASK_SHA256SUM0   MATCH_SHA256SUM0   Zknh     rv32 1 1 UnaryW(sha256sum0);
+    "sha256sum1"  MASK_SHA256SUM1   MATCH_SHA256SUM1   Zknh     rv32 1 1 UnaryW(sha256sum1);
+    "sha512sig0h" MASK_SHA512SIG0H  MATCH_SHA512SIG0H  Zknh     rv32 1 1 RW(sha512sig0h);
+    "sha512sig0l" MASK_SHA512SIG0L  MATCH_SHA512SIG0L  Zknh     rv32 1 1 RW(sha512sig0l);
+    "sha512sig1h" MASK_SHA512SIG1H  MATCH_SHA512SIG1H  Zknh     rv32 1 1 RW(sha512sig1h);
+    "sha512sig1l" MASK_SHA512SIG1L  MATCH_SHA512SIG1L  Zknh     rv32 1 1 RW(sha512sig1l);
+    "sha512sum0r" MASK_SHA512SUM0R  MATCH_SHA512SUM0R  Zknh     rv32 1 1 RW(sha512sum0r);
+    "sha512sum1r" MASK_SHA512SUM1R  MATCH_SHA512SUM1R  Zknh     rv32 1 1 RW(sha512sum1r);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, INSTRUCTIONS, Instruction};
+    use crate::vm::custom::encodings_overlap;
+
+    #[test]
+    fn test_table_encodings_are_distinct() {
+        for (index, instruction) in INSTRUCTIONS.iter().enumerate() {
+            assert_eq!(instruction.match_val & !instruction.mask, 0, "{}", instruction.mnemonic);
+            // the RV32 and RV64 rows of an instruction never get installed together
+            let widths = |other: &&Instruction| instruction.xlen.zip(other.xlen).is_none_or(|(a, b)| a == b);
+            for other in INSTRUCTIONS[index + 1..].iter().filter(widths) {
+                assert!(
+                    !encodings_overlap(instruction.mask, instruction.match_val, other.mask, other.match_val),
+                    "{} overlaps {}",
+                    instruction.mnemonic,
+                    other.mnemonic
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_immediates_round_trip() {
+        let cases = [
+            (Format::I, -2048),
+            (Format::Store { rs2: super::RegisterClass::Integer }, -3),
+            (Format::Branch, -4096),
+            (Format::Branch, 2046),
+            (Format::J, -8),
+            (Format::J, (1 << 20) - 2),
+            (Format::U, 0xfffff),
+            (Format::Shift { shamt_bits: 5 }, 31),
+            (Format::Shift { shamt_bits: 6 }, 63),
+        ];
+        for (format, immediate) in cases {
+            assert_eq!(format.immediate(format.encode_immediate(immediate)), immediate, "{format:?}");
+        }
+    }
+}
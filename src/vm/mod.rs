@@ -1,8 +1,29 @@
+mod atypes;
+mod bitmanip;
+pub mod btb;
 mod btypes;
+pub mod cache;
+pub mod catalogue;
 mod common;
+pub mod cpu;
+mod crypto;
+mod csrtypes;
+pub mod custom;
+mod float;
+mod ftypes;
+pub mod fusion;
+pub mod isa;
 mod itypes;
 mod jtypes;
+mod mtypes;
+pub mod predictor;
+pub mod profile;
+mod ras;
+pub mod region;
 mod rtypes;
 mod stypes;
+pub mod units;
 mod utypes;
+mod vector;
 pub mod vm;
+mod vtypes;
@@ -0,0 +1,41 @@
+// division never traps, dividing by zero and overflowing give the results the spec defines
+macro_rules! division {
+    ($signed:ty, $unsigned:ty, $divide:ident, $divide_unsigned:ident, $remainder:ident, $remainder_unsigned:ident) => {
+        pub fn $divide(dividend: $signed, divisor: $signed) -> $signed {
+            if divisor == 0 {
+                -1
+            } else {
+                dividend.wrapping_div(divisor)
+            }
+        }
+
+        pub fn $divide_unsigned(dividend: $signed, divisor: $signed) -> $signed {
+            if divisor == 0 {
+                -1
+            } else {
+                ((dividend as $unsigned) / (divisor as $unsigned)) as $signed
+            }
+        }
+
+        pub fn $remainder(dividend: $signed, divisor: $signed) -> $signed {
+            if divisor == 0 {
+                dividend
+            } else {
+                dividend.wrapping_rem(divisor)
+            }
+        }
+
+        pub fn $remainder_unsigned(dividend: $signed, divisor: $signed) -> $signed {
+            if divisor == 0 {
+                dividend
+            } else {
+                ((dividend as $unsigned) % (divisor as $unsigned)) as $signed
+            }
+        }
+    };
+}
+
+// the full register width
+division!(i64, u64, divide, divide_unsigned, remainder, remainder_unsigned);
+// RV32 and the RV64 `*w` instructions
+division!(i32, u32, divide_word, divide_unsigned_word, remainder_word, remainder_unsigned_word);
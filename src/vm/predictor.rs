@@ -0,0 +1,319 @@
+// Branch predictors guess the direction of conditional branches at fetch, the target comes from
+// the offset encoded in the branch itself. The VM asks `predict` for every branch it fetches and
+// calls `update` once the branch executes, so a branch fetched on a wrong path and flushed never
+// trains the predictor.
+//
+// The global history only holds branches that executed. A branch fetched right behind another is
+// predicted before the outcome of the first one is known, as in a pipeline without speculative
+// history.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchSaturatingCounter {
+    StronglyNotTaken,
+    WeaklyNotTaken,
+    WeaklyTaken,
+    StronglyTaken
+}
+
+impl BranchSaturatingCounter {
+    pub fn predicts_taken(self) -> bool {
+        matches!(self, BranchSaturatingCounter::WeaklyTaken | BranchSaturatingCounter::StronglyTaken)
+    }
+
+    /// One step towards the outcome, it takes two mispredictions in a row to flip a strong state.
+    pub fn update(self, taken: bool) -> Self {
+        use BranchSaturatingCounter::*;
+        match (self, taken) {
+            (StronglyNotTaken, false) | (WeaklyNotTaken, false) => StronglyNotTaken,
+            (StronglyNotTaken, true) => WeaklyNotTaken,
+            (WeaklyNotTaken, true) | (StronglyTaken, false) => WeaklyTaken,
+            (WeaklyTaken, false) => WeaklyNotTaken,
+            (WeaklyTaken, true) | (StronglyTaken, true) => StronglyTaken,
+        }
+    }
+}
+
+pub trait BranchPredictor {
+    /// Shown when predictors are compared.
+    fn name(&self) -> &'static str;
+
+    /// Whether the branch at `address` jumping to `target` is taken.
+    fn predict(&mut self, address: usize, target: usize) -> bool;
+
+    /// Trains the predictor with the outcome of the branch at `address` once it executed.
+    fn update(&mut self, address: usize, target: usize, taken: bool);
+}
+
+/// How often the predictions of the executed branches were right. The VM counts them for the
+/// predictor it uses as a whole, so a `Tournament` doesn't report its two predictors separately and
+/// replacing the predictor starts the count over.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PredictorStats {
+    pub correct: u64,
+    pub mispredicted: u64,
+}
+
+impl PredictorStats {
+    /// The share of correct predictions, `None` before the first branch.
+    pub fn accuracy(&self) -> Option<f64> {
+        let total = self.correct + self.mispredicted;
+        (total > 0).then(|| self.correct as f64 / total as f64)
+    }
+}
+
+/// Predicts every branch not taken, fetch just continues with the next word.
+pub struct StaticNotTaken;
+
+impl BranchPredictor for StaticNotTaken {
+    fn name(&self) -> &'static str {
+        "static not-taken"
+    }
+
+    fn predict(&mut self, _address: usize, _target: usize) -> bool {
+        false
+    }
+
+    fn update(&mut self, _address: usize, _target: usize, _taken: bool) {}
+}
+
+pub struct AlwaysTaken;
+
+impl BranchPredictor for AlwaysTaken {
+    fn name(&self) -> &'static str {
+        "always taken"
+    }
+
+    fn predict(&mut self, _address: usize, _target: usize) -> bool {
+        true
+    }
+
+    fn update(&mut self, _address: usize, _target: usize, _taken: bool) {}
+}
+
+/// Backward taken, forward not taken, which gets loops right without any state.
+pub struct Btfn;
+
+impl BranchPredictor for Btfn {
+    fn name(&self) -> &'static str {
+        "backward taken, forward not taken"
+    }
+
+    fn predict(&mut self, address: usize, target: usize) -> bool {
+        target <= address
+    }
+
+    fn update(&mut self, _address: usize, _target: usize, _taken: bool) {}
+}
+
+/// A saturating counter for every branch, starting out weakly not taken.
+#[derive(Default)]
+pub struct Bimodal {
+    counters: HashMap<usize, BranchSaturatingCounter>, // by the address of the branch
+}
+
+impl Bimodal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The counter of the branch at `address`, `None` until it executed once.
+    pub fn counter(&self, address: usize) -> Option<BranchSaturatingCounter> {
+        self.counters.get(&address).copied()
+    }
+}
+
+impl BranchPredictor for Bimodal {
+    fn name(&self) -> &'static str {
+        "bimodal"
+    }
+
+    fn predict(&mut self, address: usize, _target: usize) -> bool {
+        self.counter(address).is_some_and(|x| x.predicts_taken())
+    }
+
+    fn update(&mut self, address: usize, _target: usize, taken: bool) {
+        let counter = self.counters.entry(address).or_insert(BranchSaturatingCounter::WeaklyNotTaken);
+        *counter = counter.update(taken);
+    }
+}
+
+/// The longest global history `Gshare` and `Tournament` keep, a table of a million counters.
+pub const MAX_HISTORY_BITS: u32 = 20;
+
+/// A history longer than `MAX_HISTORY_BITS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryBitsError {
+    pub history_bits: u32,
+}
+
+/// A table of `2^history_bits` saturating counters indexed by the address of the branch xor the
+/// outcomes of the last `history_bits` branches, so one branch can follow a pattern that depends
+/// on the branches before it.
+pub struct Gshare {
+    history_bits: u32,
+    history: usize, // the latest outcome in bit 0
+    counters: Vec<BranchSaturatingCounter>,
+}
+
+impl Gshare {
+    pub fn new(history_bits: u32) -> Result<Self, HistoryBitsError> {
+        if history_bits > MAX_HISTORY_BITS {
+            return Err(HistoryBitsError { history_bits });
+        }
+        Ok(Self {
+            history_bits,
+            history: 0,
+            counters: vec![BranchSaturatingCounter::WeaklyNotTaken; 1 << history_bits],
+        })
+    }
+
+    fn index(&self, address: usize) -> usize {
+        // instructions are word aligned, the low bits would leave half the table unused
+        ((address / 4) ^ self.history) & ((1 << self.history_bits) - 1)
+    }
+}
+
+impl BranchPredictor for Gshare {
+    fn name(&self) -> &'static str {
+        "gshare"
+    }
+
+    fn predict(&mut self, address: usize, _target: usize) -> bool {
+        self.counters[self.index(address)].predicts_taken()
+    }
+
+    fn update(&mut self, address: usize, _target: usize, taken: bool) {
+        let index = self.index(address);
+        self.counters[index] = self.counters[index].update(taken);
+        self.history = (self.history << 1 | taken as usize) & ((1 << self.history_bits) - 1);
+    }
+}
+
+/// Runs a bimodal and a gshare predictor side by side, a table of `2^history_bits` counters
+/// indexed by the branch address picks the one that has been right more often for the branch.
+pub struct Tournament {
+    bimodal: Bimodal,
+    gshare: Gshare,
+    choosers: Vec<BranchSaturatingCounter>, // taken picks gshare
+}
+
+impl Tournament {
+    pub fn new(history_bits: u32) -> Result<Self, HistoryBitsError> {
+        Ok(Self {
+            bimodal: Bimodal::new(),
+            gshare: Gshare::new(history_bits)?,
+            choosers: vec![BranchSaturatingCounter::WeaklyNotTaken; 1 << history_bits],
+        })
+    }
+
+    fn chooser(&self, address: usize) -> usize {
+        (address / 4) % self.choosers.len()
+    }
+}
+
+impl BranchPredictor for Tournament {
+    fn name(&self) -> &'static str {
+        "tournament"
+    }
+
+    fn predict(&mut self, address: usize, target: usize) -> bool {
+        if self.choosers[self.chooser(address)].predicts_taken() {
+            self.gshare.predict(address, target)
+        } else {
+            self.bimodal.predict(address, target)
+        }
+    }
+
+    fn update(&mut self, address: usize, target: usize, taken: bool) {
+        let bimodal = self.bimodal.predict(address, target);
+        let gshare = self.gshare.predict(address, target);
+        // the chooser only learns from branches where exactly one of them was right
+        if bimodal != gshare {
+            let chooser = self.chooser(address);
+            self.choosers[chooser] = self.choosers[chooser].update(gshare == taken);
+        }
+        self.bimodal.update(address, target, taken);
+        self.gshare.update(address, target, taken);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AlwaysTaken, Bimodal, BranchPredictor, BranchSaturatingCounter, Btfn, Gshare, HistoryBitsError,
+        MAX_HISTORY_BITS, PredictorStats, StaticNotTaken, Tournament,
+    };
+
+    // the correct predictions of the branch at 16 that jumps back to 8 with the `pattern` of
+    // outcomes, repeated `times` times
+    fn correct(predictor: &mut dyn BranchPredictor, pattern: &[bool], times: usize) -> usize {
+        let mut correct = 0;
+        for &taken in pattern.iter().cycle().take(pattern.len() * times) {
+            correct += (predictor.predict(16, 8) == taken) as usize;
+            predictor.update(16, 8, taken);
+        }
+        correct
+    }
+
+    #[test]
+    fn test_saturating_counter() {
+        use BranchSaturatingCounter::*;
+        assert_eq!(StronglyNotTaken.update(false), StronglyNotTaken);
+        assert_eq!(StronglyNotTaken.update(true), WeaklyNotTaken);
+        assert_eq!(WeaklyNotTaken.update(true), WeaklyTaken);
+        assert_eq!(WeaklyTaken.update(true), StronglyTaken);
+        assert_eq!(StronglyTaken.update(true), StronglyTaken);
+        assert_eq!(StronglyTaken.update(false), WeaklyTaken);
+        assert!(WeaklyTaken.predicts_taken());
+        assert!(!WeaklyNotTaken.predicts_taken());
+    }
+
+    #[test]
+    fn test_static_predictors() {
+        let loop_exit = [true, true, true, false];
+        assert_eq!(correct(&mut StaticNotTaken, &loop_exit, 10), 10);
+        assert_eq!(correct(&mut AlwaysTaken, &loop_exit, 10), 30);
+        assert_eq!(correct(&mut Btfn, &loop_exit, 10), 30);
+        assert!(!Btfn.predict(8, 16));
+    }
+
+    #[test]
+    fn test_bimodal() {
+        let mut bimodal = Bimodal::new();
+        assert_eq!(correct(&mut bimodal, &[true, true, true, false], 10), 29);
+        assert_eq!(bimodal.counter(16), Some(BranchSaturatingCounter::WeaklyTaken));
+        assert_eq!(bimodal.counter(20), None);
+        // alternating outcomes keep the counter flipping between the weak states
+        assert_eq!(correct(&mut Bimodal::new(), &[true, false], 20), 0);
+    }
+
+    #[test]
+    fn test_gshare_learns_patterns() {
+        // alternating outcomes, where bimodal gets none right
+        assert_eq!(correct(&mut Gshare::new(4).unwrap(), &[true, false], 20), 37);
+        // the history tells the loop exit apart from the other iterations, bimodal gets 29 right
+        assert_eq!(correct(&mut Gshare::new(4).unwrap(), &[true, true, true, false], 10), 34);
+    }
+
+    #[test]
+    fn test_tournament_picks_the_better_predictor() {
+        // bimodal is right while gshare warms up, then the chooser moves over to gshare
+        assert_eq!(correct(&mut Tournament::new(4).unwrap(), &[true, false], 20), 36);
+        assert_eq!(correct(&mut Tournament::new(4).unwrap(), &[true, true, true, false], 10), 36);
+    }
+
+    #[test]
+    fn test_history_limit() {
+        assert!(Gshare::new(MAX_HISTORY_BITS).is_ok());
+        assert_eq!(Gshare::new(MAX_HISTORY_BITS + 1).err(), Some(HistoryBitsError { history_bits: 21 }));
+        assert_eq!(Tournament::new(64).err(), Some(HistoryBitsError { history_bits: 64 }));
+    }
+
+    #[test]
+    fn test_accuracy() {
+        assert_eq!(PredictorStats::default().accuracy(), None);
+        assert_eq!(PredictorStats { correct: 3, mispredicted: 1 }.accuracy(), Some(0.75));
+    }
+}
@@ -0,0 +1,414 @@
+use std::{fmt, str::FromStr};
+
+use super::{
+    common::InstructionDefinition,
+    isa::{Instruction, INSTRUCTIONS},
+    vector::is_valid_vlen,
+};
+
+/// VLEN of a profile that doesn't name one with `zvl<N>b`.
+pub const DEFAULT_VLEN: usize = 128;
+
+/// The base integer isa a profile builds on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BaseIsa {
+    #[default]
+    Rv32I,
+    /// The embedded base, only `x0..x15` exist. Encodings naming `x16..x31` trap as illegal.
+    Rv32E,
+    /// 64 bit registers, adds `ld`, `sd`, `lwu` and the `*w` instructions that work on the low
+    /// 32 bits and sign-extend their result.
+    Rv64I,
+}
+
+impl BaseIsa {
+    pub fn integer_register_count(self) -> usize {
+        match self {
+            BaseIsa::Rv32I | BaseIsa::Rv64I => 32,
+            BaseIsa::Rv32E => 16,
+        }
+    }
+
+    /// Width of the integer registers in bits.
+    pub fn xlen(self) -> u32 {
+        match self {
+            BaseIsa::Rv32I | BaseIsa::Rv32E => 32,
+            BaseIsa::Rv64I => 64,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BaseIsa::Rv32I => "rv32i",
+            BaseIsa::Rv32E => "rv32e",
+            BaseIsa::Rv64I => "rv64i",
+        }
+    }
+}
+
+/// Extensions on top of the base isa. They can be switched on and off while the VM runs, so the
+/// game can unlock them as upgrades.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Extension {
+    /// Integer multiplication and division.
+    M,
+    /// Atomics, `lr.w`, `sc.w` and the `amo*.w` read-modify-writes.
+    A,
+    /// Single-precision floating point.
+    F,
+    /// A subset of the vector extension: `vsetvli`, unit-stride and strided loads and stores and
+    /// integer add, multiply, min, max and their reductions.
+    V,
+    /// Conditional zero, `czero.eqz` and `czero.nez`, which make selects possible without a branch.
+    Zicond,
+    /// The `csrr*` instructions.
+    Zicsr,
+    /// `fence.i`, which makes stores to instruction memory visible to fetch.
+    Zifencei,
+    /// Compare-and-swap, `amocas.w`.
+    Zacas,
+    /// Address generation, `sh1add`, `sh2add` and `sh3add`. Like the other Zb and the Zk extensions
+    /// it is only implemented for RV32.
+    Zba,
+    /// Basic bit manipulation like `clz`, `cpop`, `rev8` and the rotates.
+    Zbb,
+    /// Single-bit set, clear, invert and extract.
+    Zbs,
+    /// AES encryption, `aes32esi` and `aes32esmi`.
+    Zkne,
+    /// AES decryption, `aes32dsi` and `aes32dsmi`.
+    Zknd,
+    /// The SHA-256 and SHA-512 sigma and sum functions.
+    Zknh,
+}
+
+impl Extension {
+    /// The name of the extension in an isa string.
+    pub fn name(self) -> &'static str {
+        match self {
+            Extension::M => "m",
+            Extension::A => "a",
+            Extension::F => "f",
+            Extension::V => "v",
+            Extension::Zicond => "zicond",
+            Extension::Zicsr => "zicsr",
+            Extension::Zifencei => "zifencei",
+            Extension::Zacas => "zacas",
+            Extension::Zba => "zba",
+            Extension::Zbb => "zbb",
+            Extension::Zbs => "zbs",
+            Extension::Zkne => "zkne",
+            Extension::Zknd => "zknd",
+            Extension::Zknh => "zknh",
+        }
+    }
+
+    /// Whether the VM implements the extension on `base`, the Zb and Zk ones are only there for
+    /// RV32.
+    pub fn supports(self, base: BaseIsa) -> bool {
+        let rv32_only = matches!(
+            self,
+            Extension::Zba | Extension::Zbb | Extension::Zbs | Extension::Zkne | Extension::Zknd | Extension::Zknh
+        );
+        !rv32_only || base.xlen() == 32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsaProfileError {
+    /// The isa string doesn't start with `rv32i`, `rv32e` or `rv64i`.
+    InvalidBase(String),
+    /// A standard extension this VM can't run, like `c` or `d`, or one it only runs on RV32.
+    UnsupportedExtension(String),
+    UnknownExtension(String),
+    /// `zvl<N>b` with an N that isn't a power of two from 64 to 65536.
+    InvalidVectorLength(String),
+}
+
+/// The base isa and the extensions a rover implements on top of it. Only their instructions are
+/// installed, everything else traps as an illegal instruction.
+///
+/// Profiles are usually parsed from an isa string like `"rv32imf_zicsr_zbb"`. The length of the
+/// vector registers is given as `zvl<VLEN>b`, like `"rv32imv_zvl256b"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsaProfile {
+    base: BaseIsa,
+    extensions: Vec<Extension>, // sorted, so the isa string comes out in order
+    vlen: usize,
+}
+
+impl IsaProfile {
+    /// Only the RV32I base.
+    pub fn base() -> Self {
+        Self {
+            base: BaseIsa::Rv32I,
+            extensions: vec![],
+            vlen: DEFAULT_VLEN,
+        }
+    }
+
+    /// Changes the base isa, it fails when an extension of the profile isn't supported on it.
+    pub fn with_base(mut self, base: BaseIsa) -> Result<Self, IsaProfileError> {
+        if let Some(extension) = self.extensions.iter().find(|x| !x.supports(base)) {
+            return Err(IsaProfileError::UnsupportedExtension(extension.name().to_string()));
+        }
+        self.base = base;
+        Ok(self)
+    }
+
+    pub fn base_isa(&self) -> BaseIsa {
+        self.base
+    }
+
+    /// Sets the length of the vector registers in bits, a power of two from 64 to 65536.
+    pub fn with_vlen(mut self, vlen: usize) -> Result<Self, IsaProfileError> {
+        if !is_valid_vlen(vlen) {
+            return Err(IsaProfileError::InvalidVectorLength(format!("zvl{vlen}b")));
+        }
+        self.vlen = vlen;
+        Ok(self)
+    }
+
+    pub fn vlen(&self) -> usize {
+        self.vlen
+    }
+
+    /// Adds an extension, it fails when the extension isn't supported on the base isa.
+    pub fn with(mut self, extension: Extension) -> Result<Self, IsaProfileError> {
+        self.set(extension, true)?;
+        Ok(self)
+    }
+
+    /// Enables or disables an extension. Enabling one that isn't supported on the base isa fails
+    /// and leaves the profile as it was.
+    pub fn set(&mut self, extension: Extension, enabled: bool) -> Result<(), IsaProfileError> {
+        if enabled && !extension.supports(self.base) {
+            return Err(IsaProfileError::UnsupportedExtension(extension.name().to_string()));
+        }
+        match (self.extensions.binary_search(&extension), enabled) {
+            (Err(index), true) => self.extensions.insert(index, extension),
+            (Ok(index), false) => {
+                self.extensions.remove(index);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    pub fn has(&self, extension: Extension) -> bool {
+        self.extensions.contains(&extension)
+    }
+
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+
+    /// Whether the row is part of this profile, its extension is enabled and it exists for the
+    /// register width of the base.
+    pub fn installs(&self, instruction: &Instruction) -> bool {
+        instruction.extension.is_none_or(|extension| self.has(extension))
+            && instruction.xlen.is_none_or(|xlen| xlen == self.base.xlen())
+    }
+
+    pub(super) fn instruction_definitions(&self) -> Vec<InstructionDefinition> {
+        INSTRUCTIONS
+            .iter()
+            .filter(|x| self.installs(x))
+            .map(|x| x.definition.clone())
+            .collect()
+    }
+}
+
+/// `rv32imaf_zicsr_zifencei`, everything `VM::new` used to install before profiles existed plus
+/// `fence.i`, so any rover can load code at runtime.
+impl Default for IsaProfile {
+    fn default() -> Self {
+        Self {
+            extensions: vec![Extension::M, Extension::A, Extension::F, Extension::Zicsr, Extension::Zifencei],
+            ..Self::base()
+        }
+    }
+}
+
+impl FromStr for IsaProfile {
+    type Err = IsaProfileError;
+
+    fn from_str(isa: &str) -> Result<Self, Self::Err> {
+        let isa = isa.trim().to_ascii_lowercase();
+        let Some((base, rest)) = [BaseIsa::Rv32I, BaseIsa::Rv32E, BaseIsa::Rv64I]
+            .into_iter()
+            .find_map(|base| isa.strip_prefix(base.name()).map(|rest| (base, rest)))
+        else {
+            return Err(IsaProfileError::InvalidBase(isa));
+        };
+
+        // single letter extensions come first, the multi-letter ones are separated by underscores
+        let (single, multi) = match rest.find(['_', 'z']) {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+
+        let mut profile = Self::base().with_base(base)?;
+        for letter in single.chars() {
+            let extension = match letter {
+                'm' => Extension::M,
+                'a' => Extension::A,
+                'f' => Extension::F,
+                'v' => Extension::V,
+                'c' | 'd' | 'q' => {
+                    return Err(IsaProfileError::UnsupportedExtension(letter.to_string()));
+                }
+                _ => return Err(IsaProfileError::UnknownExtension(letter.to_string())),
+            };
+            profile.set(extension, true)?;
+        }
+
+        for name in multi.split('_').filter(|x| !x.is_empty()) {
+            let extension = match name {
+                "zicond" => Extension::Zicond,
+                "zicsr" => Extension::Zicsr,
+                "zifencei" => Extension::Zifencei,
+                "zacas" => Extension::Zacas,
+                "zba" => Extension::Zba,
+                "zbb" => Extension::Zbb,
+                "zbs" => Extension::Zbs,
+                "zkne" => Extension::Zkne,
+                "zknd" => Extension::Zknd,
+                "zknh" => Extension::Zknh,
+                _ if name.starts_with("zvl") && name.ends_with('b') => {
+                    profile.vlen = name[3..name.len() - 1]
+                        .parse()
+                        .ok()
+                        .filter(|x| is_valid_vlen(*x))
+                        .ok_or_else(|| IsaProfileError::InvalidVectorLength(name.to_string()))?;
+                    continue;
+                }
+                _ => return Err(IsaProfileError::UnknownExtension(name.to_string())),
+            };
+            profile.set(extension, true)?;
+        }
+
+        Ok(profile)
+    }
+}
+
+impl fmt::Display for IsaProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base.name())?;
+        for extension in &self.extensions {
+            if extension.name().len() > 1 {
+                write!(f, "_")?;
+            }
+            write!(f, "{}", extension.name())?;
+        }
+        if self.vlen != DEFAULT_VLEN {
+            write!(f, "_zvl{}b", self.vlen)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BaseIsa, Extension, IsaProfile, IsaProfileError};
+
+    #[test]
+    fn test_parse_profile() {
+        let profile: IsaProfile = "rv32imf_zicsr_zbb".parse().unwrap();
+        assert_eq!(profile.extensions(), [Extension::M, Extension::F, Extension::Zicsr, Extension::Zbb]);
+
+        let profile: IsaProfile = "RV32I".parse().unwrap();
+        assert_eq!(profile, IsaProfile::base());
+
+        let profile: IsaProfile = "rv32iazicsr".parse().unwrap();
+        assert_eq!(profile.extensions(), [Extension::A, Extension::Zicsr]);
+
+        let profile: IsaProfile = "rv32em_zicsr".parse().unwrap();
+        assert_eq!(profile.base_isa(), BaseIsa::Rv32E);
+        assert_eq!(profile.extensions(), [Extension::M, Extension::Zicsr]);
+        assert_eq!(profile.to_string(), "rv32em_zicsr");
+
+        let profile: IsaProfile = "rv64im".parse().unwrap();
+        assert_eq!(profile.base_isa().xlen(), 64);
+        assert_eq!(profile.to_string(), "rv64im");
+
+        let profile: IsaProfile = "rv32imv_zicsr_zvl256b".parse().unwrap();
+        assert_eq!(profile.extensions(), [Extension::M, Extension::V, Extension::Zicsr]);
+        assert_eq!(profile.vlen(), 256);
+        assert_eq!(profile.to_string(), "rv32imv_zicsr_zvl256b");
+        assert_eq!("rv32iv".parse::<IsaProfile>().unwrap().vlen(), 128);
+
+        let profile: IsaProfile = "rv64i_zicsr_zicond".parse().unwrap();
+        assert_eq!(profile.extensions(), [Extension::Zicond, Extension::Zicsr]);
+
+        let profile: IsaProfile = "rv32ia_zacas".parse().unwrap();
+        assert_eq!(profile.extensions(), [Extension::A, Extension::Zacas]);
+
+        let profile: IsaProfile = "rv32i_zknh_zkne".parse().unwrap();
+        assert_eq!(profile.extensions(), [Extension::Zkne, Extension::Zknh]);
+        assert_eq!(profile.to_string(), "rv32i_zkne_zknh");
+    }
+
+    #[test]
+    fn test_parse_profile_errors() {
+        assert_eq!("rv128i".parse::<IsaProfile>(), Err(IsaProfileError::InvalidBase("rv128i".to_string())));
+        assert_eq!(
+            "rv64i_zbb".parse::<IsaProfile>(),
+            Err(IsaProfileError::UnsupportedExtension("zbb".to_string()))
+        );
+        assert_eq!(
+            "rv64i_zknd".parse::<IsaProfile>(),
+            Err(IsaProfileError::UnsupportedExtension("zknd".to_string()))
+        );
+        assert_eq!(
+            "rv32imc_zicsr".parse::<IsaProfile>(),
+            Err(IsaProfileError::UnsupportedExtension("c".to_string()))
+        );
+        assert_eq!(
+            "rv32iv_zvl100b".parse::<IsaProfile>(),
+            Err(IsaProfileError::InvalidVectorLength("zvl100b".to_string()))
+        );
+        assert_eq!(
+            "rv32i_zfoo".parse::<IsaProfile>(),
+            Err(IsaProfileError::UnknownExtension("zfoo".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_set_rejects_rv32_only_extensions_on_rv64() {
+        let mut profile = IsaProfile::base().with_base(BaseIsa::Rv64I).unwrap();
+        assert_eq!(profile.set(Extension::Zbs, true), Err(IsaProfileError::UnsupportedExtension("zbs".to_string())));
+        assert_eq!(profile.set(Extension::Zknh, true), Err(IsaProfileError::UnsupportedExtension("zknh".to_string())));
+        assert_eq!(profile.set(Extension::Zbs, false), Ok(()));
+        assert_eq!(profile.set(Extension::M, true), Ok(()));
+        assert_eq!(profile.extensions(), [Extension::M]);
+    }
+
+    #[test]
+    fn test_builders_reject_invalid_profiles() {
+        let unsupported = |name: &str| Err(IsaProfileError::UnsupportedExtension(name.to_string()));
+        let rv64 = IsaProfile::base().with_base(BaseIsa::Rv64I).unwrap();
+        assert_eq!(rv64.with(Extension::Zba), unsupported("zba"));
+        let zbb = IsaProfile::base().with(Extension::Zbb).unwrap();
+        assert_eq!(zbb.clone().with_base(BaseIsa::Rv64I), unsupported("zbb"));
+        assert_eq!(zbb.with_base(BaseIsa::Rv32E).map(|x| x.to_string()), Ok("rv32e_zbb".to_string()));
+
+        assert_eq!(
+            IsaProfile::base().with_vlen(100),
+            Err(IsaProfileError::InvalidVectorLength("zvl100b".to_string()))
+        );
+        assert_eq!(IsaProfile::base().with_vlen(256).map(|x| x.vlen()), Ok(256));
+    }
+
+    #[test]
+    fn test_profile_display_round_trips() {
+        let profile = IsaProfile::base()
+            .with(Extension::Zbs)
+            .and_then(|x| x.with(Extension::M))
+            .and_then(|x| x.with(Extension::Zicsr))
+            .unwrap();
+        assert_eq!(profile.to_string(), "rv32im_zicsr_zbs");
+        assert_eq!(profile.to_string().parse::<IsaProfile>(), Ok(profile));
+        assert_eq!(IsaProfile::default().to_string(), "rv32imaf_zicsr_zifencei");
+    }
+}
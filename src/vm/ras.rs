@@ -0,0 +1,93 @@
+// The return address stack predicts the target of function returns. Fetch pushes the return address
+// of every call and pops it again for the return, so neither waits for execute to compute the
+// target. When the stack is full a call drops the oldest entry, and the return that would have
+// used it later is predicted like any other jalr, which flushes.
+//
+// Fetch runs ahead of execute, so it can push or pop for an instruction on a mispredicted path.
+// Every change is handed back to be undone if that instruction gets flushed.
+
+use std::collections::VecDeque;
+
+/// The default number of return addresses kept.
+pub const DEFAULT_RETURN_STACK_DEPTH: usize = 8;
+
+/// What fetching one instruction did to the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReturnStackChange {
+    /// A call pushed its return address, the stack was full if it dropped the oldest entry.
+    Pushed { dropped: Option<usize> },
+    /// A return took this address as its predicted target.
+    Popped(usize),
+}
+
+#[derive(Debug)]
+pub struct ReturnAddressStack {
+    depth: usize,
+    entries: VecDeque<usize>, // the oldest entry first
+}
+
+impl ReturnAddressStack {
+    /// A stack of `depth` return addresses, 0 leaves every return to flush.
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            entries: VecDeque::with_capacity(depth),
+        }
+    }
+
+    pub fn push(&mut self, address: usize) -> Option<ReturnStackChange> {
+        if self.depth == 0 {
+            return None;
+        }
+        let dropped = if self.entries.len() == self.depth { self.entries.pop_front() } else { None };
+        self.entries.push_back(address);
+        Some(ReturnStackChange::Pushed { dropped })
+    }
+
+    /// The predicted return address, `None` when the stack is empty.
+    pub fn pop(&mut self) -> Option<usize> {
+        self.entries.pop_back()
+    }
+
+    pub fn undo(&mut self, change: ReturnStackChange) {
+        match change {
+            ReturnStackChange::Pushed { dropped } => {
+                self.entries.pop_back();
+                if let Some(address) = dropped {
+                    self.entries.push_front(address);
+                }
+            }
+            ReturnStackChange::Popped(address) => self.entries.push_back(address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReturnAddressStack;
+
+    #[test]
+    fn test_overflow_and_underflow() {
+        let mut stack = ReturnAddressStack::new(2);
+        stack.push(4);
+        stack.push(8);
+        stack.push(12); // drops 4
+        assert_eq!(stack.pop(), Some(12));
+        assert_eq!(stack.pop(), Some(8));
+        assert_eq!(stack.pop(), None);
+        assert_eq!(ReturnAddressStack::new(0).push(4), None);
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut stack = ReturnAddressStack::new(2);
+        stack.push(4);
+        stack.push(8);
+        let change = stack.push(12).unwrap();
+        stack.undo(change);
+        assert_eq!(stack.pop(), Some(8));
+        stack.undo(super::ReturnStackChange::Popped(8));
+        assert_eq!(stack.pop(), Some(8));
+        assert_eq!(stack.pop(), Some(4));
+    }
+}
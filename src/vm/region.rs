@@ -0,0 +1,77 @@
+// Slow regions of memory, like flash holding the code. Addresses outside every region take a cycle.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub start: usize,
+    pub size: usize,
+    /// Extra cycles a load or fetch waits.
+    pub read_latency: usize,
+    /// Extra cycles a store waits.
+    pub write_latency: usize,
+}
+
+impl MemoryRegion {
+    fn overlaps(&self, start: usize, size: usize) -> bool {
+        self.start < start + size && start < self.start + self.size
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryRegionError {
+    Empty,
+    /// The region overlaps the one added before at `start`.
+    Overlap { start: usize },
+}
+
+/// Checks that `region` doesn't overlap any of `regions`.
+pub fn check_region(regions: &[MemoryRegion], region: &MemoryRegion) -> Result<(), MemoryRegionError> {
+    if region.size == 0 {
+        return Err(MemoryRegionError::Empty);
+    }
+    match regions.iter().find(|x| x.overlaps(region.start, region.size)) {
+        Some(other) => Err(MemoryRegionError::Overlap { start: other.start }),
+        None => Ok(()),
+    }
+}
+
+/// The cycles an access waits, the latency of the slowest region it touches.
+pub fn wait_states(regions: &[MemoryRegion], address: usize, size: usize, write: bool) -> usize {
+    regions
+        .iter()
+        .filter(|x| x.overlaps(address, size.max(1)))
+        .map(|x| if write { x.write_latency } else { x.read_latency })
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_region, wait_states, MemoryRegion, MemoryRegionError};
+
+    fn region(start: usize, size: usize, read_latency: usize, write_latency: usize) -> MemoryRegion {
+        MemoryRegion {
+            start,
+            size,
+            read_latency,
+            write_latency,
+        }
+    }
+
+    #[test]
+    fn test_wait_states() {
+        let regions = [region(0, 64, 2, 20), region(64, 16, 8, 8)];
+        assert_eq!(wait_states(&regions, 0, 4, false), 2);
+        assert_eq!(wait_states(&regions, 60, 4, true), 20);
+        // a word straddling both regions waits for the slower one
+        assert_eq!(wait_states(&regions, 62, 4, false), 8);
+        assert_eq!(wait_states(&regions, 80, 4, false), 0);
+    }
+
+    #[test]
+    fn test_check_region() {
+        let regions = [region(16, 16, 1, 1)];
+        assert_eq!(check_region(&regions, &region(0, 16, 1, 1)), Ok(()));
+        assert_eq!(check_region(&regions, &region(28, 8, 1, 1)), Err(MemoryRegionError::Overlap { start: 16 }));
+        assert_eq!(check_region(&regions, &region(0, 0, 1, 1)), Err(MemoryRegionError::Empty));
+    }
+}
@@ -0,0 +1,220 @@
+// Functional units, register write ports and the memory port that decode checks before it issues.
+// The units compute their result right away, their latency only delays the instructions reading it.
+
+use super::common::REGISTER_COUNT;
+
+const OPCODE_OP: u32 = 0b0110011;
+const OPCODE_OP_32: u32 = 0b0111011;
+const OPCODE_OP_FP: u32 = 0b1010011;
+const OPCODE_MADD: u32 = 0b1000011;
+const OPCODE_MSUB: u32 = 0b1000111;
+const OPCODE_NMSUB: u32 = 0b1001011;
+const OPCODE_NMADD: u32 = 0b1001111;
+const FUNCT7_MULDIV: u32 = 0b0000001;
+const FUNCT5_FDIV: u32 = 0b00011;
+const FUNCT5_FSQRT: u32 = 0b01011;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionalUnit {
+    /// `mul*`
+    Multiplier,
+    /// `div*` and `rem*`
+    Divider,
+    /// The other F and D instructions.
+    Fpu,
+    /// `fdiv.*` and `fsqrt.*`
+    FpDivider,
+}
+
+/// The unit `instruction` executes on, `None` for the single-cycle ALU.
+pub fn functional_unit(instruction: u32) -> Option<FunctionalUnit> {
+    let funct7 = instruction >> 25;
+    match instruction & 0x7f {
+        OPCODE_OP | OPCODE_OP_32 if funct7 == FUNCT7_MULDIV => match (instruction >> 12) & 0x7 {
+            0..4 => Some(FunctionalUnit::Multiplier),
+            _ => Some(FunctionalUnit::Divider),
+        },
+        OPCODE_OP_FP if matches!(funct7 >> 2, FUNCT5_FDIV | FUNCT5_FSQRT) => Some(FunctionalUnit::FpDivider),
+        OPCODE_OP_FP | OPCODE_MADD | OPCODE_MSUB | OPCODE_NMSUB | OPCODE_NMADD => Some(FunctionalUnit::Fpu),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitConfig {
+    /// Cycles until the result can be forwarded, 1 like the ALU.
+    pub latency: usize,
+    /// Starts an instruction every cycle instead of waiting for the one before.
+    pub pipelined: bool,
+}
+
+impl Default for UnitConfig {
+    fn default() -> Self {
+        Self { latency: 1, pipelined: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resources {
+    /// Fetch waits while a load or store uses the one port to memory.
+    pub shared_memory_port: bool,
+    /// Registers written per cycle, at least 1.
+    pub register_write_ports: usize,
+    pub multiplier: UnitConfig,
+    pub divider: UnitConfig,
+    pub fpu: UnitConfig,
+    pub fp_divider: UnitConfig,
+}
+
+impl Default for Resources {
+    /// Nothing ever stalls for a resource, there is a write port for each dual issue slot.
+    fn default() -> Self {
+        Self {
+            shared_memory_port: false,
+            register_write_ports: 2,
+            multiplier: UnitConfig::default(),
+            divider: UnitConfig::default(),
+            fpu: UnitConfig::default(),
+            fp_divider: UnitConfig::default(),
+        }
+    }
+}
+
+impl Resources {
+    pub fn unit(&self, unit: FunctionalUnit) -> UnitConfig {
+        match unit {
+            FunctionalUnit::Multiplier => self.multiplier,
+            FunctionalUnit::Divider => self.divider,
+            FunctionalUnit::Fpu => self.fpu,
+            FunctionalUnit::FpDivider => self.fp_divider,
+        }
+    }
+}
+
+/// What `Scoreboard::issue` booked for an instruction, with the values it replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Booking {
+    unit: Option<(FunctionalUnit, usize)>,
+    rd: Option<(usize, usize)>,
+    write_at: usize,
+}
+
+/// When units, results and write ports are available, in the cycles the stages were clocked.
+#[derive(Clone)]
+pub(super) struct Scoreboard {
+    unit_free_at: [usize; 4],
+    result_ready_at: [usize; REGISTER_COUNT],
+    writes: Vec<usize>,
+}
+
+impl Scoreboard {
+    pub(super) fn new() -> Self {
+        Self {
+            unit_free_at: [0; 4],
+            result_ready_at: [0; REGISTER_COUNT],
+            writes: Vec::new(),
+        }
+    }
+
+    /// Whether `register` can be read or forwarded in cycle `now`.
+    pub(super) fn result_ready(&self, register: usize, now: usize) -> bool {
+        self.result_ready_at[register] <= now
+    }
+
+    pub(super) fn unit_free(&self, unit: FunctionalUnit, now: usize) -> bool {
+        self.unit_free_at[unit as usize] <= now
+    }
+
+    /// Whether one of the `ports` write ports is left in cycle `at`.
+    pub(super) fn write_port_free(&self, at: usize, ports: usize) -> bool {
+        self.writes.iter().filter(|&&x| x == at).count() < ports.max(1)
+    }
+
+    /// Whether a result is still on its way to the register file in cycle `now`.
+    pub(super) fn writing(&self, now: usize) -> bool {
+        self.writes.iter().any(|&x| x + 2 > now)
+    }
+
+    /// Books an instruction issued in cycle `now`, `unit` is the non-pipelined unit it keeps busy.
+    pub(super) fn issue(
+        &mut self,
+        now: usize,
+        latency: usize,
+        unit: Option<FunctionalUnit>,
+        rd: Option<usize>,
+    ) -> Booking {
+        self.writes.retain(|&x| x > now);
+        let write_at = now + latency;
+        let booking = Booking {
+            unit: unit.map(|x| (x, self.unit_free_at[x as usize])),
+            rd: rd.map(|x| (x, self.result_ready_at[x])),
+            write_at,
+        };
+        if let Some(unit) = unit {
+            self.unit_free_at[unit as usize] = write_at;
+        }
+        if let Some(rd) = rd {
+            self.result_ready_at[rd] = write_at;
+            self.writes.push(write_at);
+        }
+        booking
+    }
+
+    /// Gives back the booking of an instruction that was flushed before it executed. Nothing may
+    /// have been booked after it.
+    pub(super) fn release(&mut self, booking: Booking) {
+        if let Some((unit, free_at)) = booking.unit {
+            self.unit_free_at[unit as usize] = free_at;
+        }
+        if let Some((rd, ready_at)) = booking.rd {
+            self.result_ready_at[rd] = ready_at;
+            if let Some(index) = self.writes.iter().position(|&x| x == booking.write_at) {
+                self.writes.swap_remove(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{functional_unit, FunctionalUnit, Scoreboard};
+
+    #[test]
+    fn test_functional_unit() {
+        assert_eq!(functional_unit(0x0020_81b3), None); // ADD x3, x1, x2
+        assert_eq!(functional_unit(0x0220_81b3), Some(FunctionalUnit::Multiplier)); // MUL x3, x1, x2
+        assert_eq!(functional_unit(0x0220_c1b3), Some(FunctionalUnit::Divider)); // DIV x3, x1, x2
+        assert_eq!(functional_unit(0x0220_f1bb), Some(FunctionalUnit::Divider)); // REMUW x3, x1, x2
+        assert_eq!(functional_unit(0x0020_81d3), Some(FunctionalUnit::Fpu)); // FADD.S f3, f1, f2
+        assert_eq!(functional_unit(0x1820_81d3), Some(FunctionalUnit::FpDivider)); // FDIV.S f3, f1, f2
+        assert_eq!(functional_unit(0x5800_81d3), Some(FunctionalUnit::FpDivider)); // FSQRT.S f3, f1
+        assert_eq!(functional_unit(0x1820_81c3), Some(FunctionalUnit::Fpu)); // FMADD.S f3, f1, f2, f3
+    }
+
+    #[test]
+    fn test_scoreboard() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.issue(10, 4, Some(FunctionalUnit::Divider), Some(3));
+        assert!(!scoreboard.result_ready(3, 13));
+        assert!(scoreboard.result_ready(3, 14));
+        assert!(!scoreboard.unit_free(FunctionalUnit::Divider, 13));
+        assert!(scoreboard.unit_free(FunctionalUnit::Multiplier, 11));
+        assert!(!scoreboard.write_port_free(14, 1));
+        assert!(scoreboard.write_port_free(14, 2));
+        assert!(scoreboard.write_port_free(13, 1));
+        assert!(scoreboard.writing(15));
+        assert!(!scoreboard.writing(16));
+    }
+
+    #[test]
+    fn test_release() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.issue(10, 4, None, Some(3));
+        let booking = scoreboard.issue(11, 8, Some(FunctionalUnit::Divider), Some(3));
+        scoreboard.release(booking);
+        assert!(scoreboard.result_ready(3, 14));
+        assert!(scoreboard.unit_free(FunctionalUnit::Divider, 11));
+        assert!(scoreboard.write_port_free(19, 1));
+        assert!(!scoreboard.writing(16));
+    }
+}
@@ -0,0 +1,227 @@
+// The vector register file of the V subset. The 32 registers of VLEN bits each are stored back to
+// back, so a group of LMUL registers is one run of bytes and element `i` of the group starting at
+// `v` sits at byte `v * VLENB + i * SEW / 8`.
+//
+// Every instruction leaves the tail and the masked-off elements undisturbed, which the agnostic
+// policies allow as well.
+
+use super::common::VectorArithmetic;
+
+pub const VECTOR_REGISTER_COUNT: usize = 32;
+/// The widest element, SEW and EEW go from 8 to 64 bits.
+pub const ELEN: usize = 64;
+
+const VTYPE_VLMUL: u32 = 0x7;
+const VTYPE_VSEW: u32 = 0x7 << 3;
+const VTYPE_VTA: u32 = 1 << 6;
+const VTYPE_VMA: u32 = 1 << 7;
+
+/// Whether `vlen` can be the length of a vector register, a power of two from `ELEN` bits up.
+pub fn is_valid_vlen(vlen: usize) -> bool {
+    vlen.is_power_of_two() && (ELEN..=65536).contains(&vlen)
+}
+
+/// The vtype fields vsetvli takes, decoded from its immediate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vtype {
+    /// Selected element width in bits.
+    pub sew: usize,
+    /// The register group size LMUL in eighths, 1 for `mf8` up to 64 for `m8`.
+    pub lmul_eighths: usize,
+    pub tail_agnostic: bool,
+    pub mask_agnostic: bool,
+}
+
+impl Vtype {
+    /// `None` for the encodings that set vill, reserved widths and group sizes or bits above vma.
+    pub fn decode(bits: u32) -> Option<Vtype> {
+        if bits & !(VTYPE_VLMUL | VTYPE_VSEW | VTYPE_VTA | VTYPE_VMA) != 0 {
+            return None;
+        }
+        let sew = match (bits & VTYPE_VSEW) >> 3 {
+            vsew @ 0..=3 => 8 << vsew,
+            _ => return None,
+        };
+        let lmul_eighths = match bits & VTYPE_VLMUL {
+            vlmul @ 0..=3 => 8 << vlmul,
+            4 => return None,
+            vlmul => 1 << (vlmul - 5),
+        };
+        // fractional groups only support SEW up to LMUL * ELEN
+        if sew * 8 > lmul_eighths * ELEN {
+            return None;
+        }
+        Some(Vtype {
+            sew,
+            lmul_eighths,
+            tail_agnostic: bits & VTYPE_VTA != 0,
+            mask_agnostic: bits & VTYPE_VMA != 0,
+        })
+    }
+
+    pub fn encode(self) -> u32 {
+        let vsew = (self.sew / 8).trailing_zeros();
+        let vlmul = match self.lmul_eighths {
+            8.. => (self.lmul_eighths / 8).trailing_zeros(),
+            _ => 5 + self.lmul_eighths.trailing_zeros(),
+        };
+        vlmul | vsew << 3 | (self.tail_agnostic as u32) << 6 | (self.mask_agnostic as u32) << 7
+    }
+}
+
+pub struct VectorRegisters {
+    vlen: usize,
+    bytes: Vec<u8>,
+    vl: usize,
+    vtype: Option<Vtype>, // `None` while vill is set, every vector instruction but vsetvli is illegal then
+}
+
+impl VectorRegisters {
+    pub fn new(vlen: usize) -> Self {
+        Self {
+            vlen,
+            bytes: vec![0; VECTOR_REGISTER_COUNT * vlen / 8],
+            vl: 0,
+            vtype: None,
+        }
+    }
+
+    /// The length of a vector register in bytes.
+    pub fn vlenb(&self) -> usize {
+        self.vlen / 8
+    }
+
+    pub fn vl(&self) -> usize {
+        self.vl
+    }
+
+    pub fn vtype(&self) -> Option<Vtype> {
+        self.vtype
+    }
+
+    pub fn register(&self, register: usize) -> &[u8] {
+        &self.bytes[register * self.vlenb()..(register + 1) * self.vlenb()]
+    }
+
+    /// The number of elements a register group holds with `vtype`.
+    pub fn vlmax(&self, vtype: Vtype) -> usize {
+        self.vlen * vtype.lmul_eighths / 8 / vtype.sew
+    }
+
+    /// Sets vtype and `vl = min(avl, VLMAX)`, an invalid vtype sets vill and vl to 0. Returns the
+    /// new vl.
+    pub fn set_vl(&mut self, vtype: u32, avl: u64) -> usize {
+        self.vtype = Vtype::decode(vtype).filter(|x| self.vlmax(*x) > 0);
+        self.vl = match self.vtype {
+            Some(vtype) => avl.min(self.vlmax(vtype) as u64) as usize,
+            None => 0,
+        };
+        self.vl
+    }
+
+    /// Whether the register group starting at `register` is valid for elements of `width` bits,
+    /// its size EMUL = width / SEW * LMUL has to be a legal group size and `register` a multiple
+    /// of it.
+    pub fn is_valid_group(&self, register: usize, width: usize) -> bool {
+        let Some(vtype) = self.vtype else {
+            return false;
+        };
+        let emul_eighths = width * vtype.lmul_eighths / vtype.sew;
+        let registers = emul_eighths.div_ceil(8);
+        (1..=64).contains(&emul_eighths) && register.is_multiple_of(registers)
+    }
+
+    /// Element `index` of the group starting at `register`, sign-extended from `width` bits.
+    pub fn element(&self, register: usize, index: usize, width: usize) -> i64 {
+        let start = register * self.vlenb() + index * width / 8;
+        let mut bytes = [0; 8];
+        bytes[..width / 8].copy_from_slice(&self.bytes[start..start + width / 8]);
+        sign_extend(i64::from_le_bytes(bytes), width)
+    }
+
+    pub fn set_element(&mut self, register: usize, index: usize, width: usize, value: i64) {
+        let start = register * self.vlenb() + index * width / 8;
+        self.bytes[start..start + width / 8].copy_from_slice(&value.to_le_bytes()[..width / 8]);
+    }
+
+    /// Whether element `index` of a masked instruction is active, bit `index` of `v0` is set.
+    pub fn is_active(&self, index: usize) -> bool {
+        self.bytes[index / 8] & (1 << (index % 8)) != 0
+    }
+}
+
+pub fn sign_extend(value: i64, width: usize) -> i64 {
+    value << (64 - width) >> (64 - width)
+}
+
+/// Combines two elements of `sew` bits, both sign-extended. The unsigned variants compare them
+/// zero-extended.
+pub fn arithmetic(operation: VectorArithmetic, a: i64, b: i64, sew: usize) -> i64 {
+    let unsigned = |x: i64| (x as u64) & (u64::MAX >> (64 - sew));
+    let result = match operation {
+        VectorArithmetic::Add => a.wrapping_add(b),
+        VectorArithmetic::Mul => a.wrapping_mul(b),
+        VectorArithmetic::Min => a.min(b),
+        VectorArithmetic::Max => a.max(b),
+        VectorArithmetic::MinUnsigned => unsigned(a).min(unsigned(b)) as i64,
+        VectorArithmetic::MaxUnsigned => unsigned(a).max(unsigned(b)) as i64,
+    };
+    sign_extend(result, sew)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arithmetic, VectorRegisters, Vtype};
+    use crate::vm::common::VectorArithmetic;
+
+    #[test]
+    fn test_vtype() {
+        // e32, m2, ta, ma
+        let vtype = Vtype::decode(0xd1).unwrap();
+        assert_eq!((vtype.sew, vtype.lmul_eighths), (32, 16));
+        assert!(vtype.tail_agnostic && vtype.mask_agnostic);
+        assert_eq!(vtype.encode(), 0xd1);
+        // e16, mf2
+        assert_eq!(Vtype::decode(0x0f).map(|x| (x.sew, x.lmul_eighths)), Some((16, 4)));
+        // e64, mf8 can't hold an element
+        assert_eq!(Vtype::decode(0x1d), None);
+        assert_eq!(Vtype::decode(0x04), None); // reserved vlmul
+        assert_eq!(Vtype::decode(0x20), None); // reserved vsew
+        assert_eq!(Vtype::decode(0x100), None);
+    }
+
+    #[test]
+    fn test_set_vl() {
+        let mut vector = VectorRegisters::new(128);
+        assert_eq!(vector.set_vl(0x10, 100), 4); // e32, m1
+        assert_eq!(vector.set_vl(0x13, 100), 32); // e32, m8
+        assert_eq!(vector.set_vl(0x10, 3), 3);
+        assert_eq!(vector.set_vl(0x04, 3), 0);
+        assert_eq!(vector.vtype(), None);
+    }
+
+    #[test]
+    fn test_register_groups() {
+        let mut vector = VectorRegisters::new(128);
+        vector.set_vl(0x11, 8); // e32, m2
+        assert!(vector.is_valid_group(2, 32));
+        assert!(!vector.is_valid_group(3, 32));
+        assert!(!vector.is_valid_group(30, 64)); // m4 groups start at multiples of 4
+        assert!(vector.is_valid_group(3, 8)); // mf2
+
+        vector.set_element(2, 5, 32, -3);
+        assert_eq!(vector.element(2, 5, 32), -3);
+        assert_eq!(vector.element(3, 1, 32), -3); // element 5 of v2 is element 1 of v3
+        assert_eq!(vector.element(3, 4, 8), -3);
+        assert_eq!(vector.element(3, 5, 8), -1);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(arithmetic(VectorArithmetic::Add, 127, 1, 8), -128);
+        assert_eq!(arithmetic(VectorArithmetic::Mul, 0x100, 0x100, 16), 0);
+        assert_eq!(arithmetic(VectorArithmetic::Min, -1, 1, 8), -1);
+        assert_eq!(arithmetic(VectorArithmetic::MinUnsigned, -1, 1, 8), 1);
+        assert_eq!(arithmetic(VectorArithmetic::MaxUnsigned, -1, 1, 32), -1);
+    }
+}
@@ -1,136 +1,768 @@
-use std::collections::HashMap;
+use std::fmt;
+
+use crate::inst::{
+    CSR_CYCLE, CSR_CYCLEH, CSR_FCSR, CSR_FFLAGS, CSR_FRM, CSR_VL, CSR_VLENB, CSR_VTYPE, MASK_JAL, MASK_JALR, MATCH_JAL,
+    MATCH_JALR,
+};
 
 use super::{
-    btypes::BTYPE_LIST,
-    common::{InstructionDefinition, MemoryRange, OperandsFormat, TrapType, EXMEM, IDEX, IFID, MEMWB},
-    itypes::ITYPE_LIST,
-    jtypes::JTYPE_LIST,
-    rtypes::RTYPE_LIST,
-    stypes::STYPE_LIST,
-    utypes::UTYPE_LIST,
+    atypes,
+    btb::{BranchTargetBuffer, BtbConfig, BtbConfigError, BtbStats},
+    btypes::btype_offset,
+    cache::{Cache, CacheConfig, CacheConfigError, CacheStats},
+    catalogue::{self, KnownInstruction},
+    common::{
+        AtomicOperation, CsrAccess, CsrOperation, ExecuteResult, InstructionDefinition, MemoryRange,
+        OperandsFormat, TrapType, VectorOperation, VectorSource, EXMEM, FP_REGISTER_OFFSET, IDEX, IFID, MEM1MEM2,
+        MEMWB, REGISTER_COUNT,
+    },
+    cpu::{self, CpuModel},
+    custom::{encodings_overlap, CustomInstruction, CustomInstructionError},
+    float::{RoundingMode, ROUNDING_MODE_DYNAMIC},
+    fusion::FusionRules,
+    isa,
+    predictor::{Bimodal, BranchPredictor, PredictorStats},
+    profile::{BaseIsa, Extension, IsaProfile, IsaProfileError},
+    ras::{ReturnAddressStack, ReturnStackChange, DEFAULT_RETURN_STACK_DEPTH},
+    region::{self, MemoryRegion, MemoryRegionError},
+    units::{self, Resources, Scoreboard},
+    vector::{self, VectorRegisters},
 };
 
-pub enum BranchSaturatingCounter {
-    StronglyNotTaken,
-    WeaklyNotTaken,
-    WeaklyTaken,
-    StronglyTaken
+pub enum VmError { Trap, InvalidSyscall }
+
+const OPCODE_BRANCH: u32 = 0b1100011;
+/// `ra`, calls link through it and `jalr x0, 0(ra)` returns.
+const RETURN_ADDRESS: u32 = 1;
+
+pub trait VMEnvironment {
+    fn ecall(&self, arguments: &[i64]) -> Result<u32, VmError>;
+    fn ebreak(&self) -> ();
+    /// Called for every store this hart commits. A host running several harts on shared memory
+    /// mirrors it into the other harts with `VM::external_store`.
+    fn store(&self, _address: usize, _bytes: &[u8]) {}
 }
 
-pub struct BranchData {
-    pub address: usize,
-    pub saturating_counter: BranchSaturatingCounter
+/// What a call to `step` or `run` left the VM doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmState {
+    /// Instructions are still in flight.
+    Running,
+    /// Asleep in `wfi` until an interrupt becomes pending.
+    Waiting,
+    /// The pipeline is empty and there is nothing left to fetch.
+    Halted,
+    /// An instruction faulted, the instructions before it completed and nothing after it runs.
+    Faulted,
 }
 
-pub enum VmError { Trap, InvalidSyscall }
+/// Why the VM stopped with `VmState::Faulted`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// The encoding is no instruction at all, or names registers the base isa doesn't have.
+    IllegalInstruction { address: usize, instruction: u32 },
+    /// A known instruction from an extension the rover's isa profile doesn't have, or one this
+    /// VM doesn't implement.
+    NotEnabled {
+        address: usize,
+        instruction: u32,
+        known: KnownInstruction,
+    },
+    /// A store of `size` bytes at `addr` that doesn't lie entirely within memory.
+    StoreOutOfRange { address: usize, addr: usize, size: usize },
+    /// A load of `size` bytes at `addr` that doesn't lie entirely within memory.
+    LoadOutOfRange { address: usize, addr: usize, size: usize },
+    /// A jump or branch to an address outside of memory.
+    FetchOutOfRange { address: usize },
+}
 
-pub trait VMEnvironment {
-    fn ecall(&self, arguments: &[i32]) -> Result<u32, VmError>;
-    fn ebreak(&self) -> ();
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::IllegalInstruction { address, instruction } => {
+                write!(f, "illegal instruction {instruction:#010x} at {address:#x}")
+            }
+            Fault::NotEnabled { address, known, .. } => write!(
+                f,
+                "`{}` ({} extension) at {address:#x} is not enabled on this rover",
+                known.mnemonic,
+                known.extension_name()
+            ),
+            Fault::StoreOutOfRange { address, addr, size } => {
+                write!(f, "store of {size} bytes at {addr:#x} from {address:#x} is outside of memory")
+            }
+            Fault::LoadOutOfRange { address, addr, size } => {
+                write!(f, "load of {size} bytes at {addr:#x} from {address:#x} is outside of memory")
+            }
+            Fault::FetchOutOfRange { address } => write!(f, "fetch at {address:#x} is outside of memory"),
+        }
+    }
+}
+
+/// An interrupt line outside of 0..32.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterruptLineError {
+    pub line: u32,
+}
+
+/// An access that doesn't lie entirely within the memory of the VM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRangeError {
+    pub addr: usize,
+    pub size: usize,
+}
+
+/// Which pipeline registers decode can take a result from before it is written back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forwarding {
+    /// From EX/MEM and MEM/WB, only a load followed by a use of its result stalls.
+    Full,
+    /// From MEM/WB, an instruction using the result of the one right before it stalls a cycle.
+    MemoryOnly,
+    /// Nothing, decode stalls until the result is in the register file.
+    None,
+}
+
+/// How the pipeline resolves read after write hazards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HazardPolicy {
+    pub forwarding: Forwarding,
+    /// The register file is written in the first half of a cycle and read in the second, so decode
+    /// sees the value written back in the same cycle. Without it decode waits one more cycle.
+    pub split_cycle_register_file: bool,
+}
+
+impl Default for HazardPolicy {
+    /// Full forwarding and a split cycle register file.
+    fn default() -> Self {
+        Self {
+            forwarding: Forwarding::Full,
+            split_cycle_register_file: true,
+        }
+    }
+}
+
+/// The cycles the pipeline stalled, by what it waited for.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StallStats {
+    /// Decode waited for an operand.
+    pub data_hazard: u64,
+    /// Decode waited for a busy non-pipelined unit.
+    pub functional_unit: u64,
+    /// Decode waited for a register write port.
+    pub write_port: u64,
+    /// Fetch waited for the shared memory port.
+    pub memory_port: u64,
+    /// Everything waited for a multi-cycle instruction in execute.
+    pub execute: u64,
+    /// Everything waited for a cache miss or a slow memory region.
+    pub memory: u64,
 }
 
 pub enum HazardAction {
     None,
     Stall,
-    ForwardExecute(bool), // boolean indicates if it concerns the first register like r1 or r2
-    ForwardMemory(bool),  // boolean indicates if it concerns the first register like r1 or r2
+    Forward(usize, i64), // index of the source operand (r1, r2 or r3) and the value it receives
 }
 
+// the younger instruction of every latch of the dual issue pipeline, the latches of `VM` hold the
+// older one. The five stage pipeline fetches the tail of a fused pair into `if_id`
 #[derive(Default)]
+struct SecondSlot {
+    if_id: Option<IFID>,
+    id_ex: Option<IDEX>,
+    ex_mem: Option<EXMEM>,
+    mem_wb: Option<MEMWB>,
+}
+
 pub struct VM<T: VMEnvironment> {
     vm_environment: T,
     instruction_definitions: Vec<InstructionDefinition>,
+    profile: IsaProfile,
     memory: Vec<u8>,
-    registers: [i32; 32],
+    registers: [i64; REGISTER_COUNT],
+    vector: VectorRegisters,
+    fcsr: u32,
     pc: usize,
     cycle: usize,
     stall: bool,
+    cpu_model: CpuModel,
+    if1_if2: Option<IFID>, // only used by the seven stage pipeline, like mem1_mem2
     if_id: Option<IFID>,
     id_ex: Option<IDEX>,
     ex_mem: Option<EXMEM>,
+    mem1_mem2: Option<MEM1MEM2>,
     mem_wb: Option<MEMWB>,
-    seen_branches: HashMap<usize, BranchData>,
-    seen_jumps: HashMap<usize, usize>
+    second: SecondSlot,
+    hazard_policy: HazardPolicy,
+    resources: Resources,
+    scoreboard: Scoreboard,
+    stage_cycles: usize, // cycles the stages were clocked, the scoreboard counts in these
+    stall_stats: StallStats,
+    memory_port_busy: bool, // the memory stage accessed memory this cycle
+    fetch_blocked: bool,    // fetch had a word to fetch but the memory stage had the shared port
+    written_back: [usize; 2], // the registers writeback wrote this cycle, 0 for none
+    retired: u64,
+    fusion_rules: FusionRules,
+    fused_ops: u64, // pairs that executed as one instruction
+    predictor: Box<dyn BranchPredictor>,
+    predictor_stats: PredictorStats,
+    btb: BranchTargetBuffer, // the targets of the jumps executed so far
+    instruction_cache: Option<Cache>, // without one every fetch takes a single cycle
+    data_cache: Option<Cache>,
+    memory_regions: Vec<MemoryRegion>,
+    return_stack: ReturnAddressStack,
+    waiting: bool,
+    pending_interrupts: u32,
+    idle_cycles: usize,
+    reservation: Option<usize>, // word set by lr.w, sc.w only succeeds while it is held
+    busy: usize,                // cycles a multi-cycle instruction still holds its stage for
+    memory_wait: usize,         // cycles a slow memory access still holds its stage for
+    custom_instructions: Vec<CustomInstruction>,
+    energy: u64,
+    fault: Option<Fault>,
 }
 
 impl<T: VMEnvironment> VM<T> {
+    /// Creates a VM implementing `IsaProfile::default()`.
     pub fn new(memory: Vec<u8>, vm_environment: T) -> Self {
-        let instruction_definitions: Vec<InstructionDefinition> = [
-            &RTYPE_LIST[..],
-            &ITYPE_LIST[..],
-            &STYPE_LIST[..],
-            &BTYPE_LIST[..],
-            &UTYPE_LIST[..],
-            &JTYPE_LIST[..],
-        ]
-        .concat();
+        Self::with_profile(memory, vm_environment, IsaProfile::default())
+    }
 
+    /// Creates a VM that only installs the instructions of `profile`.
+    pub fn with_profile(memory: Vec<u8>, vm_environment: T, profile: IsaProfile) -> Self {
         Self {
             pc: 0,
             memory,
-            registers: [0; 32],
+            registers: [0; REGISTER_COUNT],
+            vector: VectorRegisters::new(profile.vlen()),
+            fcsr: 0,
             cycle: 0,
+            cpu_model: CpuModel::FiveStage,
+            if1_if2: None,
             if_id: None,
             id_ex: None,
             ex_mem: None,
+            mem1_mem2: None,
             mem_wb: None,
-            instruction_definitions,
+            second: SecondSlot::default(),
+            hazard_policy: HazardPolicy::default(),
+            resources: Resources::default(),
+            scoreboard: Scoreboard::new(),
+            stage_cycles: 0,
+            stall_stats: StallStats::default(),
+            memory_port_busy: false,
+            fetch_blocked: false,
+            written_back: [0; 2],
+            retired: 0,
+            fusion_rules: FusionRules::default(),
+            fused_ops: 0,
+            instruction_definitions: profile.instruction_definitions(),
+            profile,
             stall: false,
             vm_environment,
-            seen_branches: HashMap::new(),
-            seen_jumps: HashMap::new(),
+            predictor: Box::new(Bimodal::new()),
+            predictor_stats: PredictorStats::default(),
+            btb: BranchTargetBuffer::new(BtbConfig::default()).unwrap(),
+            instruction_cache: None,
+            data_cache: None,
+            memory_regions: Vec::new(),
+            return_stack: ReturnAddressStack::new(DEFAULT_RETURN_STACK_DEPTH),
+            waiting: false,
+            pending_interrupts: 0,
+            idle_cycles: 0,
+            reservation: None,
+            busy: 0,
+            memory_wait: 0,
+            custom_instructions: vec![],
+            energy: 0,
+            fault: None,
+        }
+    }
+
+    /// Switches an extension on or off. Instructions that are already past decode still complete.
+    /// Extensions the base isa doesn't support are rejected, see `IsaProfile::set`.
+    pub fn set_extension(&mut self, extension: Extension, enabled: bool) -> Result<(), IsaProfileError> {
+        self.profile.set(extension, enabled)?;
+        self.instruction_definitions = self.profile.instruction_definitions();
+        Ok(())
+    }
+
+    pub fn has_extension(&self, extension: Extension) -> bool {
+        self.profile.has(extension)
+    }
+
+    pub fn profile(&self) -> &IsaProfile {
+        &self.profile
+    }
+
+    /// Installs a game specific instruction in the custom-0 or custom-1 opcode space. It fails if
+    /// any installed instruction, custom or not, could be confused with it.
+    pub fn register_custom_instruction(
+        &mut self,
+        instruction: CustomInstruction,
+    ) -> Result<(), CustomInstructionError> {
+        instruction.validate()?;
+
+        let installed = self
+            .instruction_definitions
+            .iter()
+            .map(|x| (x.mask, x.match_val))
+            .chain(self.custom_instructions.iter().map(|x| (x.mask, x.match_val)));
+        for (mask, match_val) in installed {
+            if encodings_overlap(instruction.mask, instruction.match_val, mask, match_val) {
+                return Err(CustomInstructionError::Overlap { mask, match_val });
+            }
+        }
+
+        self.custom_instructions.push(instruction);
+        Ok(())
+    }
+
+    /// Changes the CPU model `step` and `run` clock, set it before the program starts. The VM starts
+    /// out with `CpuModel::FiveStage`.
+    pub fn set_cpu_model(&mut self, model: CpuModel) {
+        self.cpu_model = model;
+    }
+
+    /// Changes the forwarding paths and register file timing decode resolves data hazards with.
+    pub fn set_hazard_policy(&mut self, policy: HazardPolicy) {
+        self.hazard_policy = policy;
+    }
+
+    /// Changes the resources instructions compete for, set it before the program starts.
+    pub fn set_resources(&mut self, resources: Resources) {
+        self.resources = resources;
+    }
+
+    /// Changes the pairs decode fuses, only the five stage and dual issue pipelines fuse.
+    pub fn set_fusion_rules(&mut self, rules: FusionRules) {
+        self.fusion_rules = rules;
+    }
+
+    /// The cycles the pipeline stalled so far, by reason.
+    pub fn stall_stats(&self) -> StallStats {
+        self.stall_stats
+    }
+
+    /// Replaces the branch target buffer with an empty one of another size or replacement policy.
+    pub fn configure_btb(&mut self, config: BtbConfig) -> Result<(), BtbConfigError> {
+        self.btb = BranchTargetBuffer::new(config)?;
+        Ok(())
+    }
+
+    /// Hits, misses and aliasing of the branch target buffer since it was configured.
+    pub fn btb_stats(&self) -> BtbStats {
+        self.btb.stats()
+    }
+
+    /// Puts an empty instruction cache between fetch and memory, the VM starts out without caches.
+    pub fn configure_instruction_cache(&mut self, config: CacheConfig) -> Result<(), CacheConfigError> {
+        self.instruction_cache = Some(Cache::new(config)?);
+        Ok(())
+    }
+
+    /// Puts an empty data cache between the memory stage and memory.
+    pub fn configure_data_cache(&mut self, config: CacheConfig) -> Result<(), CacheConfigError> {
+        self.data_cache = Some(Cache::new(config)?);
+        Ok(())
+    }
+
+    /// Hits, misses and writebacks of the instruction cache since it was configured.
+    pub fn instruction_cache_stats(&self) -> Option<CacheStats> {
+        self.instruction_cache.as_ref().map(Cache::stats)
+    }
+
+    /// Hits, misses and writebacks of the data cache since it was configured.
+    pub fn data_cache_stats(&self) -> Option<CacheStats> {
+        self.data_cache.as_ref().map(Cache::stats)
+    }
+
+    /// Makes the accesses to `region` wait for its latencies, regions can't overlap.
+    pub fn add_memory_region(&mut self, region: MemoryRegion) -> Result<(), MemoryRegionError> {
+        region::check_region(&self.memory_regions, &region)?;
+        self.memory_regions.push(region);
+        Ok(())
+    }
+
+    pub fn memory_regions(&self) -> &[MemoryRegion] {
+        &self.memory_regions
+    }
+
+    /// Replaces the predictor fetch asks about the direction of branches and resets its stats, the
+    /// VM starts out with `Bimodal`.
+    pub fn set_branch_predictor(&mut self, predictor: Box<dyn BranchPredictor>) {
+        self.predictor = predictor;
+        self.predictor_stats = PredictorStats::default();
+    }
+
+    /// Correct and mispredicted branches since the predictor was set.
+    pub fn predictor_stats(&self) -> PredictorStats {
+        self.predictor_stats
+    }
+
+    /// Replaces the return address stack with an empty one keeping `depth` return addresses, 0
+    /// turns return prediction off.
+    pub fn configure_return_stack(&mut self, depth: usize) {
+        self.return_stack = ReturnAddressStack::new(depth);
+    }
+
+    /// Energy spent on the instructions executed so far, each one costs the energy of its row in
+    /// the instruction table or of its custom instruction.
+    pub fn energy(&self) -> u64 {
+        self.energy
+    }
+
+    pub fn fault(&self) -> Option<&Fault> {
+        self.fault.as_ref()
+    }
+
+    /// The integer registers the base isa has, `x0..x15` for RV32E and `x0..x31` otherwise.
+    pub fn integer_registers(&self) -> &[i64] {
+        &self.registers[..self.profile.base_isa().integer_register_count()]
+    }
+
+    /// The VLEN / 8 bytes of vector register `register`, element 0 first.
+    pub fn vector_register(&self, register: usize) -> &[u8] {
+        self.vector.register(register)
+    }
+
+    /// One line per four integer registers, like `x0  = 0x00000000  x1  = 0x00000004 ...`. The
+    /// values are 16 hex digits wide on RV64.
+    pub fn register_dump(&self) -> String {
+        let xlen = self.profile.base_isa().xlen();
+        self.integer_registers()
+            .chunks(4)
+            .enumerate()
+            .map(|(row, values)| {
+                let columns: Vec<String> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(column, value)| {
+                        let name = format!("x{}", row * 4 + column);
+                        match xlen {
+                            32 => format!("{name:<3} = {:#010x}", *value as u32),
+                            _ => format!("{name:<3} = {value:#018x}"),
+                        }
+                    })
+                    .collect();
+                columns.join("  ")
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Cycles that have passed, including the ones spent asleep.
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    /// Cycles spent asleep in `wfi`, the host can charge them at idle power.
+    pub fn idle_cycles(&self) -> usize {
+        self.idle_cycles
+    }
+
+    /// Number of instructions that went through execute, flushed and illegal ones don't count.
+    pub fn instructions_retired(&self) -> u64 {
+        self.retired
+    }
+
+    /// Fused pairs that went through execute, each retires two instructions.
+    pub fn fused_ops(&self) -> u64 {
+        self.fused_ops
+    }
+
+    /// Instructions retired per cycle spent executing, above 1 only with `CpuModel::DualIssue` or
+    /// fusion.
+    pub fn ipc(&self) -> f64 {
+        let cycles = self.cycle - self.idle_cycles;
+        if cycles == 0 {
+            return 0.0;
         }
+        self.retired as f64 / cycles as f64
+    }
+
+    /// Marks interrupt `line` as pending, which wakes the VM from `wfi`.
+    pub fn raise_interrupt(&mut self, line: u32) -> Result<(), InterruptLineError> {
+        self.pending_interrupts |= interrupt_bit(line)?;
+        Ok(())
+    }
+
+    pub fn clear_interrupt(&mut self, line: u32) -> Result<(), InterruptLineError> {
+        self.pending_interrupts &= !interrupt_bit(line)?;
+        Ok(())
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    fn pipeline_is_empty(&self) -> bool {
+        self.if1_if2.is_none()
+            && self.if_id.is_none()
+            && self.id_ex.is_none()
+            && self.ex_mem.is_none()
+            && self.mem1_mem2.is_none()
+            && self.mem_wb.is_none()
+            && self.second.if_id.is_none()
+            && self.second.id_ex.is_none()
+            && self.second.ex_mem.is_none()
+            && self.second.mem_wb.is_none()
     }
 
     pub fn step_no_pipeline(&mut self) {
+        self.stage_cycles += 1;
         self.fetch();
         self.decode();
         self.execute();
         self.memory();
         self.writeback();
-        self.cycle += 5;
+        self.cycle += 5 + std::mem::take(&mut self.busy).max(std::mem::take(&mut self.memory_wait));
     }
 
-    pub fn run(&mut self) {
+    /// Steps until the program halts or the VM goes to sleep in `wfi`.
+    pub fn run(&mut self) -> VmState {
         loop {
-            self.step();
-            if self.if_id.is_none()
-                && self.id_ex.is_none()
-                && self.ex_mem.is_none()
-                && self.mem_wb.is_none()
-            {
-                break;
+            let state = self.step();
+            if state != VmState::Running {
+                return state;
             }
         }
     }
 
-    pub fn step(&mut self) {
-        self.writeback();
-        self.memory();
-        self.execute();
-        self.decode();
+    pub fn step(&mut self) -> VmState {
+        // a multi-cycle instruction or slow memory access still occupies its stage, everything waits
+        // for it
+        if self.busy > 0 || self.memory_wait > 0 {
+            if self.memory_wait > 0 {
+                self.stall_stats.memory += 1;
+            } else {
+                self.stall_stats.execute += 1;
+            }
+            self.busy = self.busy.saturating_sub(1);
+            self.memory_wait = self.memory_wait.saturating_sub(1);
+            self.cycle += 1;
+            return VmState::Running;
+        }
+
+        if self.waiting {
+            if self.pending_interrupts != 0 {
+                self.waiting = false;
+            } else if self.pipeline_is_empty() {
+                // asleep, nothing is in flight so no need to clock the stages
+                self.cycle += 1;
+                self.idle_cycles += 1;
+                return VmState::Waiting;
+            }
+        }
+
+        self.stage_cycles += 1;
+        (self.memory_port_busy, self.fetch_blocked) = (false, false);
+        let in_flight = match self.cpu_model {
+            CpuModel::SingleCycle | CpuModel::MultiCycle => self.step_unpipelined(),
+            CpuModel::FiveStage => {
+                self.writeback();
+                self.memory();
+                self.execute();
+                self.decode();
+                self.fuse();
+                self.fetch();
+                self.cycle += 1;
+                !self.pipeline_is_empty()
+            }
+            CpuModel::SevenStage => {
+                self.writeback();
+                // MEM1 does the access and MEM2 hands its result on to writeback
+                let leaving = self.mem1_mem2.take();
+                let is_load = self.ex_mem.as_ref().and_then(|x| x.memory_operation.as_ref()).is_some_and(|x| x.is_load);
+                self.mem_wb = None;
+                self.memory();
+                self.mem1_mem2 = self.mem_wb.take().map(|mem_wb| MEM1MEM2 { mem_wb, is_load });
+                self.mem_wb = leaving.map(|x| x.mem_wb);
+                self.execute();
+                self.decode();
+                self.fetch();
+                self.cycle += 1;
+                !self.pipeline_is_empty()
+            }
+            CpuModel::DualIssue => {
+                self.writeback();
+                self.mem_wb = None;
+                self.memory();
+                self.in_second_slot(|vm| {
+                    vm.mem_wb = None;
+                    vm.memory();
+                });
+                // the older instruction executes first, when it flushes the younger one of its bundle
+                // was on the wrong path. Execute gave back the return stack changes of the
+                // instructions fetched behind the bundle, the younger slot's comes after them
+                if self.execute() {
+                    if let Some(younger) = self.second.id_ex.take() {
+                        if let Some(change) = younger.return_stack_change {
+                            self.return_stack.undo(change);
+                        }
+                        if let Some(booking) = younger.booking {
+                            self.scoreboard.release(booking);
+                        }
+                    }
+                } else {
+                    self.in_second_slot(|vm| {
+                        vm.execute();
+                    });
+                }
+                self.decode_bundle();
+                self.fetch();
+                self.cycle += 1;
+                !self.pipeline_is_empty()
+            }
+        };
+
+        // a fetch that waited for the memory port leaves the pipeline empty for a cycle, and the
+        // results of slow functional units arrive after the instructions left the pipeline
+        let in_flight = in_flight || self.fetch_blocked || self.scoreboard.writing(self.stage_cycles);
+        match (in_flight, self.waiting) {
+            (true, _) => VmState::Running,
+            (false, _) if self.fault.is_some() => VmState::Faulted,
+            (false, true) => VmState::Waiting,
+            (false, false) => VmState::Halted,
+        }
+    }
+
+    // runs a stage after the id_ex, ex_mem and mem_wb of the second slot were swapped in
+    fn in_second_slot(&mut self, stage: impl FnOnce(&mut Self)) {
+        let swap = |vm: &mut Self| {
+            std::mem::swap(&mut vm.id_ex, &mut vm.second.id_ex);
+            std::mem::swap(&mut vm.ex_mem, &mut vm.second.ex_mem);
+            std::mem::swap(&mut vm.mem_wb, &mut vm.second.mem_wb);
+        };
+        swap(self);
+        stage(self);
+        swap(self);
+    }
+
+    // runs one instruction through every stage before the next one is fetched, nothing stays in
+    // flight to forward from or to flush. Returns whether there was an instruction to run
+    fn step_unpipelined(&mut self) -> bool {
         self.fetch();
-        self.cycle += 1;
+        let Some(instruction) = self.if_id.as_ref().map(|x| x.instruction) else {
+            return false;
+        };
+        self.decode();
+        self.execute();
+        self.memory();
+        self.writeback();
+        (self.if_id, self.id_ex, self.mem_wb, self.written_back) = (None, None, None, [0; 2]);
+
+        let cycles = match self.cpu_model {
+            CpuModel::MultiCycle => cpu::multi_cycle_cpi(instruction),
+            _ => 1,
+        };
+        self.cycle += cycles + std::mem::take(&mut self.busy).max(std::mem::take(&mut self.memory_wait));
+        true
     }
 
+    // Fetch doesn't snoop stores. A store commits in the memory stage, after the two instructions
+    // behind it were fetched, so those still run their old bytes. `fence.i` refetches everything
+    // behind it and is the way to run code written at runtime.
     fn fetch(&mut self) {
-        if self.stall {
+        // while waiting for an interrupt or after a fault the pipeline drains, nothing new is fetched
+        if self.stall || self.waiting || self.fault.is_some() {
             return;
         }
+        match self.cpu_model {
+            // IF2 hands on what IF1 fetched the cycle before
+            CpuModel::SevenStage => {
+                let fetched = self.fetch_word();
+                self.if_id = std::mem::replace(&mut self.if1_if2, fetched);
+            }
+            // the word behind a possible head of a fused pair comes along in the second slot, when
+            // decode didn't fuse it it moves up
+            CpuModel::FiveStage => {
+                self.if_id = self.second.if_id.take().or_else(|| self.fetch_word());
+                let heads = self.if_id.as_ref().is_some_and(|x| {
+                    x.predicted_pc == x.address.wrapping_add(4) && self.fusion_rules.can_head(x.instruction)
+                });
+                if heads {
+                    self.second.if_id = self.fetch_word();
+                }
+            }
+            // fills the slots decode emptied, a bundle ends after a control transfer predicted taken
+            CpuModel::DualIssue => {
+                if self.if_id.is_none() {
+                    self.if_id = self.fetch_word();
+                }
+                if self.second.if_id.is_none() && self.if_id.as_ref().is_some_and(|x| x.predicted_pc == x.address.wrapping_add(4)) {
+                    self.second.if_id = self.fetch_word();
+                }
+            }
+            _ => self.if_id = self.fetch_word(),
+        }
+    }
+
+    // Running into the end of memory ends the program. A pc past it, after a jump or branch outside
+    // of memory, fetches a word that traps once it reaches execute
+    fn fetch_word(&mut self) -> Option<IFID> {
         let pc = self.pc;
-        // assert!(pc + 4 <= self.memory.len(), "Unexpected end of program");
-        if pc + 4 <= self.memory.len() {
-            let bytes = &self.memory[pc..pc + 4];
-            let instruction = u32::from_le_bytes(bytes.try_into().unwrap());
-            self.if_id = Some(IFID {
-                instruction,
-                address: self.pc,
+        let Some(range) = self.memory_range(pc, 4) else {
+            if pc <= self.memory.len() {
+                return None;
+            }
+            self.pc = pc.wrapping_add(4);
+            return Some(IFID {
+                instruction: 0,
+                address: pc,
+                predicted_pc: self.pc,
+                return_stack_change: None,
+                outside_memory: true,
             });
-            // eagerly update the pc, this can be overwritten in the execute stage if the instruction
-            // is a branch/ jump
-            self.pc += 4;
+        };
+        if self.resources.shared_memory_port && self.memory_port_busy {
+            self.stall_stats.memory_port += !self.fetch_blocked as u64;
+            self.fetch_blocked = true;
+            return None;
+        }
+        let bytes = &self.memory[range];
+        let instruction = u32::from_le_bytes(bytes.try_into().unwrap());
+        // a slow fetch holds up the whole pipeline
+        let stall = memory_stall(self.instruction_cache.as_mut(), &self.memory_regions, pc, 4, false);
+        self.memory_wait = self.memory_wait.max(stall);
+        let (predicted_pc, return_stack_change) = match self.cpu_model.is_pipelined() {
+            true => self.predict(pc, instruction),
+            false => (pc + 4, None),
+        };
+        self.pc = predicted_pc;
+        Some(IFID {
+            instruction,
+            address: pc,
+            predicted_pc,
+            return_stack_change,
+            outside_memory: false,
+        })
+    }
+
+    // Fetch tells the control transfers apart by their opcode. Branches seen before follow their
+    // counter and jumps in the btb go straight to their target, calls push their return address and
+    // returns pop it. Everything else continues with the next word, execute corrects the pc when the
+    // prediction was wrong.
+    fn predict(&mut self, pc: usize, instruction: u32) -> (usize, Option<ReturnStackChange>) {
+        let rd = (instruction >> 7) & 0x1f;
+        let rs1 = (instruction >> 15) & 0x1f;
+        let next = pc + 4;
+        if instruction & 0x7f == OPCODE_BRANCH {
+            let target = pc.wrapping_add(btype_offset(instruction) as usize);
+            (if self.predictor.predict(pc, target) { target } else { next }, None)
+        } else if instruction & MASK_JAL == MATCH_JAL {
+            let call = if rd == RETURN_ADDRESS { self.return_stack.push(next) } else { None };
+            (self.btb.lookup(pc).unwrap_or(next), call)
+        } else if instruction & MASK_JALR == MATCH_JALR && rd == 0 && rs1 == RETURN_ADDRESS {
+            match self.return_stack.pop() {
+                Some(target) => (target, Some(ReturnStackChange::Popped(target))),
+                None => (next, None),
+            }
+        } else if instruction & MASK_JALR == MATCH_JALR && rd == RETURN_ADDRESS {
+            (next, self.return_stack.push(next))
         } else {
-            self.if_id = None;
+            (next, None)
         }
     }
 
@@ -142,109 +774,343 @@ impl<T: VMEnvironment> VM<T> {
             return;
         };
 
-        for def in &self.instruction_definitions {
-            if if_id.instruction & def.mask == def.match_val {
-                let mut decoded = (def.decode)(if_id.instruction, &self.registers, if_id.address);
-
-                match self.detect_data_hazard(&decoded) {
-                    HazardAction::ForwardExecute(target_r1) => {
-                        if let Some(ex_mem) = &self.ex_mem {
-                            match decoded.operands.as_mut() {
-                                Some(
-                                    OperandsFormat::Btype { r1_val, r2_val, .. }
-                                    | OperandsFormat::Stype { r1_val, r2_val, .. }
-                                    | OperandsFormat::Rtype { r1_val, r2_val, .. },
-                                ) => {
-                                    if target_r1 {
-                                        // if true forward result to r1
-                                        *r1_val = ex_mem.calculation_result;
-                                    } else {
-                                        *r2_val = ex_mem.calculation_result;
-                                    }
-                                }
-                                Some(OperandsFormat::Itype { r1_val, .. }) => {
-                                    *r1_val = ex_mem.calculation_result;
-                                }
-                                _ => (),
-                            }
-                        }
-                        self.id_ex = Some(decoded);
-                    }
-                    HazardAction::ForwardMemory(target_r1) => {
-                        if let Some(mem_wb) = &self.mem_wb {
-                            match decoded.operands.as_mut() {
-                                Some(
-                                    OperandsFormat::Btype { r1_val, r2_val, .. }
-                                    | OperandsFormat::Stype { r1_val, r2_val, .. }
-                                    | OperandsFormat::Rtype { r1_val, r2_val, .. },
-                                ) => {
-                                    if target_r1 {
-                                        // if true forward result to r1
-                                        *r1_val = mem_wb.value;
-                                    } else {
-                                        *r2_val = mem_wb.value;
-                                    }
-                                }
-                                Some(OperandsFormat::Itype { r1_val, .. }) => {
-                                    *r1_val = mem_wb.value;
-                                }
-                                _ => (),
-                            }
-                        }
-                        self.id_ex = Some(decoded);
-                    }
-                    HazardAction::None => {
-                        self.id_ex = Some(decoded);
-                    }
-                    HazardAction::Stall => {
-                        self.id_ex = None;
-                        self.stall = true;
-                    }
+        let (instruction, predicted_pc, return_stack_change) =
+            (if_id.instruction, if_id.predicted_pc, if_id.return_stack_change);
+
+        // the instruction traps once it reaches execute. Trapping any earlier would also fault on
+        // words fetched behind a taken branch
+        if if_id.outside_memory {
+            self.id_ex = Some(fetch_outside_memory(if_id.address));
+            return;
+        }
+        let Some(mut decoded) = self.decode_word(if_id) else {
+            self.id_ex = Some(illegal_instruction(if_id.instruction, if_id.address));
+            return;
+        };
+
+        let hazards = self.detect_data_hazard(&decoded);
+        let stall = hazards.iter().any(|x| matches!(x, HazardAction::Stall));
+        self.stall_stats.data_hazard += stall as u64;
+        if stall || !self.issue(instruction, &mut decoded) {
+            self.id_ex = None;
+            self.stall = true;
+            return;
+        }
+
+        if let Some(operands) = decoded.operands.as_mut() {
+            for hazard in hazards {
+                if let HazardAction::Forward(operand, value) = hazard {
+                    forward_operand(operands, operand, value);
+                }
+            }
+
+            // floating point instructions with the dynamic rounding mode use frm
+            if let OperandsFormat::Ftype { rm, .. } | OperandsFormat::R4type { rm, .. } = operands
+                && *rm == ROUNDING_MODE_DYNAMIC
+            {
+                *rm = ((self.fcsr >> 5) & 0x7) as u8;
+            }
+        }
+
+        decoded.predicted_pc = predicted_pc;
+        decoded.return_stack_change = return_stack_change;
+        self.id_ex = Some(decoded);
+    }
+
+    // `None` when no definition matches the word or it names registers the base doesn't have
+    fn decode_word(&self, if_id: &IFID) -> Option<IDEX> {
+        let decoded = match self
+            .instruction_definitions
+            .iter()
+            .find(|def| if_id.instruction & def.mask == def.match_val)
+        {
+            Some(def) => (def.decode)(if_id.instruction, &self.registers, if_id.address),
+            None => self
+                .custom_instructions
+                .iter()
+                .find(|x| x.matches(if_id.instruction))
+                .map(|x| x.decode(if_id.instruction, &self.registers, if_id.address))?,
+        };
+
+        // RV32E only has x0..x15, the upper half of the register file doesn't exist
+        if self.profile.base_isa() == BaseIsa::Rv32E
+            && named_integer_registers(&decoded).any(|x| x >= BaseIsa::Rv32E.integer_register_count())
+        {
+            return None;
+        }
+        // a reserved rounding mode is illegal, whether the instruction names it or frm holds it
+        if let Some(OperandsFormat::Ftype { rm, .. } | OperandsFormat::R4type { rm, .. }) = decoded.operands {
+            let rm = if rm == ROUNDING_MODE_DYNAMIC { ((self.fcsr >> 5) & 0x7) as u8 } else { rm };
+            RoundingMode::from_bits(rm)?;
+        }
+        Some(decoded)
+    }
+
+    // folds the instruction behind the one decode just issued into its slot and returns whether it
+    // did. The tail gets the head's result forwarded and only fuses when nothing else makes it wait
+    fn fuse(&mut self) -> bool {
+        let (Some(head), Some(head_if_id), Some(tail_if_id)) =
+            (self.id_ex.as_ref(), self.if_id.as_ref(), self.second.if_id.as_ref())
+        else {
+            return false;
+        };
+        // an illegal head writes no register
+        let Some(rd) = destination_register(head) else {
+            return false;
+        };
+        if self.fusion_rules.fuse(head_if_id.instruction, tail_if_id.instruction).is_none() {
+            return false;
+        }
+        let Some(mut tail) = self.decode_word(tail_if_id) else {
+            return false;
+        };
+
+        let sources = source_registers(&tail);
+        let hazards = self.detect_data_hazard(&tail);
+        let waits = sources.iter().zip(&hazards).any(|(&x, hazard)| {
+            x != rd && (matches!(hazard, HazardAction::Stall) || !self.scoreboard.result_ready(x, self.stage_cycles))
+        });
+        if waits {
+            return false;
+        }
+
+        let mut head_result = (head.execute)(head).ex_mem;
+        if self.profile.base_isa().xlen() == 32 {
+            head_result.calculation_result = head_result.calculation_result as i32 as i64;
+        }
+        if let Some(operands) = tail.operands.as_mut() {
+            for (index, (&register, hazard)) in sources.iter().zip(hazards).enumerate() {
+                if register == rd {
+                    forward_operand(operands, index, head_result.calculation_result);
+                } else if let HazardAction::Forward(operand, value) = hazard {
+                    forward_operand(operands, operand, value);
                 }
-                break;
             }
         }
+        tail.energy += head.energy;
+        tail.predicted_pc = tail_if_id.predicted_pc;
+        tail.fused = Some(head_result);
+
+        self.id_ex = Some(tail);
+        (self.if_id, self.second.if_id) = (None, None);
+        true
+    }
+
+    // books the functional unit and write port the instruction needs, or counts the reason it has to
+    // wait and returns false. Without a pipeline it just takes the latency of its unit
+    fn issue(&mut self, instruction: u32, decoded: &mut IDEX) -> bool {
+        let unit = units::functional_unit(instruction);
+        let config = unit.map(|x| self.resources.unit(x)).unwrap_or_default();
+        let latency = config.latency.max(1);
+        if !self.cpu_model.is_pipelined() {
+            self.busy = self.busy.max(latency - 1);
+            return true;
+        }
+
+        let now = self.stage_cycles;
+        let rd = destination_register(decoded);
+        // an older write to rd that is still being computed must not land after this one
+        let waits_for_operand = source_registers(decoded).into_iter().any(|x| !self.scoreboard.result_ready(x, now))
+            || rd.is_some_and(|x| !self.scoreboard.result_ready(x, now + latency));
+        if waits_for_operand {
+            self.stall_stats.data_hazard += 1;
+            return false;
+        }
+        if unit.is_some_and(|x| !self.scoreboard.unit_free(x, now)) {
+            self.stall_stats.functional_unit += 1;
+            return false;
+        }
+        if rd.is_some() && !self.scoreboard.write_port_free(now + latency, self.resources.register_write_ports) {
+            self.stall_stats.write_port += 1;
+            return false;
+        }
+        decoded.booking = Some(self.scoreboard.issue(now, latency, unit.filter(|_| !config.pipelined), rd));
+        true
+    }
+
+    // decodes the older fetched instruction and issues the younger one alongside it when the two
+    // can pair. The slots of a bundle don't forward to each other, the scoreboard stalls a younger
+    // one that reads the older one's result. What didn't issue moves up to the older slot
+    fn decode_bundle(&mut self) {
+        self.second.id_ex = None;
+        self.decode();
+        if self.stall {
+            return;
+        }
+        // a fused pair takes the older slot, the younger one stays empty
+        if self.fuse() {
+            return;
+        }
+
+        let pairs = match (self.if_id.as_ref(), self.second.if_id.as_ref()) {
+            (Some(first), Some(second)) => cpu::can_pair(first.instruction, second.instruction),
+            _ => false,
+        };
+        let mut issued = false;
+        if pairs {
+            // the younger instruction not issuing isn't a stall, and it books its resources again
+            // once it is the older one
+            let saved = (self.scoreboard.clone(), self.stall_stats);
+            let swap = |vm: &mut Self| {
+                std::mem::swap(&mut vm.if_id, &mut vm.second.if_id);
+                std::mem::swap(&mut vm.id_ex, &mut vm.second.id_ex);
+            };
+            swap(self);
+            self.decode();
+            swap(self);
+
+            issued = !self.stall;
+            self.stall = false;
+            if !issued {
+                (self.scoreboard, self.stall_stats) = saved;
+            }
+        }
+
+        if issued {
+            (self.if_id, self.second.if_id) = (None, None);
+        } else {
+            self.second.id_ex = None;
+            self.if_id = self.second.if_id.take();
+        }
     }
 
-    fn execute(&mut self) {
+    // returns whether the instruction flushed the instructions behind it
+    fn execute(&mut self) -> bool {
         let id_ex = match self.id_ex.as_ref() {
             Some(v) => v,
             _ => {
                 self.ex_mem = None;
-                return;
+                return false;
             }
         };
 
-        let result = (id_ex.execute)(id_ex);
-
+        let (address, latency, energy) = (id_ex.address, id_ex.latency, id_ex.energy);
+        let (predicted_pc, fused) = (id_ex.predicted_pc, id_ex.fused.clone());
+        let mut result = (id_ex.execute)(id_ex);
+
+        // the control transfers only flush when fetch didn't follow them, they train the
+        // predictors with where execution actually continues. Without a pipeline nothing is predicted
+        let next_pc = result.new_pc.unwrap_or(predicted_pc);
+        let operands = if self.cpu_model.is_pipelined() { result.ex_mem.operands.as_ref() } else { None };
+        match operands {
+            Some(OperandsFormat::Jtype { .. }) => self.btb.update(address, next_pc, !result.flush),
+            Some(&OperandsFormat::Btype { imm, .. }) => {
+                let taken = next_pc != address.wrapping_add(4);
+                self.predictor.update(address, address.wrapping_add(imm as usize), taken);
+                if result.flush {
+                    self.predictor_stats.mispredicted += 1;
+                } else {
+                    self.predictor_stats.correct += 1;
+                }
+            }
+            _ => (),
+        }
 
-        if let Some(operands) = &result.ex_mem.operands {
-            match operands {
-                // OperandsFormat::Btype { r1, r2, r1_val, r2_val, imm } => todo!(),
-                OperandsFormat::Jtype { .. } => { self.seen_jumps.insert(id_ex.address, result.new_pc.unwrap()); },
-                _ => ()
+        self.busy = self.busy.max(latency - 1);
+        self.energy += energy;
+
+        self.fcsr |= result.fflags as u32;
+        if let Some(csr_operation) = result.csr_operation.take() {
+            match self.access_csr(&csr_operation) {
+                Some(value) => result.ex_mem.calculation_result = value,
+                None => {
+                    result.ex_mem.rd = None;
+                    result.flush = true;
+                    result.trap_type = Some(TrapType::IllegalInstruction(csr_operation.instruction));
+                }
+            }
+        }
+        if let Some(operands @ OperandsFormat::Vtype { .. }) = result.ex_mem.operands.clone() {
+            match self.execute_vector(&operands) {
+                // every element takes the latency and energy of the row
+                Ok((value, elements)) => {
+                    result.ex_mem.calculation_result = value;
+                    self.busy = self.busy.max(latency * elements.max(1) - 1);
+                    self.energy += energy * (elements.max(1) as u64 - 1);
+                }
+                Err(trap_type) => {
+                    result.ex_mem.rd = None;
+                    result.flush = true;
+                    result.trap_type = Some(trap_type);
+                }
             }
         }
+        // the tail of a fused pair writing no register leaves the result of the head to write
+        if let Some(head) = &fused
+            && matches!(result.ex_mem.rd, None | Some(0))
+        {
+            result.ex_mem.rd = head.rd;
+            result.ex_mem.calculation_result = head.calculation_result;
+        }
+        // RV32 values live in the 64 bit registers sign-extended from bit 31
+        if self.profile.base_isa().xlen() == 32 {
+            result.ex_mem.calculation_result = result.ex_mem.calculation_result as i32 as i64;
+        }
+        // an access outside of memory traps while the instructions behind it can still be flushed
+        if let Some(trap_type) = self.access_fault(&result.ex_mem) {
+            result.ex_mem.rd = None;
+            result.ex_mem.memory_operation = None;
+            result.flush = true;
+            result.trap_type = Some(trap_type);
+        }
 
         if let Some(new_pc) = result.new_pc {
             self.pc = new_pc;
         }
 
         if result.flush {
-            self.if_id = None;
+            // the flushed instructions get fetched again, or never run at all. The youngest one
+            // gives back its return stack change first
+            let flushed = [self.if1_if2.take(), self.second.if_id.take(), self.if_id.take()];
+            for if_id in flushed.into_iter().flatten() {
+                if let Some(change) = if_id.return_stack_change {
+                    self.return_stack.undo(change);
+                }
+            }
             self.id_ex = None;
         }
 
+        if !result.trap_type.as_ref().is_some_and(TrapType::is_fault) {
+            self.retired += 1 + fused.is_some() as u64;
+            self.fused_ops += fused.is_some() as u64;
+        }
         if let Some(trap_type) = result.trap_type {
             match trap_type {
                 TrapType::Ecall => {
-                    self.vm_environment.ecall(&self.registers[10..17]);
+                    let _ = self.vm_environment.ecall(&self.registers[10..17]);
                 },
                 TrapType::Ebreak => self.vm_environment.ebreak(),
+                TrapType::Wfi => self.waiting = true,
+                TrapType::FenceI => {
+                    if let Some(cache) = self.instruction_cache.as_mut() {
+                        cache.invalidate();
+                    }
+                }
+                TrapType::IllegalInstruction(instruction) => {
+                    self.fault = Some(self.illegal_instruction_fault(address, instruction));
+                }
+                TrapType::StoreOutOfRange { addr, size } => {
+                    self.fault = Some(Fault::StoreOutOfRange { address, addr, size });
+                }
+                TrapType::LoadOutOfRange { addr, size } => {
+                    self.fault = Some(Fault::LoadOutOfRange { address, addr, size });
+                }
+                TrapType::FetchOutOfRange => self.fault = Some(Fault::FetchOutOfRange { address }),
             }
         }
 
         self.ex_mem = Some(result.ex_mem);
+        result.flush
+    }
+
+    // an installed instruction only traps for naming registers the base doesn't have, anything else
+    // is named from the catalogue so the player knows what their rover is missing
+    fn illegal_instruction_fault(&self, address: usize, instruction: u32) -> Fault {
+        let installed = isa::INSTRUCTIONS.iter().any(|x| x.matches(instruction) && self.profile.installs(x));
+        match catalogue::identify(instruction) {
+            Some(known) if !installed => Fault::NotEnabled { address, instruction, known },
+            _ => Fault::IllegalInstruction { address, instruction },
+        }
     }
 
     fn memory(&mut self) {
@@ -256,7 +1122,18 @@ impl<T: VMEnvironment> VM<T> {
         let addr = ex_mem.calculation_result as usize;
         let mut value = ex_mem.calculation_result;
 
-        if let Some(mem_op) = &ex_mem.memory_operation {
+        if let Some(OperandsFormat::Atype {
+            r2_val,
+            rd_val,
+            operation,
+            aq,
+            rl,
+            ..
+        }) = &ex_mem.operands
+        {
+            value = self.atomic_memory(*operation, addr, *r2_val, *rd_val);
+            self.memory_wait = self.memory_wait.max(atypes::extra_memory_cycles(*operation, *aq, *rl));
+        } else if let Some(mem_op) = &ex_mem.memory_operation {
             if mem_op.is_load {
                 value = self.load_memory(mem_op.memory_range.clone(), addr);
             } else if let Some(OperandsFormat::Stype { r2_val, .. }) = &ex_mem.operands {
@@ -269,101 +1146,559 @@ impl<T: VMEnvironment> VM<T> {
         }
     }
 
-    fn load_memory(&self, kind: MemoryRange, addr: usize) -> i32 {
-        match kind {
-            MemoryRange::Byte => self.memory.get(addr).map_or(0, |&b| b as i8 as i32),
-            MemoryRange::ByteUnsigned => self.memory.get(addr).map_or(0, |&b| b as i32),
-            MemoryRange::Half => self.memory.get(addr..addr + 2).map_or(0, |bytes| {
-                let half = u16::from_le_bytes(bytes.try_into().unwrap());
-                half as i16 as i32
-            }),
-            MemoryRange::HalfUnsigned => self.memory.get(addr..addr + 2).map_or(0, |bytes| {
-                u16::from_le_bytes(bytes.try_into().unwrap()) as i32
-            }),
-            MemoryRange::Word => self
-                .memory
-                .get(addr..addr + 4)
-                .map_or(0, |bytes| i32::from_le_bytes(bytes.try_into().unwrap())),
-        }
-    }
-
-    fn store_memory(&mut self, kind: MemoryRange, addr: usize, value: i32) {
-        match kind {
-            MemoryRange::Byte | MemoryRange::ByteUnsigned => {
-                self.memory[addr] = value as u8;
-            }
-            MemoryRange::Half | MemoryRange::HalfUnsigned => {
-                let bytes = (value as u16).to_le_bytes();
-                self.memory[addr..addr + 2].copy_from_slice(&bytes);
-            }
-            MemoryRange::Word => {
-                let bytes = value.to_le_bytes();
-                self.memory[addr..addr + 4].copy_from_slice(&bytes);
-            }
+    // the trap of a load or store that doesn't lie entirely within memory. Atomics read 0 and write
+    // nothing instead, see `atomic_memory`
+    fn access_fault(&self, ex_mem: &EXMEM) -> Option<TrapType> {
+        let mem_op = ex_mem.memory_operation.as_ref()?;
+        if matches!(ex_mem.operands, Some(OperandsFormat::Atype { .. })) {
+            return None;
         }
+        let (addr, size) = (ex_mem.calculation_result as usize, mem_op.memory_range.size());
+        self.memory_range(addr, size).is_none().then_some(match mem_op.is_load {
+            true => TrapType::LoadOutOfRange { addr, size },
+            false => TrapType::StoreOutOfRange { addr, size },
+        })
     }
-    fn writeback(&mut self) {
-        let mem_wb = match self.mem_wb.as_ref() {
-            Some(v) => v,
-            _ => return,
-        };
 
-        if mem_wb.rd != 0 {
-            // x0 must always be 0
-            self.registers[mem_wb.rd] = mem_wb.value;
+    fn atomic_memory(&mut self, operation: AtomicOperation, addr: usize, r2_val: i64, rd_val: i64) -> i64 {
+        // a word outside of memory reads as 0 like it does for a load, is never written and can't be
+        // reserved, so the sc.w fails
+        if self.memory_range(addr, 4).is_none() {
+            return match operation {
+                AtomicOperation::LoadReserved | AtomicOperation::StoreConditional => {
+                    self.reservation = None;
+                    (operation == AtomicOperation::StoreConditional) as i64
+                }
+                _ => 0,
+            };
+        }
+        match operation {
+            AtomicOperation::LoadReserved => {
+                self.reservation = Some(addr & !0x3);
+                self.load_memory(MemoryRange::Word, addr)
+            }
+            AtomicOperation::StoreConditional => {
+                // the reservation is used up by any sc.w, whether it succeeds or not
+                if self.reservation.take() == Some(addr & !0x3) {
+                    self.store_memory(MemoryRange::Word, addr, r2_val);
+                    0
+                } else {
+                    1
+                }
+            }
+            AtomicOperation::CompareAndSwap => {
+                let loaded = self.load_memory(MemoryRange::Word, addr);
+                if loaded as i32 == rd_val as i32 {
+                    self.store_memory(MemoryRange::Word, addr, r2_val);
+                }
+                loaded
+            }
+            _ => {
+                let loaded = self.load_memory(MemoryRange::Word, addr);
+                let value = atypes::amo_value(operation, loaded as i32, r2_val as i32);
+                self.store_memory(MemoryRange::Word, addr, value as i64);
+                loaded
+            }
         }
     }
 
-    fn detect_data_hazard(&self, id_ex: &IDEX) -> HazardAction {
-        match id_ex.operands {
-            Some(OperandsFormat::Rtype { r1, r2, .. }) => self.check_steps(&[r1, r2]),
-            Some(OperandsFormat::Itype { r1, .. }) => self.check_steps(&[r1]),
-            Some(OperandsFormat::Stype { r1, r2, .. }) => self.check_steps(&[r1, r2]),
-            Some(OperandsFormat::Btype { r1, r2, .. }) => self.check_steps(&[r1, r2]),
-            _ => HazardAction::None,
+    // performs a V instruction on the vector registers and returns the value for rd and the number
+    // of elements it processed. It is an illegal instruction when vill is set, a register group is
+    // misaligned or a masked instruction would overwrite its mask in v0. A load or store running off
+    // the end of memory keeps the elements before the one that doesn't fit.
+    fn execute_vector(&mut self, operands: &OperandsFormat) -> Result<(i64, usize), TrapType> {
+        let OperandsFormat::Vtype {
+            rd,
+            r1,
+            r1_val,
+            r2_val,
+            vd,
+            vs1,
+            vs2,
+            imm,
+            masked,
+            operation,
+            instruction,
+            ..
+        } = *operands
+        else {
+            unreachable!()
+        };
+
+        if operation == VectorOperation::SetVl {
+            let avl = match (r1, rd) {
+                (0, 0) => self.vector.vl() as u64, // keeps vl
+                (0, _) => u64::MAX,                // asks for VLMAX
+                _ if self.profile.base_isa().xlen() == 32 => r1_val as u32 as u64,
+                _ => r1_val as u64,
+            };
+            return Ok((self.vector.set_vl(imm as u32, avl) as i64, 1));
         }
-    }
 
-    fn check_steps(&self, registers: &[usize]) -> HazardAction {
-        if let Some(ex_mem) = &self.ex_mem {
-            if let Some(rd) = ex_mem.rd {
-                if rd != 0 {
-                    let maybe_rd_index = registers.iter().position(|y| y == &rd);
-                    if let Some(rd_index) = maybe_rd_index {
-                        if ex_mem.memory_operation.as_ref().is_some_and(|x| x.is_load) {
-                            return HazardAction::Stall;
+        let illegal = TrapType::IllegalInstruction(instruction);
+        let sew = self.vector.vtype().ok_or(illegal.clone())?.sew;
+        let vl = self.vector.vl();
+        match operation {
+            VectorOperation::SetVl => unreachable!(),
+            VectorOperation::Load { width, strided } | VectorOperation::Store { width, strided } => {
+                let is_load = matches!(operation, VectorOperation::Load { .. });
+                if !self.vector.is_valid_group(vd, width) || (is_load && masked && vd == 0) {
+                    return Err(illegal);
+                }
+                let stride = if strided { r2_val } else { (width / 8) as i64 };
+                let range = match width {
+                    8 => MemoryRange::Byte,
+                    16 => MemoryRange::Half,
+                    32 => MemoryRange::Word,
+                    _ => MemoryRange::Double,
+                };
+                for index in 0..vl {
+                    if masked && !self.vector.is_active(index) {
+                        continue;
+                    }
+                    let addr = r1_val.wrapping_add(stride.wrapping_mul(index as i64)) as usize;
+                    let size = range.size();
+                    if is_load {
+                        if self.memory_range(addr, size).is_none() {
+                            return Err(TrapType::LoadOutOfRange { addr, size });
+                        }
+                        let value = self.load_memory(range.clone(), addr);
+                        self.vector.set_element(vd, index, width, value);
+                    } else {
+                        if self.memory_range(addr, size).is_none() {
+                            return Err(TrapType::StoreOutOfRange { addr, size });
+                        }
+                        self.store_memory(range.clone(), addr, self.vector.element(vd, index, width));
+                    }
+                }
+            }
+            VectorOperation::Elementwise(arithmetic, source) => {
+                let groups_valid = [vd, vs2].into_iter().all(|x| self.vector.is_valid_group(x, sew))
+                    && (source != VectorSource::Vector || self.vector.is_valid_group(vs1, sew));
+                if !groups_valid || (masked && vd == 0) {
+                    return Err(illegal);
+                }
+                for index in 0..vl {
+                    if masked && !self.vector.is_active(index) {
+                        continue;
+                    }
+                    let b = match source {
+                        VectorSource::Vector => self.vector.element(vs1, index, sew),
+                        VectorSource::Scalar => vector::sign_extend(r1_val, sew),
+                        VectorSource::Immediate => imm as i64,
+                    };
+                    let value = vector::arithmetic(arithmetic, self.vector.element(vs2, index, sew), b, sew);
+                    self.vector.set_element(vd, index, sew, value);
+                }
+            }
+            // vd and vs1 are single registers whatever LMUL is, only vs2 is a group
+            VectorOperation::Reduction(arithmetic) => {
+                if !self.vector.is_valid_group(vs2, sew) {
+                    return Err(illegal);
+                }
+                if vl > 0 {
+                    let mut accumulator = self.vector.element(vs1, 0, sew);
+                    for index in 0..vl {
+                        if !masked || self.vector.is_active(index) {
+                            let element = self.vector.element(vs2, index, sew);
+                            accumulator = vector::arithmetic(arithmetic, accumulator, element, sew);
                         }
-                        return HazardAction::ForwardExecute(rd_index == 0);
                     }
+                    self.vector.set_element(vd, 0, sew, accumulator);
+                }
+            }
+        }
+        Ok((0, vl))
+    }
+
+    /// Writes `bytes` on behalf of another hart or a device. A store that overlaps the word
+    /// reserved by `lr.w` breaks the reservation, so the next `sc.w` of this hart fails. Bytes that
+    /// don't all fit in memory are rejected and nothing is written.
+    pub fn external_store(&mut self, addr: usize, bytes: &[u8]) -> Result<(), MemoryRangeError> {
+        let range = self.memory_range(addr, bytes.len()).ok_or(MemoryRangeError { addr, size: bytes.len() })?;
+        if let Some(reservation) = self.reservation
+            && range.start < reservation + 4
+            && reservation < range.end
+        {
+            self.reservation = None;
+        }
+        self.memory[range].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Performs a Zicsr read-modify-write and returns the old value of the csr, `None` when the csr
+    /// doesn't exist or the access writes a read-only one, csr[11:10] == 0b11.
+    fn access_csr(&mut self, csr_operation: &CsrOperation) -> Option<i64> {
+        let old = self.read_csr(csr_operation.csr)?;
+        if csr_operation.write {
+            if csr_operation.csr >> 10 == 0b11 {
+                return None;
+            }
+            let new = match csr_operation.access {
+                CsrAccess::Write => csr_operation.value,
+                CsrAccess::Set => old | csr_operation.value,
+                CsrAccess::Clear => old & !csr_operation.value,
+            };
+            self.write_csr(csr_operation.csr, new);
+        }
+        Some(old)
+    }
+
+    // on RV32 the execute stage keeps the low half of `cycle`, `cycleh` has the high half
+    fn read_csr(&self, csr: u16) -> Option<i64> {
+        let value = match csr {
+            CSR_FFLAGS => (self.fcsr & 0x1f) as i64,
+            CSR_FRM => ((self.fcsr >> 5) & 0x7) as i64,
+            CSR_FCSR => (self.fcsr & 0xff) as i64,
+            CSR_CYCLE => self.cycle as i64,
+            CSR_CYCLEH => ((self.cycle as u64) >> 32) as i64,
+            CSR_VL => self.vector.vl() as i64,
+            // vill is the top bit
+            CSR_VTYPE => match self.vector.vtype() {
+                Some(vtype) => vtype.encode() as i64,
+                None => (1u64 << (self.profile.base_isa().xlen() - 1)) as i64,
+            },
+            CSR_VLENB => self.vector.vlenb() as i64,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn write_csr(&mut self, csr: u16, value: i64) {
+        let value = value as u32;
+        match csr {
+            CSR_FFLAGS => self.fcsr = (self.fcsr & !0x1f) | (value & 0x1f),
+            CSR_FRM => self.fcsr = (self.fcsr & !0xe0) | ((value & 0x7) << 5),
+            CSR_FCSR => self.fcsr = value & 0xff,
+            // the other csrs are read-only, `access_csr` doesn't write them
+            _ => (),
+        }
+    }
+
+    // the stall of a data access adds up with the other accesses of the instruction, a vector
+    // access can miss on many lines
+    fn access_data(&mut self, addr: usize, size: usize, write: bool) {
+        self.memory_port_busy = true;
+        self.memory_wait += memory_stall(self.data_cache.as_mut(), &self.memory_regions, addr, size, write);
+    }
+
+    // the bytes `size` bytes at `addr` cover, `None` when some of them are outside of memory
+    fn memory_range(&self, addr: usize, size: usize) -> Option<std::ops::Range<usize>> {
+        let end = addr.checked_add(size)?;
+        (end <= self.memory.len()).then_some(addr..end)
+    }
+
+    // the callers trapped on loads outside of memory already
+    fn load_memory(&mut self, kind: MemoryRange, addr: usize) -> i64 {
+        let Some(range) = self.memory_range(addr, kind.size()) else {
+            return 0;
+        };
+        self.access_data(addr, kind.size(), false);
+        let bytes = &self.memory[range];
+        match kind {
+            MemoryRange::Byte => bytes[0] as i8 as i64,
+            MemoryRange::ByteUnsigned => bytes[0] as i64,
+            MemoryRange::Half => i16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            MemoryRange::HalfUnsigned => u16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            MemoryRange::Word => i32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            MemoryRange::WordUnsigned => u32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            MemoryRange::Double => i64::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    // the callers trapped on stores outside of memory already
+    fn store_memory(&mut self, kind: MemoryRange, addr: usize, value: i64) {
+        let Some(range) = self.memory_range(addr, kind.size()) else {
+            return;
+        };
+        self.access_data(addr, kind.size(), true);
+        let bytes = &value.to_le_bytes()[..kind.size()];
+        self.memory[range].copy_from_slice(bytes);
+        self.vm_environment.store(addr, bytes);
+    }
+    fn writeback(&mut self) {
+        self.written_back = [0; 2];
+        // the younger slot writes last, its value wins when both write the same register
+        for (slot, mem_wb) in [&self.mem_wb, &self.second.mem_wb].into_iter().enumerate() {
+            let Some(mem_wb) = mem_wb else {
+                continue;
+            };
+            self.written_back[slot] = mem_wb.rd;
+
+            if mem_wb.rd != 0 {
+                // x0 must always be 0
+                self.registers[mem_wb.rd] = mem_wb.value;
+            }
+        }
+    }
+
+    fn detect_data_hazard(&self, id_ex: &IDEX) -> Vec<HazardAction> {
+        source_registers(id_ex)
+            .into_iter()
+            .enumerate()
+            .map(|(operand, register)| self.check_steps(operand, register))
+            .collect()
+    }
+
+    fn check_steps(&self, operand: usize, register: usize) -> HazardAction {
+        // x0 is hardwired to zero, values written to it must never be forwarded
+        if register == 0 {
+            return HazardAction::None;
+        }
+        let forwarding = self.hazard_policy.forwarding;
+        // the younger slot of a dual issue latch holds the newer value
+        for ex_mem in [&self.second.ex_mem, &self.ex_mem].into_iter().flatten() {
+            if ex_mem.rd == Some(register) {
+                // a load only has its value after the memory stage
+                if forwarding != Forwarding::Full || ex_mem.memory_operation.as_ref().is_some_and(|x| x.is_load) {
+                    return HazardAction::Stall;
                 }
+                return HazardAction::Forward(operand, ex_mem.calculation_result);
+            }
+        }
+        if let Some(mem1_mem2) = &self.mem1_mem2
+            && mem1_mem2.mem_wb.rd == register
+        {
+            if forwarding != Forwarding::Full || mem1_mem2.is_load {
+                return HazardAction::Stall;
             }
+            return HazardAction::Forward(operand, mem1_mem2.mem_wb.value);
         }
-        if let Some(mem_wb) = &self.mem_wb {
-            let maybe_rd_index = registers.iter().position(|y| y == &mem_wb.rd);
-            if let Some(rd_index) = maybe_rd_index {
-                return HazardAction::ForwardMemory(rd_index == 0);
+        for mem_wb in [&self.second.mem_wb, &self.mem_wb].into_iter().flatten() {
+            if mem_wb.rd == register {
+                if forwarding == Forwarding::None {
+                    return HazardAction::Stall;
+                }
+                return HazardAction::Forward(operand, mem_wb.value);
             }
         }
+        if !self.hazard_policy.split_cycle_register_file && self.written_back.contains(&register) {
+            return HazardAction::Stall;
+        }
         HazardAction::None
     }
 }
 
+fn interrupt_bit(line: u32) -> Result<u32, InterruptLineError> {
+    1u32.checked_shl(line).ok_or(InterruptLineError { line })
+}
+
+// the cycles an access stalls the pipeline for. Without a cache every access goes to memory and
+// waits for its region. With one only the misses that stall do, hits and the stores a
+// write-through cache buffers don't wait
+fn memory_stall(cache: Option<&mut Cache>, regions: &[MemoryRegion], addr: usize, size: usize, write: bool) -> usize {
+    let wait_states = region::wait_states(regions, addr, size, write);
+    match cache.map(|x| x.access(addr, size, write)) {
+        Some(0) => 0,
+        Some(miss_penalty) => miss_penalty + wait_states,
+        None => wait_states,
+    }
+}
+
+// the registers an instruction reads in decode, in the order of its operands
+fn source_registers(id_ex: &IDEX) -> Vec<usize> {
+    match id_ex.operands {
+        Some(
+            OperandsFormat::Rtype { r1, r2, .. }
+            | OperandsFormat::Stype { r1, r2, .. }
+            | OperandsFormat::Btype { r1, r2, .. }
+            | OperandsFormat::Ftype { r1, r2, .. }
+            | OperandsFormat::Custom { r1, r2, .. },
+        ) => vec![r1, r2],
+        Some(OperandsFormat::Itype { r1, .. } | OperandsFormat::Csrtype { r1, .. }) => vec![r1],
+        Some(OperandsFormat::R4type { r1, r2, r3, .. }) => vec![r1, r2, r3],
+        Some(OperandsFormat::Vtype { r1, r2, .. }) => vec![r1, r2],
+        Some(OperandsFormat::Atype {
+            rd, r1, r2, operation, ..
+        }) => match operation {
+            AtomicOperation::CompareAndSwap => vec![r1, r2, rd],
+            _ => vec![r1, r2],
+        },
+        _ => vec![],
+    }
+}
+
+// the register an instruction writes, `None` for stores, branches and x0
+fn destination_register(id_ex: &IDEX) -> Option<usize> {
+    let rd = match id_ex.operands {
+        Some(
+            OperandsFormat::Rtype { rd, .. }
+            | OperandsFormat::Itype { rd, .. }
+            | OperandsFormat::Utype { rd, .. }
+            | OperandsFormat::Jtype { rd, .. }
+            | OperandsFormat::Ftype { rd, .. }
+            | OperandsFormat::R4type { rd, .. }
+            | OperandsFormat::Csrtype { rd, .. }
+            | OperandsFormat::Atype { rd, .. }
+            | OperandsFormat::Vtype { rd, .. }
+            | OperandsFormat::Custom { rd, .. },
+        ) => rd,
+        _ => 0,
+    };
+    (rd != 0).then_some(rd)
+}
+
+// the integer registers an instruction reads or writes, floating point registers are left out
+fn named_integer_registers(id_ex: &IDEX) -> impl Iterator<Item = usize> {
+    let registers = match id_ex.operands {
+        Some(
+            OperandsFormat::Rtype { rd, r1, r2, .. }
+            | OperandsFormat::Ftype { rd, r1, r2, .. }
+            | OperandsFormat::Atype { rd, r1, r2, .. }
+            | OperandsFormat::Vtype { rd, r1, r2, .. }
+            | OperandsFormat::Custom { rd, r1, r2, .. },
+        ) => vec![rd, r1, r2],
+        Some(OperandsFormat::Itype { rd, r1, .. } | OperandsFormat::Csrtype { rd, r1, .. }) => vec![rd, r1],
+        Some(OperandsFormat::Stype { r1, r2, .. } | OperandsFormat::Btype { r1, r2, .. }) => vec![r1, r2],
+        Some(OperandsFormat::Utype { rd, .. } | OperandsFormat::Jtype { rd, .. }) => vec![rd],
+        Some(OperandsFormat::R4type { .. } | OperandsFormat::Illegal { .. }) | None => vec![],
+    };
+    registers.into_iter().filter(|x| *x < FP_REGISTER_OFFSET)
+}
+
+fn illegal_instruction(instruction: u32, address: usize) -> IDEX {
+    IDEX {
+        operands: Some(OperandsFormat::Illegal { instruction }),
+        memory_operation: None,
+        address,
+        latency: 1,
+        energy: 0,
+        predicted_pc: address.wrapping_add(4),
+        return_stack_change: None,
+        booking: None,
+        fused: None,
+        execute: |id_ex| {
+            if let Some(OperandsFormat::Illegal { instruction }) = &id_ex.operands {
+                ExecuteResult {
+                    ex_mem: EXMEM {
+                        rd: None,
+                        calculation_result: 0,
+                        memory_operation: None,
+                        operands: id_ex.operands.clone(),
+                    },
+                    flush: true,
+                    new_pc: None,
+                    trap_type: Some(TrapType::IllegalInstruction(*instruction)),
+                    fflags: 0,
+                    csr_operation: None,
+                }
+            } else {
+                unreachable!()
+            }
+        },
+    }
+}
+
+fn fetch_outside_memory(address: usize) -> IDEX {
+    IDEX {
+        operands: None,
+        memory_operation: None,
+        address,
+        latency: 1,
+        energy: 0,
+        predicted_pc: address.wrapping_add(4),
+        return_stack_change: None,
+        booking: None,
+        fused: None,
+        execute: |_| ExecuteResult {
+            ex_mem: EXMEM {
+                rd: None,
+                calculation_result: 0,
+                memory_operation: None,
+                operands: None,
+            },
+            flush: true,
+            new_pc: None,
+            trap_type: Some(TrapType::FetchOutOfRange),
+            fflags: 0,
+            csr_operation: None,
+        },
+    }
+}
+
+fn forward_operand(operands: &mut OperandsFormat, operand: usize, value: i64) {
+    match (operands, operand) {
+        (
+            OperandsFormat::Rtype { r1_val, .. }
+            | OperandsFormat::Itype { r1_val, .. }
+            | OperandsFormat::Stype { r1_val, .. }
+            | OperandsFormat::Btype { r1_val, .. }
+            | OperandsFormat::Ftype { r1_val, .. }
+            | OperandsFormat::R4type { r1_val, .. }
+            | OperandsFormat::Csrtype { r1_val, .. }
+            | OperandsFormat::Atype { r1_val, .. }
+            | OperandsFormat::Vtype { r1_val, .. }
+            | OperandsFormat::Custom { r1_val, .. },
+            0,
+        ) => *r1_val = value,
+        (
+            OperandsFormat::Rtype { r2_val, .. }
+            | OperandsFormat::Stype { r2_val, .. }
+            | OperandsFormat::Btype { r2_val, .. }
+            | OperandsFormat::Ftype { r2_val, .. }
+            | OperandsFormat::R4type { r2_val, .. }
+            | OperandsFormat::Atype { r2_val, .. }
+            | OperandsFormat::Vtype { r2_val, .. }
+            | OperandsFormat::Custom { r2_val, .. },
+            1,
+        ) => *r2_val = value,
+        (OperandsFormat::R4type { r3_val, .. }, 2) => *r3_val = value,
+        (OperandsFormat::Atype { rd_val, .. }, 2) => *rd_val = value,
+        _ => (),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{VMEnvironment, VM};
+    use std::cell::RefCell;
+
+    use super::{
+        Extension, Fault, Forwarding, HazardPolicy, InterruptLineError, IsaProfile, IsaProfileError, MemoryRangeError,
+        StallStats,
+        VMEnvironment, VmState,
+        VM,
+    };
+    use crate::vm::catalogue::{DefinedBy, KnownInstruction};
+    use crate::vm::btb::{BtbConfig, BtbStats, ReplacementPolicy};
+    use crate::vm::cache::{CacheConfig, CacheStats, WritePolicy};
+    use crate::vm::region::{MemoryRegion, MemoryRegionError};
+    use crate::vm::units::{Resources, UnitConfig};
+    use crate::vm::cpu::CpuModel;
+    use crate::vm::fusion::FusionRules;
+    use crate::vm::predictor::PredictorStats;
+    use crate::vm::custom::{CustomInstruction, CustomInstructionError, OPCODE_CUSTOM_0, OPCODE_CUSTOM_1};
+    use crate::vm::common::FP_REGISTER_OFFSET;
 
     struct MockEnv {}
 
     impl VMEnvironment for MockEnv {
-        fn ecall(&self, arguments: &[i32]) -> Result<u32, super::VmError> {
+        fn ecall(&self, _arguments: &[i64]) -> Result<u32, super::VmError> {
             Ok(0)
         }
 
-        fn ebreak(&self) -> () {
-            ()
+        fn ebreak(&self) {}
+    }
+
+    struct RecordingEnv {
+        stores: RefCell<Vec<(usize, Vec<u8>)>>,
+    }
+
+    impl VMEnvironment for RecordingEnv {
+        fn ecall(&self, _arguments: &[i64]) -> Result<u32, super::VmError> {
+            Ok(0)
+        }
+
+        fn ebreak(&self) {}
+
+        fn store(&self, address: usize, bytes: &[u8]) {
+            self.stores.borrow_mut().push((address, bytes.to_vec()));
         }
     }
 
+    fn fp(value: f32) -> i64 {
+        value.to_bits() as i32 as i64
+    }
+
     // === DATA HAZARDS ==============
 
     #[test]
@@ -451,6 +1786,149 @@ mod tests {
         vm.run();
 
         assert_eq!(vm.registers[8], 10);
+        // the BNE is predicted taken from its second iteration on, flushing on every taken branch
+        // took 34 cycles
+        assert_eq!(vm.cycle, 26);
+        assert_eq!(vm.predictor_stats(), PredictorStats { correct: 8, mispredicted: 2 });
+    }
+
+    #[test]
+    fn test_branch_predictors() {
+        use crate::vm::predictor::{
+            AlwaysTaken, Bimodal, BranchPredictor, Btfn, Gshare, StaticNotTaken, Tournament,
+        };
+        // the loop of test_bne_for_loop, the static predictors that guess backward branches taken
+        // only miss the exit, gshare needs a new counter for every history until it fills up
+        let predictors: [(Box<dyn BranchPredictor>, u64, usize); 6] = [
+            (Box::new(StaticNotTaken), 9, 34),
+            (Box::new(AlwaysTaken), 1, 25),
+            (Box::new(Btfn), 1, 25),
+            (Box::new(Bimodal::new()), 2, 26),
+            (Box::new(Gshare::new(4).unwrap()), 6, 30),
+            (Box::new(Tournament::new(4).unwrap()), 2, 26),
+        ];
+        for (predictor, mispredicted, cycles) in predictors {
+            let name = predictor.name();
+            let program = vec![0x93, 0x04, 0xa0, 0x00, 0x13, 0x04, 0x14, 0x00, 0xe3, 0x1e, 0x94, 0xfe];
+            let mut vm = VM::new(program, MockEnv {});
+            vm.set_branch_predictor(predictor);
+            vm.run();
+            assert_eq!(vm.registers[8], 10, "{name}");
+            assert_eq!(vm.predictor_stats().mispredicted, mispredicted, "{name}");
+            assert_eq!(vm.cycle, cycles, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_predictor_stats_start_over_with_a_new_predictor() {
+        use crate::vm::predictor::Btfn;
+        let program = vec![0x93, 0x04, 0xa0, 0x00, 0x13, 0x04, 0x14, 0x00, 0xe3, 0x1e, 0x94, 0xfe];
+        let mut vm = VM::new(program, MockEnv {});
+        vm.run();
+        assert_eq!(vm.predictor_stats(), PredictorStats { correct: 8, mispredicted: 2 });
+        // the counts belong to the VM, replacing the predictor drops them
+        vm.set_branch_predictor(Box::new(Btfn));
+        assert_eq!(vm.predictor_stats(), PredictorStats::default());
+    }
+
+    fn jump_loop(btb: BtbConfig) -> VM<MockEnv> {
+        let program = vec![
+            0x13, 0x04, 0x14, 0x00, // ADDI x8, x8, 1
+            0x6f, 0x00, 0x80, 0x00, // JAL x0, 8
+            0x13, 0x05, 0x15, 0x00, // ADDI x10, x10, 1
+            0x63, 0x04, 0x94, 0x00, // BEQ x8, x9, 8
+            0x6f, 0xf0, 0x1f, 0xff, // JAL x0, -16
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.configure_btb(btb).unwrap();
+        vm.registers[9] = 10;
+        vm.run();
+        assert_eq!(vm.registers[8], 10);
+        assert_eq!(vm.registers[10], 0);
+        vm
+    }
+
+    #[test]
+    fn test_btb_jumps_without_bubbles() {
+        // each jump misses once, after that fetch follows both without flushing
+        let vm = jump_loop(BtbConfig::default());
+        assert_eq!(vm.btb_stats(), BtbStats { hits: 17, misses: 2, aliasing: 0 });
+        assert_eq!(vm.cycle, 45);
+
+        let vm = jump_loop(BtbConfig { entries: 0, ..BtbConfig::default() });
+        assert_eq!(vm.btb_stats(), BtbStats { hits: 0, misses: 19, aliasing: 0 });
+        assert_eq!(vm.cycle, 61);
+    }
+
+    #[test]
+    fn test_btb_aliasing() {
+        // a single entry, the two jumps keep evicting each other
+        let config = BtbConfig { entries: 1, associativity: 1, replacement: ReplacementPolicy::FirstInFirstOut };
+        let vm = jump_loop(config);
+        assert_eq!(vm.btb_stats(), BtbStats { hits: 0, misses: 19, aliasing: 18 });
+    }
+
+    #[test]
+    fn test_prediction_of_overwritten_branch() {
+        let program = vec![
+            0x93, 0x80, 0x10, 0x00, // ADDI x1, x1, 1
+            0xe3, 0x9e, 0x20, 0xfe, // BNE x1, x2, -4
+            0x63, 0x1a, 0x03, 0x00, // BNE x6, x0, 20
+            0x23, 0x22, 0x30, 0x00, // SW x3, 4(x0)
+            0x0f, 0x10, 0x00, 0x00, // FENCE.I
+            0x13, 0x03, 0x10, 0x00, // ADDI x6, x0, 1
+            0x6f, 0xf0, 0xdf, 0xfe, // JAL x0, -20
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[2] = 3;
+        vm.registers[3] = 0x0012_8293; // ADDI x5, x5, 1
+        vm.run();
+        // the counter of the first BNE still says taken when it comes around as an ADDI, fetch only
+        // predicts words that are branches
+        assert_eq!(vm.registers[1], 3);
+        assert_eq!(vm.registers[5], 1);
+    }
+
+    // f(n) calls itself until n is 0 and counts the calls in x11, main calls f(x10) and jumps past
+    // the end of memory
+    fn recursion(depth: usize, n: i64) -> VM<MockEnv> {
+        let mut program = vec![
+            0xef, 0x00, 0x80, 0x00, // JAL ra, 8
+            0x6f, 0x00, 0xc0, 0x0f, // JAL x0, 252
+            0x63, 0x14, 0x05, 0x00, // BNE x10, x0, 8
+            0x67, 0x80, 0x00, 0x00, // JALR x0, 0(ra)
+            0x13, 0x01, 0xc1, 0xff, // ADDI sp, sp, -4
+            0x23, 0x20, 0x11, 0x00, // SW ra, 0(sp)
+            0x13, 0x05, 0xf5, 0xff, // ADDI x10, x10, -1
+            0xef, 0xf0, 0xdf, 0xfe, // JAL ra, -20
+            0x93, 0x85, 0x15, 0x00, // ADDI x11, x11, 1
+            0x83, 0x20, 0x01, 0x00, // LW ra, 0(sp)
+            0x13, 0x01, 0x41, 0x00, // ADDI sp, sp, 4
+            0x67, 0x80, 0x00, 0x00, // JALR x0, 0(ra)
+        ];
+        program.resize(256, 0);
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.configure_return_stack(depth);
+        vm.registers[2] = 256;
+        vm.registers[10] = n;
+        vm.run();
+        assert_eq!(vm.registers[11], n);
+        assert_eq!(vm.registers[1], 4);
+        assert_eq!(vm.registers[2], 256);
+        vm
+    }
+
+    #[test]
+    fn test_return_stack() {
+        // six calls deep, every return is predicted once the stack holds all of them. The first BNE
+        // is predicted not taken and fetches the leaf return, which has to give back the address it
+        // popped when it is flushed. Each return that fell off the stack costs a flush.
+        for (depth, cycles) in [(8, 57), (6, 57), (5, 58), (2, 61), (0, 63)] {
+            assert_eq!(recursion(depth, 5).cycle, cycles, "depth {depth}");
+        }
     }
 
     #[test]
@@ -496,6 +1974,37 @@ mod tests {
         assert_eq!(vm.cycle, 7);
     }
 
+    #[test]
+    fn test_cycle_count_by_hazard_policy() {
+        let nop = [0x13, 0x00, 0x00, 0x00]; // ADDI x0, x0, 0
+        let produce = [0x93, 0x00, 0x10, 0x00]; // ADDI x1, x0, 1
+        let consume = [0x13, 0x81, 0x10, 0x00]; // ADDI x2, x1, 1
+        let programs = [
+            [&produce[..], &consume].concat(),
+            [&produce[..], &nop, &consume].concat(),
+            [&produce[..], &nop, &nop, &consume].concat(),
+            // LB x1, 8(x0) followed by the use of x1 and the byte it loads
+            [&[0x83, 0x00, 0x80, 0x00][..], &consume, &[0x01]].concat(),
+        ];
+        // the cycles of each program, the instructions alone take 6, 7, 8 and 6
+        let policies = [
+            (Forwarding::Full, true, [6, 7, 8, 7]),
+            (Forwarding::MemoryOnly, true, [7, 7, 8, 7]),
+            (Forwarding::None, true, [8, 8, 8, 8]),
+            (Forwarding::Full, false, [6, 7, 9, 7]),
+            (Forwarding::None, false, [9, 9, 9, 9]),
+        ];
+        for (forwarding, split_cycle_register_file, cycles) in policies {
+            for (program, cycles) in programs.iter().zip(cycles) {
+                let mut vm = VM::new(program.clone(), MockEnv {});
+                vm.set_hazard_policy(HazardPolicy { forwarding, split_cycle_register_file });
+                vm.run();
+                assert_eq!(vm.registers[2], 2);
+                assert_eq!(vm.cycle, cycles, "{forwarding:?}, split cycle {split_cycle_register_file}");
+            }
+        }
+    }
+
     #[test]
     fn test_lb_twice_same_register() {
         // LB x8, 8(x0)
@@ -520,100 +2029,766 @@ mod tests {
         assert_eq!(vm.cycle, 6); // no stalls
     }
 
-    // === NON PIPELINED ==============
-
     #[test]
-    fn test_add() {
-        // ADD x1, x2, x3
-        let mut vm = VM::new(vec![0xb3, 0x00, 0x31, 0x00], MockEnv {});
-        vm.registers[2] = 1;
-        vm.registers[3] = 2;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[1], 3);
+    fn test_data_hazard_forward_both_operands() {
+        // ADDI x1, x0, 3
+        // ADD x2, x1, x1
+        let mut vm = VM::new(vec![0x93, 0x00, 0x30, 0x00, 0x33, 0x81, 0x10, 0x00], MockEnv {});
+        vm.run();
+        assert_eq!(vm.registers[2], 6);
+        assert_eq!(vm.cycle, 6);
     }
 
     #[test]
-    fn test_sub() {
-        // SUB x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0x84, 0xa4, 0x40], MockEnv {});
-        vm.registers[9] = 2;
-        vm.registers[10] = 1;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 1);
+    fn test_data_hazard_x0_not_forwarded() {
+        // ADDI x0, x0, 5
+        // ADD x1, x0, x0
+        let mut vm = VM::new(vec![0x13, 0x00, 0x50, 0x00, 0xb3, 0x00, 0x00, 0x00], MockEnv {});
+        vm.run();
+        assert_eq!(vm.registers[0], 0);
+        assert_eq!(vm.registers[1], 0);
     }
 
     #[test]
-    fn test_xor() {
-        // XOR x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0xc4, 0xa4, 0x00], MockEnv {});
-        vm.registers[9] = 0b11111000;
-        vm.registers[10] = 0b00011110;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 0b11100110);
+    fn test_data_hazard_fp_forwarding() {
+        let program = vec![
+            0xd3, 0x80, 0x00, 0xf0, // FMV.W.X f1, x1
+            0x53, 0x01, 0x01, 0xf0, // FMV.W.X f2, x2
+            0xd3, 0xf1, 0x20, 0x00, // FADD.S f3, f1, f2
+            0xd3, 0x81, 0x01, 0xe0, // FMV.X.W x3, f3
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[1] = fp(1.5);
+        vm.registers[2] = fp(2.25);
+        vm.run();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(3.75));
+        assert_eq!(vm.registers[3], fp(3.75));
+        assert_eq!(vm.cycle, 8);
     }
 
     #[test]
-    fn test_or() {
-        // OR x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0xe4, 0xa4, 0x00], MockEnv {});
-        vm.registers[9] = 0b11111000;
-        vm.registers[10] = 0b00011110;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 0b11111110);
+    fn test_data_hazard_flw_fadd() {
+        let program = vec![
+            0x87, 0x20, 0x00, 0x01, // FLW f1, 16(x0)
+            0x53, 0xf1, 0x10, 0x00, // FADD.S f2, f1, f1
+            0x27, 0x2a, 0x20, 0x00, // FSW f2, 20(x0)
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x00, 0x00, 0xc0, 0x3f, // 1.5
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.run();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 2], fp(3.0));
+        assert_eq!(vm.memory[20..24], 3.0f32.to_le_bytes());
+        assert_eq!(vm.cycle, 10); // 1 stall, the data word after the nop traps in execute
     }
 
     #[test]
-    fn test_and() {
-        // AND x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0xf4, 0xa4, 0x00], MockEnv {});
-        vm.registers[9] = 0b11111000;
-        vm.registers[10] = 0b00011110;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 0b00011000);
+    fn test_data_hazard_dynamic_rounding_mode() {
+        let program = vec![
+            0x73, 0x50, 0x21, 0x00, // CSRRWI x0, frm, 2 (round down)
+            0xd3, 0xf0, 0x00, 0xc0, // FCVT.W.S x1, f1, dyn
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(-1.5);
+        vm.run();
+        assert_eq!(vm.registers[1], -2);
+        assert_eq!(vm.fcsr, 0b010_00001);
+    }
+
+    // === CPU MODELS ==============
+
+    // sums 5 three times with a load-use in the first iteration, the loop branch is taken twice and
+    // the JAL jumps past the data to the end of memory
+    fn run_model(model: CpuModel) -> VM<MockEnv> {
+        let program = vec![
+            0x83, 0x20, 0xc0, 0x01, // LW x1, 28(x0)
+            0x03, 0x21, 0x00, 0x02, // LW x2, 32(x0)
+            0xb3, 0x81, 0x21, 0x00, // ADD x3, x3, x2
+            0x93, 0x80, 0xf0, 0xff, // ADDI x1, x1, -1
+            0xe3, 0x9c, 0x00, 0xfe, // BNE x1, x0, -8
+            0x23, 0x22, 0x30, 0x02, // SW x3, 36(x0)
+            0x6f, 0x00, 0x00, 0x01, // JAL x0, 16
+            0x03, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_cpu_model(model);
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[3], 15);
+        assert_eq!(vm.memory[36], 15);
+        vm
     }
 
     #[test]
-    fn test_sll() {
-        // SLL x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0x94, 0xa4, 0x00], MockEnv {});
-        vm.registers[9] = 0b00000000_00000000_00000000_11111000;
-        vm.registers[10] = 2;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 0b00000000_00000000_00000011_11100000);
+    fn test_cpu_models() {
+        // 13 instructions, the multi-cycle CPU takes 5 cycles for a load, 3 for a branch and 4 for
+        // everything else. The pipelines fill up and drain, stall on the load-use and mispredict the
+        // first and last BNE, the penalty of the JAL overlaps with the drain. Dual issue pairs the
+        // loads with the instructions behind them and the ADDs with the BNEs
+        for (model, cycles, time) in [
+            (CpuModel::SingleCycle, 13, 91),
+            (CpuModel::MultiCycle, 51, 102),
+            (CpuModel::FiveStage, 13 + 4 + 1 + 2, 40),
+            (CpuModel::SevenStage, 13 + 6 + 2 + 2 * 2, 25),
+            (CpuModel::DualIssue, 16, 32),
+        ] {
+            let vm = run_model(model);
+            assert_eq!(vm.cycle, cycles, "{model:?}");
+            assert_eq!(vm.cycle * model.cycle_time(), time, "{model:?}");
+        }
     }
 
     #[test]
-    fn test_srl() {
-        // SRL x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x00], MockEnv {});
-        vm.registers[9] = 0b00000000_00000000_00000000_11111000;
-        vm.registers[10] = 2;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 0b00000000_00000000_00000000_00111110);
+    fn test_seven_stage_hazards() {
+        let produce = [0x93, 0x00, 0x10, 0x00]; // ADDI x1, x0, 1
+        let consume = [0x13, 0x81, 0x10, 0x00]; // ADDI x2, x1, 1
+        let load = [0x83, 0x00, 0xc0, 0x00]; // LB x1, 12(x0), the byte behind the program
+        let nop = [0x13, 0x00, 0x00, 0x00]; // ADDI x0, x0, 0
+        // the two instructions take 8 cycles, three take 9. The loaded value is only there after
+        // MEM2, two stalls for a use right behind the load and one with an instruction in between
+        for (program, cycles) in [
+            ([&produce[..], &consume].concat(), 8),
+            ([&load[..], &consume, &nop, &[0x01]].concat(), 11),
+            ([&load[..], &nop, &consume, &[0x01]].concat(), 10),
+        ] {
+            let mut vm = VM::new(program, MockEnv {});
+            vm.set_cpu_model(CpuModel::SevenStage);
+            vm.run();
+            assert_eq!(vm.registers[2], 2);
+            assert_eq!(vm.cycle, cycles);
+        }
+    }
 
-        // SRL x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x00], MockEnv {});
-        vm.registers[9] = -8; // 00000000_00000000_11111111_11111000
-        vm.registers[10] = 2;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 0b00111111_11111111_11111111_11111110);
+    // === DUAL ISSUE ==============
+
+    fn run_dual_issue(program: Vec<u8>) -> VM<MockEnv> {
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_cpu_model(CpuModel::DualIssue);
+        assert_eq!(vm.run(), VmState::Halted);
+        vm
     }
 
     #[test]
-    fn test_sra() {
-        // SRA x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x40], MockEnv {});
-        vm.registers[9] = 0b00000000_00000000_00000000_11111000;
-        vm.registers[10] = 2;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], 0b00000000_00000000_00000000_00111110);
-
-        // SRA x8, x9, x10
-        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x40], MockEnv {});
-        vm.registers[9] = -8;
-        vm.registers[10] = 2;
-        vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], -2);
+    fn test_dual_issue_pairs_independent_instructions() {
+        let program = vec![
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+            0x13, 0x01, 0x20, 0x00, // ADDI x2, x0, 2
+            0x93, 0x01, 0x30, 0x00, // ADDI x3, x0, 3
+            0x13, 0x02, 0x40, 0x00, // ADDI x4, x0, 4
+            0x93, 0x02, 0x50, 0x00, // ADDI x5, x0, 5
+            0x13, 0x03, 0x60, 0x00, // ADDI x6, x0, 6
+            0x93, 0x03, 0x70, 0x00, // ADDI x7, x0, 7
+            0x13, 0x04, 0x80, 0x00, // ADDI x8, x0, 8
+        ];
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.run();
+        assert_eq!(vm.cycle, 8 + 4);
+        assert_eq!(vm.ipc(), 8.0 / 12.0);
+
+        // four bundles and the 4 cycles to drain the last one
+        let vm = run_dual_issue(program);
+        assert_eq!(vm.registers[1..9], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(vm.cycle, 4 + 4);
+        assert_eq!(vm.instructions_retired(), 8);
+        assert_eq!(vm.ipc(), 1.0);
+    }
+
+    #[test]
+    fn test_dual_issue_dependent_instructions() {
+        let chain = vec![
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+            0x93, 0x80, 0x10, 0x00, // ADDI x1, x1, 1
+            0x93, 0x80, 0x10, 0x00, // ADDI x1, x1, 1
+            0x93, 0x80, 0x10, 0x00, // ADDI x1, x1, 1
+        ];
+        let loads = vec![
+            0x83, 0x00, 0x00, 0x01, // LB x1, 16(x0)
+            0x03, 0x01, 0x10, 0x01, // LB x2, 17(x0)
+            0x13, 0x00, 0x00, 0x00, // ADDI x0, x0, 0
+            0x13, 0x00, 0x00, 0x00, // ADDI x0, x0, 0
+            0x03, 0x05,
+        ];
+        // every instruction reads what the one before wrote, they issue one by one as in the five
+        // stage pipeline
+        let vm = run_dual_issue(chain);
+        assert_eq!(vm.registers[1], 4);
+        assert_eq!(vm.cycle, 4 + 4);
+        // the loads share the memory port, the second one pairs with the first ADDI instead
+        let vm = run_dual_issue(loads);
+        assert_eq!(vm.registers[1..3], [3, 5]);
+        assert_eq!(vm.cycle, 3 + 4);
+    }
+
+    #[test]
+    fn test_dual_issue_scheduling() {
+        let load = [0x83, 0x00, 0x00, 0x01]; // LB x1, 16(x0), the byte behind the program
+        let double = [0x33, 0x81, 0x10, 0x00]; // ADD x2, x1, x1
+        let three = [0x93, 0x01, 0x30, 0x00]; // ADDI x3, x0, 3
+        let four = [0x13, 0x02, 0x40, 0x00]; // ADDI x4, x0, 4
+        // right behind the load the ADD neither pairs nor issues the next cycle. Moved down, the
+        // ADDIs fill the slots and the ADD gets the loaded value forwarded without a stall
+        for (program, cycles) in [
+            ([&load[..], &double, &three, &four, &[0x05]].concat(), 4 + 4),
+            ([&load[..], &three, &four, &double, &[0x05]].concat(), 3 + 4),
+        ] {
+            let vm = run_dual_issue(program);
+            assert_eq!(vm.registers[1..5], [5, 10, 3, 4]);
+            assert_eq!(vm.cycle, cycles);
+        }
+    }
+
+    #[test]
+    fn test_dual_issue_flushes_the_wrong_path_slot() {
+        let program = vec![
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+            0x63, 0x94, 0x00, 0x00, // BNE x1, x0, 8
+            0x93, 0x02, 0x50, 0x00, // ADDI x5, x0, 5
+            0x13, 0x03, 0x60, 0x00, // ADDI x6, x0, 6
+        ];
+        // the BNE reads x1 and issues with the ADDI behind it, which is gone once the BNE resolves
+        let vm = run_dual_issue(program);
+        assert_eq!(vm.registers[5], 0);
+        assert_eq!(vm.registers[6], 6);
+        assert_eq!(vm.predictor_stats().mispredicted, 1);
+        assert_eq!(vm.instructions_retired(), 3);
+        assert_eq!(vm.cycle, 3 + 1 + 4);
+
+        // the MUL faults on rv32i, the JAL it issued with had pushed its return address at fetch
+        let program = vec![
+            0xb3, 0x81, 0x20, 0x02, // MUL x3, x1, x2
+            0xef, 0x00, 0x80, 0x00, // JAL ra, 8
+        ];
+        let mut vm = VM::with_profile(program, MockEnv {}, "rv32i".parse().unwrap());
+        vm.set_cpu_model(CpuModel::DualIssue);
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.registers[1], 0);
+        assert_eq!(vm.return_stack.pop(), None);
+    }
+
+    #[test]
+    fn test_dual_issue_releases_the_wrong_path_slot() {
+        let program = |squashed: [u8; 4]| {
+            let mut program = vec![
+                0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+                0x63, 0x94, 0x00, 0x00, // BNE x1, x0, 8
+            ];
+            program.extend(squashed);
+            program.extend([0x33, 0x44, 0x73, 0x02]); // DIV x8, x6, x7
+            program
+        };
+        // the squashed DIV gives back the divider and its write, the DIV at the target of the BNE
+        // doesn't wait for it and the run doesn't wait for its result
+        let cycles = |squashed| {
+            let mut vm = VM::new(program(squashed), MockEnv {});
+            vm.set_cpu_model(CpuModel::DualIssue);
+            let divider = UnitConfig { latency: 8, pipelined: false };
+            vm.set_resources(Resources { divider, ..Resources::default() });
+            assert_eq!(vm.run(), VmState::Halted);
+            assert_eq!(vm.stall_stats().functional_unit, 0);
+            vm.cycle
+        };
+        assert_eq!(cycles([0xb3, 0x42, 0x73, 0x02]), cycles([0x13, 0x00, 0x00, 0x00])); // DIV x5, x6, x7 or NOP
+    }
+
+    // === CACHES ==============
+
+    #[test]
+    fn test_instruction_cache_misses() {
+        let program = vec![
+            0x93, 0x00, 0x50, 0x00, // ADDI x1, x0, 5
+            0x93, 0x80, 0xf0, 0xff, // ADDI x1, x1, -1
+            0xe3, 0x9e, 0x00, 0xfe, // BNE x1, x0, -4
+        ];
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.run();
+        assert_eq!(vm.cycle, 16);
+        assert_eq!(vm.instruction_cache_stats(), None);
+
+        // the program spans two 8 byte lines, every other fetch of the loop hits
+        let mut vm = VM::new(program, MockEnv {});
+        vm.configure_instruction_cache(CacheConfig { line_size: 8, ..CacheConfig::default() }).unwrap();
+        vm.run();
+        assert_eq!(vm.registers[1], 0);
+        assert_eq!(vm.cycle, 16 + 2 * 10);
+        assert_eq!(vm.instruction_cache_stats(), Some(CacheStats { hits: 10, misses: 2, writebacks: 0 }));
+        assert_eq!(vm.data_cache_stats(), None);
+    }
+
+    #[test]
+    fn test_data_cache_write_policies() {
+        let program = vec![
+            0x83, 0x00, 0xc0, 0x00, // LB x1, 12(x0)
+            0x03, 0x01, 0xd0, 0x00, // LB x2, 13(x0)
+            0x23, 0x07, 0x10, 0x00, // SB x1, 14(x0)
+            0x07, 0x09, 0x00,
+        ];
+        // the loads share a 2 byte line. The store misses on the next one, write-back fills it and
+        // write-through leaves it to the write buffer
+        for (write_policy, cycles) in [(WritePolicy::WriteBack, 7 + 2 * 10), (WritePolicy::WriteThrough, 7 + 10)] {
+            let mut vm = VM::new(program.clone(), MockEnv {});
+            vm.configure_data_cache(CacheConfig { line_size: 2, write_policy, ..CacheConfig::default() }).unwrap();
+            vm.run();
+            assert_eq!(vm.registers[1..3], [7, 9]);
+            assert_eq!(vm.memory[14], 7);
+            assert_eq!(vm.cycle, cycles, "{write_policy:?}");
+            assert_eq!(vm.data_cache_stats(), Some(CacheStats { hits: 1, misses: 2, writebacks: 0 }));
+        }
+    }
+
+    #[test]
+    fn test_fence_i_invalidates_instruction_cache() {
+        let program = vec![
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+            0x0f, 0x10, 0x00, 0x00, // FENCE.I
+            0x13, 0x01, 0x20, 0x00, // ADDI x2, x0, 2
+        ];
+        let mut vm = VM::new(program, MockEnv {});
+        vm.configure_instruction_cache(CacheConfig::default()).unwrap();
+        vm.run();
+        // the refetched ADDI misses on the line it hit before the FENCE.I
+        assert_eq!(vm.registers[2], 2);
+        assert_eq!(vm.instruction_cache_stats(), Some(CacheStats { hits: 2, misses: 2, writebacks: 0 }));
+        assert_eq!(vm.cycle, 8 + 2 * 10);
+    }
+
+    // === MEMORY REGIONS ==============
+
+    fn flash(size: usize) -> MemoryRegion {
+        MemoryRegion {
+            start: 0,
+            size,
+            read_latency: 3,
+            write_latency: 50,
+        }
+    }
+
+    #[test]
+    fn test_fetch_from_slow_memory() {
+        let program = vec![
+            0x93, 0x00, 0x50, 0x00, // ADDI x1, x0, 5
+            0x93, 0x80, 0xf0, 0xff, // ADDI x1, x1, -1
+            0xe3, 0x9e, 0x00, 0xfe, // BNE x1, x0, -4
+        ];
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.add_memory_region(flash(12)).unwrap();
+        vm.run();
+        // 12 fetches, the wrong path ones included, each waiting 3 cycles
+        assert_eq!(vm.registers[1], 0);
+        assert_eq!(vm.cycle, 16 + 12 * 3);
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.add_memory_region(flash(12)).unwrap();
+        vm.configure_instruction_cache(CacheConfig { line_size: 8, ..CacheConfig::default() }).unwrap();
+        // only the two misses go to the flash
+        vm.run();
+        assert_eq!(vm.cycle, 16 + 2 * (10 + 3));
+    }
+
+    #[test]
+    fn test_load_and_store_latencies() {
+        let program = vec![
+            0x83, 0x00, 0xc0, 0x00, // LB x1, 12(x0)
+            0x03, 0x01, 0xd0, 0x00, // LB x2, 13(x0)
+            0x23, 0x07, 0x10, 0x00, // SB x1, 14(x0)
+            0x07, 0x09, 0x00,
+        ];
+        let mut vm = VM::new(program, MockEnv {});
+        let radio = MemoryRegion { start: 12, size: 3, read_latency: 3, write_latency: 5 };
+        vm.add_memory_region(radio).unwrap();
+        assert_eq!(vm.add_memory_region(flash(16)), Err(MemoryRegionError::Overlap { start: 12 }));
+        vm.run();
+        assert_eq!(vm.memory[14], 7);
+        assert_eq!(vm.cycle, 7 + 3 + 3 + 5);
+    }
+
+    // === STRUCTURAL HAZARDS ==============
+
+    #[test]
+    fn test_non_pipelined_divider() {
+        let program = vec![
+            0x93, 0x00, 0xa0, 0x02, // ADDI x1, x0, 42
+            0x13, 0x01, 0x50, 0x00, // ADDI x2, x0, 5
+            0xb3, 0xc1, 0x20, 0x02, // DIV x3, x1, x2
+            0x33, 0xc2, 0x20, 0x02, // DIV x4, x1, x2
+            0x93, 0x02, 0x10, 0x00, // ADDI x5, x0, 1
+        ];
+        // with a slower divider the run ends once the last DIV is written back, 6 cycles after the
+        // ADDI behind it. Unless the divider is pipelined the second DIV waits for the first one
+        for (divider, cycles, functional_unit) in [
+            (UnitConfig::default(), 5 + 4, 0),
+            (UnitConfig { latency: 8, pipelined: true }, 5 + 4 + 6, 0),
+            (UnitConfig { latency: 8, pipelined: false }, 5 + 4 + 6 + 7, 7),
+        ] {
+            let mut vm = VM::new(program.clone(), MockEnv {});
+            vm.set_resources(Resources { divider, ..Resources::default() });
+            vm.run();
+            assert_eq!(vm.registers[3..6], [8, 8, 1]);
+            assert_eq!(vm.cycle, cycles, "{divider:?}");
+            assert_eq!(vm.stall_stats(), StallStats { functional_unit, ..StallStats::default() });
+        }
+    }
+
+    #[test]
+    fn test_multiplier_latency() {
+        let dependent = vec![
+            0xb3, 0x81, 0x20, 0x02, // MUL x3, x1, x2
+            0x33, 0x82, 0x31, 0x00, // ADD x4, x3, x3
+        ];
+        let independent = vec![
+            0xb3, 0x81, 0x20, 0x02, // MUL x3, x1, x2
+            0x93, 0x02, 0x10, 0x00, // ADDI x5, x0, 1
+        ];
+        // the ADD waits a cycle for the product. The ADDI doesn't, but its result is ready in the
+        // same cycle as the product and with one write port it has to wait for the next one
+        let multiplier = UnitConfig { latency: 2, pipelined: true };
+        let stalled = [
+            StallStats { data_hazard: 1, ..StallStats::default() },
+            StallStats::default(),
+            StallStats { write_port: 1, ..StallStats::default() },
+        ];
+        for ((program, register_write_ports), stalls) in
+            [(dependent, 2), (independent.clone(), 2), (independent, 1)].into_iter().zip(stalled)
+        {
+            let mut vm = VM::new(program, MockEnv {});
+            vm.set_resources(Resources { multiplier, register_write_ports, ..Resources::default() });
+            vm.run();
+            assert_eq!(vm.stall_stats(), stalls);
+            assert_eq!(vm.cycle, 2 + 4 + (stalls.data_hazard + stalls.write_port) as usize);
+        }
+    }
+
+    #[test]
+    fn test_shared_memory_port() {
+        let program = vec![
+            0x83, 0x00, 0x40, 0x01, // LB x1, 20(x0)
+            0x03, 0x01, 0x50, 0x01, // LB x2, 21(x0)
+            0x23, 0x0b, 0x10, 0x00, // SB x1, 22(x0)
+            0x13, 0x00, 0x00, 0x00, // ADDI x0, x0, 0
+            0x13, 0x00, 0x00, 0x00, // ADDI x0, x0, 0
+            0x07, 0x09, 0x00,
+        ];
+        for shared_memory_port in [false, true] {
+            let mut vm = VM::new(program.clone(), MockEnv {});
+            vm.set_resources(Resources { shared_memory_port, ..Resources::default() });
+            vm.run();
+            assert_eq!(vm.memory[22], 7);
+            // fetch waits while each of the three memory accesses has the port
+            let stalls = if shared_memory_port { 3 } else { 0 };
+            assert_eq!(vm.stall_stats().memory_port, stalls);
+            assert_eq!(vm.cycle, 5 + 4 + stalls as usize);
+        }
+    }
+
+    #[test]
+    fn test_stall_stats_by_reason() {
+        let program = vec![
+            0x83, 0x00, 0xc0, 0x00, // LB x1, 12(x0)
+            0x13, 0x81, 0x10, 0x00, // ADDI x2, x1, 1
+            0x13, 0x00, 0x00, 0x00, // ADDI x0, x0, 0
+            0x07,
+        ];
+        let mut vm = VM::new(program, MockEnv {});
+        vm.configure_data_cache(CacheConfig::default()).unwrap();
+        vm.run();
+        // the load misses and holds up the pipeline, then its use waits a cycle for the loaded value
+        assert_eq!(vm.registers[2], 8);
+        assert_eq!(vm.stall_stats(), StallStats { data_hazard: 1, memory: 10, ..StallStats::default() });
+        assert_eq!(vm.cycle, 3 + 4 + 1 + 10);
+    }
+
+    // === MACRO-OP FUSION ==============
+
+    fn run_fused(model: CpuModel, rules: FusionRules, program: Vec<u8>) -> VM<MockEnv> {
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_cpu_model(model);
+        vm.set_fusion_rules(rules);
+        assert_eq!(vm.run(), VmState::Halted);
+        vm
+    }
+
+    #[test]
+    fn test_fused_constant_and_zero_extension() {
+        let program = vec![
+            0xb7, 0x50, 0x34, 0x12, // LUI x1, 0x12345
+            0x93, 0x80, 0x80, 0x67, // ADDI x1, x1, 0x678
+            0x93, 0x01, 0xf0, 0xff, // ADDI x3, x0, -1
+            0x13, 0x92, 0x01, 0x01, // SLLI x4, x3, 16
+            0x13, 0x52, 0x02, 0x01, // SRLI x4, x4, 16
+        ];
+        // LUI and ADDI, then SLLI and SRLI fuse, the ADDI between them has no branch to fuse with
+        for (rules, slots, fused) in [(FusionRules::default(), 5, 0), (FusionRules::all(), 3, 2)] {
+            let vm = run_fused(CpuModel::FiveStage, rules, program.clone());
+            assert_eq!(vm.registers[1], 0x1234_5678);
+            assert_eq!(vm.registers[4], 0xffff);
+            assert_eq!(vm.cycle, slots + 4);
+            assert_eq!(vm.fused_ops(), fused);
+            assert_eq!(vm.instructions_retired(), 5);
+        }
+    }
+
+    #[test]
+    fn test_fused_branch_against_constant() {
+        let program = vec![
+            0x93, 0x80, 0x10, 0x00, // ADDI x1, x1, 1
+            0x13, 0x01, 0x50, 0x00, // ADDI x2, x0, 5
+            0xe3, 0x9c, 0x20, 0xfe, // BNE x1, x2, -8
+        ];
+        // the fused BNE still gets x1 forwarded from the ADDI before it, and the pair writes x2.
+        // The first iteration mispredicts the BNE, the last one flushes nothing
+        let li_branch = FusionRules { li_branch: true, ..FusionRules::default() };
+        for (rules, slots, fused) in [(FusionRules::default(), 15, 0), (li_branch, 10, 5)] {
+            let vm = run_fused(CpuModel::FiveStage, rules, program.clone());
+            assert_eq!(vm.registers[1..3], [5, 5]);
+            assert_eq!(vm.cycle, slots + 1 + 4);
+            assert_eq!(vm.fused_ops(), fused);
+            assert_eq!(vm.instructions_retired(), 15);
+        }
+    }
+
+    #[test]
+    fn test_fused_calls() {
+        let program = vec![
+            0x97, 0x00, 0x00, 0x00, // AUIPC ra, 0
+            0xe7, 0x80, 0x00, 0x01, // JALR ra, 16(ra)
+            0x93, 0x02, 0x50, 0x00, // ADDI x5, x0, 5
+            0x93, 0x02, 0x60, 0x00, // ADDI x5, x0, 6
+            0x17, 0x03, 0x00, 0x00, // AUIPC x6, 0
+            0x67, 0x00, 0xc3, 0x00, // JALR x0, 12(x6)
+            0x93, 0x03, 0x70, 0x00, // ADDI x7, x0, 7
+            0x13, 0x04, 0x80, 0x00, // ADDI x8, x0, 8
+        ];
+        // a call overwriting ra and a tail call keeping x6, both jumps still mispredict
+        for (rules, slots, fused) in [(FusionRules::default(), 5, 0), (FusionRules::all(), 3, 2)] {
+            let vm = run_fused(CpuModel::FiveStage, rules, program.clone());
+            assert_eq!(vm.registers[1], 8);
+            assert_eq!(vm.registers[5..9], [0, 16, 0, 8]);
+            assert_eq!(vm.cycle, slots + 2 + 4);
+            assert_eq!(vm.fused_ops(), fused);
+            assert_eq!(vm.instructions_retired(), 5);
+        }
+    }
+
+    #[test]
+    fn test_fusion_needs_registers_that_line_up() {
+        let other_register = vec![
+            0xb7, 0x50, 0x34, 0x12, // LUI x1, 0x12345
+            0x13, 0x81, 0x80, 0x67, // ADDI x2, x1, 0x678
+        ];
+        let load_use = vec![
+            0x83, 0x00, 0xc0, 0x00, // LB x1, 12(x0)
+            0x13, 0x01, 0x50, 0x00, // ADDI x2, x0, 5
+            0x63, 0x94, 0x20, 0x00, // BNE x1, x2, 8
+            0x05,
+        ];
+        // the ADDI leaves x1 live, the pair would have to write two registers
+        let vm = run_fused(CpuModel::FiveStage, FusionRules::all(), other_register);
+        assert_eq!(vm.registers[1..3], [0x1234_5000, 0x1234_5678]);
+        assert_eq!(vm.fused_ops(), 0);
+        assert_eq!(vm.cycle, 2 + 4);
+        // the BNE would wait for the load, so the ADDI issues alone and the BNE gets x1 forwarded
+        // the cycle after without a stall
+        let vm = run_fused(CpuModel::FiveStage, FusionRules::all(), load_use);
+        assert_eq!(vm.fused_ops(), 0);
+        assert_eq!(vm.cycle, 3 + 4);
+    }
+
+    #[test]
+    fn test_dual_issue_fuses_dependent_pairs() {
+        let program = vec![
+            0xb7, 0x50, 0x34, 0x12, // LUI x1, 0x12345
+            0x93, 0x80, 0x80, 0x67, // ADDI x1, x1, 0x678
+            0x37, 0x51, 0x34, 0x12, // LUI x2, 0x12345
+            0x13, 0x01, 0x81, 0x67, // ADDI x2, x2, 0x678
+        ];
+        // unfused only the ADDI x1 and the LUI x2 between the pairs can issue together
+        for (rules, bundles, fused) in [(FusionRules::default(), 3, 0), (FusionRules::all(), 2, 2)] {
+            let vm = run_fused(CpuModel::DualIssue, rules, program.clone());
+            assert_eq!(vm.registers[1..3], [0x1234_5678, 0x1234_5678]);
+            assert_eq!(vm.cycle, bundles + 4);
+            assert_eq!(vm.fused_ops(), fused);
+            assert_eq!(vm.instructions_retired(), 4);
+        }
+    }
+
+    // === WFI ==============
+
+    #[test]
+    fn test_wfi_drains_and_waits() {
+        let program = vec![
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+            0x73, 0x00, 0x50, 0x10, // WFI
+            0x13, 0x01, 0x20, 0x00, // ADDI x2, x0, 2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Waiting);
+        assert_eq!(vm.registers[1], 1); // older instruction completed
+        assert_eq!(vm.registers[2], 0); // younger instruction was drained
+        assert_eq!(vm.idle_cycles(), 0);
+
+        let cycle = vm.cycle();
+        for _ in 0..10 {
+            assert_eq!(vm.step(), VmState::Waiting);
+        }
+        assert_eq!(vm.idle_cycles(), 10);
+        assert_eq!(vm.cycle(), cycle + 10);
+
+        vm.raise_interrupt(3).unwrap();
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[2], 2);
+        assert_eq!(vm.idle_cycles(), 10);
+    }
+
+    #[test]
+    fn test_wfi_with_pending_interrupt_does_not_sleep() {
+        let program = vec![
+            0x73, 0x00, 0x50, 0x10, // WFI
+            0x13, 0x01, 0x20, 0x00, // ADDI x2, x0, 2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.raise_interrupt(0).unwrap();
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[2], 2);
+        assert_eq!(vm.idle_cycles(), 0);
+    }
+
+    #[test]
+    fn test_wfi_sleeps_again_after_interrupt_cleared() {
+        let program = vec![
+            0x73, 0x00, 0x50, 0x10, // WFI
+            0x73, 0x00, 0x50, 0x10, // WFI
+            0x13, 0x01, 0x20, 0x00, // ADDI x2, x0, 2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Waiting);
+        vm.raise_interrupt(1).unwrap();
+        vm.step();
+        vm.clear_interrupt(1).unwrap();
+        assert_eq!(vm.run(), VmState::Waiting);
+        assert_eq!(vm.registers[2], 0);
+        vm.raise_interrupt(1).unwrap();
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[2], 2);
+    }
+
+    #[test]
+    fn test_interrupt_lines() {
+        let program = vec![
+            0x73, 0x00, 0x50, 0x10, // WFI
+            0x13, 0x01, 0x20, 0x00, // ADDI x2, x0, 2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Waiting);
+        assert_eq!(vm.raise_interrupt(32), Err(InterruptLineError { line: 32 }));
+        assert_eq!(vm.clear_interrupt(u32::MAX), Err(InterruptLineError { line: u32::MAX }));
+        assert_eq!(vm.step(), VmState::Waiting);
+        vm.raise_interrupt(31).unwrap();
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[2], 2);
+    }
+
+    // === NON PIPELINED ==============
+
+    #[test]
+    fn test_add() {
+        // ADD x1, x2, x3
+        let mut vm = VM::new(vec![0xb3, 0x00, 0x31, 0x00], MockEnv {});
+        vm.registers[2] = 1;
+        vm.registers[3] = 2;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[1], 3);
+    }
+
+    #[test]
+    fn test_sub() {
+        // SUB x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0x84, 0xa4, 0x40], MockEnv {});
+        vm.registers[9] = 2;
+        vm.registers[10] = 1;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 1);
+    }
+
+    #[test]
+    fn test_xor() {
+        // XOR x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0xc4, 0xa4, 0x00], MockEnv {});
+        vm.registers[9] = 0b11111000;
+        vm.registers[10] = 0b00011110;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 0b11100110);
+    }
+
+    #[test]
+    fn test_or() {
+        // OR x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0xe4, 0xa4, 0x00], MockEnv {});
+        vm.registers[9] = 0b11111000;
+        vm.registers[10] = 0b00011110;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 0b11111110);
+    }
+
+    #[test]
+    fn test_and() {
+        // AND x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0xf4, 0xa4, 0x00], MockEnv {});
+        vm.registers[9] = 0b11111000;
+        vm.registers[10] = 0b00011110;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 0b00011000);
+    }
+
+    #[test]
+    fn test_sll() {
+        // SLL x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0x94, 0xa4, 0x00], MockEnv {});
+        vm.registers[9] = 0b00000000_00000000_00000000_11111000;
+        vm.registers[10] = 2;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 0b00000000_00000000_00000011_11100000);
+    }
+
+    #[test]
+    fn test_srl() {
+        // SRL x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x00], MockEnv {});
+        vm.registers[9] = 0b00000000_00000000_00000000_11111000;
+        vm.registers[10] = 2;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 0b00000000_00000000_00000000_00111110);
+
+        // SRL x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x00], MockEnv {});
+        vm.registers[9] = -8; // 00000000_00000000_11111111_11111000
+        vm.registers[10] = 2;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 0b00111111_11111111_11111111_11111110);
+    }
+
+    #[test]
+    fn test_sra() {
+        // SRA x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x40], MockEnv {});
+        vm.registers[9] = 0b00000000_00000000_00000000_11111000;
+        vm.registers[10] = 2;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], 0b00000000_00000000_00000000_00111110);
+
+        // SRA x8, x9, x10
+        let mut vm = VM::new(vec![0x33, 0xd4, 0xa4, 0x40], MockEnv {});
+        vm.registers[9] = -8;
+        vm.registers[10] = 2;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[8], -2);
     }
 
     #[test]
@@ -767,13 +2942,13 @@ mod tests {
         // LB x8, 4(x0)
         let mut vm = VM::new(vec![0x03, 0x04, 0x40, 0x00, 0x7F], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], i8::MAX as i32); // 127
+        assert_eq!(vm.registers[8], i8::MAX as i64); // 127
 
 
         // LB x8, 4(x0)
         let mut vm = VM::new(vec![0x03, 0x04, 0x40, 0x00, 0x80], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], i8::MIN as i32); // -128
+        assert_eq!(vm.registers[8], i8::MIN as i64); // -128
     }
 
     #[test]
@@ -781,12 +2956,12 @@ mod tests {
         // LH x8, 4(x0)
         let mut vm = VM::new(vec![0x03, 0x14, 0x40, 0x00, 0xFF, 0x7F], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], i16::MAX as i32); // 32767
+        assert_eq!(vm.registers[8], i16::MAX as i64); // 32767
 
         // LH x8, 4(x0)
         let mut vm = VM::new(vec![0x03, 0x14, 0x40, 0x00, 0x00, 0x80], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], i16::MIN as i32); // -32768
+        assert_eq!(vm.registers[8], i16::MIN as i64); // -32768
     }
 
     #[test]
@@ -797,7 +2972,7 @@ mod tests {
             0xFF, 0xFF, 0xFF, 0x7F
         ], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], i32::MAX); // 2147483647
+        assert_eq!(vm.registers[8], i32::MAX as i64); // 2147483647
 
         // LW x8, 4(x0)
         let mut vm = VM::new(vec![
@@ -805,7 +2980,7 @@ mod tests {
             0x00, 0x00, 0x00, 0x80
         ], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], i32::MIN); // -2147483648
+        assert_eq!(vm.registers[8], i32::MIN as i64); // -2147483648
     }
 
 
@@ -814,7 +2989,7 @@ mod tests {
         // LBU x8, 4(x0)
         let mut vm = VM::new(vec![0x03, 0x44, 0x40, 0x00, 0xFF], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], u8::MAX as i32); // 255
+        assert_eq!(vm.registers[8], u8::MAX as i64); // 255
     }
 
     #[test]
@@ -822,7 +2997,7 @@ mod tests {
         // LHU x8, 4(x0)
         let mut vm = VM::new(vec![0x03, 0x54, 0x40, 0x00, 0xFF, 0xFF], MockEnv {});
         vm.step_no_pipeline();
-        assert_eq!(vm.registers[8], u16::MAX as i32); // 65535 
+        assert_eq!(vm.registers[8], u16::MAX as i64); // 65535 
     }
 
     #[test]
@@ -1016,13 +3191,1099 @@ mod tests {
         assert_eq!(vm.registers[10], 0);
     }
 
-    // === J TYPES ===
+    // === F EXTENSION ===
 
     #[test]
-    fn test_jal() {
-        // JAL x1, 8
-        // ADDI x2, x0, 42
-        // ADDI x3, x0, 99
+    fn test_fadd_fsub_fmul_fdiv() {
+        // FADD.S f3, f1, f2
+        let mut vm = VM::new(vec![0xd3, 0xf1, 0x20, 0x00], MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(1.5);
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(0.25);
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(1.75));
+
+        // FSUB.S f3, f1, f2
+        let mut vm = VM::new(vec![0xd3, 0xf1, 0x20, 0x08], MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(1.5);
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(0.25);
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(1.25));
+
+        // FMUL.S f3, f1, f2
+        let mut vm = VM::new(vec![0xd3, 0xf1, 0x20, 0x10], MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(1.5);
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(-4.0);
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(-6.0));
+        assert_eq!(vm.fcsr, 0);
+
+        // FDIV.S f3, f1, f2
+        let mut vm = VM::new(vec![0xd3, 0xf1, 0x20, 0x18], MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(1.0);
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(3.0);
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(1.0 / 3.0));
+        assert_eq!(vm.fcsr, 0b00001); // inexact
+    }
+
+    #[test]
+    fn test_fdiv_by_zero_flag() {
+        let program = vec![
+            0xd3, 0xf1, 0x20, 0x18, // FDIV.S f3, f1, f2
+            0xf3, 0x22, 0x10, 0x00, // CSRRS x5, fflags, x0
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(-2.0);
+        vm.run();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(f32::NEG_INFINITY));
+        assert_eq!(vm.registers[5], 0b01000);
+    }
+
+    #[test]
+    fn test_fmul_overflow_flags() {
+        let program = vec![
+            0xd3, 0xf1, 0x20, 0x10, // FMUL.S f3, f1, f2
+            0xf3, 0x20, 0x10, 0x00, // CSRRS x1, fflags, x0
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(f32::MAX);
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(2.0);
+        vm.run();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(f32::INFINITY));
+        assert_eq!(vm.registers[1], 0b00101); // overflow and inexact
+    }
+
+    #[test]
+    fn test_fsqrt_invalid() {
+        // FSQRT.S f2, f1
+        let mut vm = VM::new(vec![0x53, 0xf1, 0x00, 0x58], MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(2.25);
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 2], fp(1.5));
+        assert_eq!(vm.fcsr, 0);
+
+        // FSQRT.S f2, f1
+        let mut vm = VM::new(vec![0x53, 0xf1, 0x00, 0x58], MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(-1.0);
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 2], 0x7fc00000); // canonical NaN
+        assert_eq!(vm.fcsr, 0b10000);
+    }
+
+    #[test]
+    fn test_fcvt_w_s_rounding_modes() {
+        // FCVT.W.S x1, f1 with rne, rtz, rdn, rup and rmm
+        let encodings = [
+            [0xd3, 0x80, 0x00, 0xc0],
+            [0xd3, 0x90, 0x00, 0xc0],
+            [0xd3, 0xa0, 0x00, 0xc0],
+            [0xd3, 0xb0, 0x00, 0xc0],
+            [0xd3, 0xc0, 0x00, 0xc0],
+        ];
+        let cases: [(f32, [i32; 5]); 3] = [
+            (2.5, [2, 2, 2, 3, 3]),
+            (-2.5, [-2, -2, -3, -2, -3]),
+            (3.0, [3, 3, 3, 3, 3]),
+        ];
+
+        for (value, expected) in cases {
+            for (encoding, expected) in encodings.iter().zip(expected) {
+                let mut vm = VM::new(encoding.to_vec(), MockEnv {});
+                vm.registers[FP_REGISTER_OFFSET + 1] = fp(value);
+                vm.step_no_pipeline();
+                assert_eq!(vm.registers[1], expected as i64, "{value} with {encoding:x?}");
+            }
+        }
+
+        // FCVT.W.S x1, f1, rtz
+        let mut vm = VM::new(vec![0xd3, 0x90, 0x00, 0xc0], MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(1e10);
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[1], i32::MAX as i64);
+        assert_eq!(vm.fcsr, 0b10000); // invalid
+    }
+
+    #[test]
+    fn test_reserved_rounding_modes() {
+        // FCVT.W.S x1, f1 with the reserved rm 0b101 and 0b110
+        for instruction in [0xc000_d0d3u32, 0xc000_e0d3] {
+            let mut vm = VM::new(instruction.to_le_bytes().to_vec(), MockEnv {});
+            assert_eq!(vm.run(), VmState::Faulted);
+            assert_eq!(vm.fault(), Some(&Fault::IllegalInstruction { address: 0, instruction }));
+        }
+
+        // the dynamic rounding mode with frm set to 5, 6 or 7
+        for csrrwi in [[0x73, 0xd0, 0x22, 0x00], [0x73, 0x50, 0x23, 0x00], [0x73, 0xd0, 0x23, 0x00]] {
+            let mut program = csrrwi.to_vec(); // CSRRWI x0, frm, 5..=7
+            program.extend([0xd3, 0xf0, 0x00, 0xc0]); // FCVT.W.S x1, f1, dyn
+            let mut vm = VM::new(program, MockEnv {});
+            vm.registers[1] = 9;
+            assert_eq!(vm.run(), VmState::Faulted, "{csrrwi:x?}");
+            assert_eq!(vm.fault(), Some(&Fault::IllegalInstruction { address: 4, instruction: 0xc000_f0d3 }));
+            assert_eq!(vm.registers[1], 9);
+        }
+    }
+
+    #[test]
+    fn test_fcvt_s_w() {
+        // FCVT.S.W f1, x1
+        let mut vm = VM::new(vec![0xd3, 0xf0, 0x00, 0xd0], MockEnv {});
+        vm.registers[1] = -7;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 1], fp(-7.0));
+        assert_eq!(vm.fcsr, 0);
+
+        // FCVT.S.W f1, x1, rtz
+        let mut vm = VM::new(vec![0xd3, 0x90, 0x00, 0xd0], MockEnv {});
+        vm.registers[1] = 16777217;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 1], fp(16777216.0));
+        assert_eq!(vm.fcsr, 0b00001);
+    }
+
+    #[test]
+    fn test_fmadd_single_rounding() {
+        let program = vec![
+            0x43, 0xf2, 0x20, 0x18, // FMADD.S f4, f1, f2, f3
+            0xd3, 0xf2, 0x20, 0x10, // FMUL.S f5, f1, f2
+            0x53, 0xf3, 0x32, 0x00, // FADD.S f6, f5, f3
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(1.0 + f32::EPSILON);
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(1.0 - f32::EPSILON);
+        vm.registers[FP_REGISTER_OFFSET + 3] = fp(-1.0);
+        vm.run();
+        // the fused version keeps the product exact, the split version rounds it to 1.0
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 4], fp(-f32::EPSILON * f32::EPSILON));
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 6], fp(0.0));
+    }
+
+    #[test]
+    fn test_fmin_fmax_fclass() {
+        let program = vec![
+            0xd3, 0x81, 0x20, 0x28, // FMIN.S f3, f1, f2
+            0x53, 0x92, 0x20, 0x28, // FMAX.S f4, f1, f2
+            0xd3, 0x90, 0x00, 0xe0, // FCLASS.S x1, f1
+        ];
+
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = fp(-0.0);
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(0.0);
+        vm.run();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(-0.0));
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 4], fp(0.0));
+        assert_eq!(vm.registers[1], 1 << 3); // negative zero
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = 0x7fc00000;
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(2.0);
+        vm.run();
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 3], fp(2.0));
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 4], fp(2.0));
+        assert_eq!(vm.registers[1], 1 << 9); // quiet NaN
+        assert_eq!(vm.fcsr, 0);
+    }
+
+    #[test]
+    fn test_fp_compare_nan() {
+        let program = vec![
+            0xd3, 0x90, 0x20, 0xa0, // FLT.S x1, f1, f2
+            0x73, 0x21, 0x10, 0x00, // CSRRS x2, fflags, x0
+            0xd3, 0xa1, 0x20, 0xa0, // FEQ.S x3, f1, f2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[FP_REGISTER_OFFSET + 1] = 0x7fc00000;
+        vm.registers[FP_REGISTER_OFFSET + 2] = fp(1.0);
+        vm.registers[3] = 5;
+        vm.run();
+        assert_eq!(vm.registers[1], 0);
+        assert_eq!(vm.registers[2], 0b10000); // flt signals on quiet NaN
+        assert_eq!(vm.registers[3], 0);
+    }
+
+    #[test]
+    fn test_csr_fcsr_access() {
+        let program = vec![
+            0xf3, 0xd0, 0x3f, 0x00, // CSRRWI x1, fcsr, 0x1f
+            0x73, 0xf1, 0x10, 0x00, // CSRRCI x2, fflags, 1
+            0xf3, 0x21, 0x30, 0x00, // CSRRS x3, fcsr, x0
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.fcsr = 0b101_00000;
+        vm.run();
+        assert_eq!(vm.registers[1], 0b101_00000);
+        assert_eq!(vm.registers[2], 0x1f);
+        assert_eq!(vm.registers[3], 0x1e);
+    }
+
+    #[test]
+    fn test_csr_write_to_read_only_counter_traps() {
+        let program = vec![
+            0xf3, 0x20, 0x00, 0xc0, // CSRRS x1, cycle, x0
+            0x73, 0x90, 0x00, 0xc0, // CSRRW x0, cycle, x1
+            0x13, 0x01, 0x20, 0x00, // ADDI x2, x0, 2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.fault(), Some(&Fault::IllegalInstruction { address: 4, instruction: 0xc000_9073 }));
+        assert_eq!(vm.registers[2], 0);
+    }
+
+    #[test]
+    fn test_unimplemented_csr_traps() {
+        let program = vec![
+            0xf3, 0x20, 0x00, 0x34, // CSRRS x1, mscratch, x0
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[1] = 7;
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.fault(), Some(&Fault::IllegalInstruction { address: 0, instruction: 0x3400_20f3 }));
+        assert_eq!(vm.registers[1], 7);
+    }
+
+    // === A EXTENSION ===
+
+    #[test]
+    fn test_lr_sc_success() {
+        let program = vec![
+            0xaf, 0x20, 0x01, 0x10, // LR.W x1, (x2)
+            0x93, 0x80, 0x10, 0x00, // ADDI x1, x1, 1
+            0xaf, 0x21, 0x11, 0x18, // SC.W x3, x1, (x2)
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x41, 0x00, 0x00, 0x00,
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[2] = 16;
+        vm.registers[3] = 5;
+        vm.run();
+        assert_eq!(vm.registers[1], 0x42);
+        assert_eq!(vm.registers[3], 0); // success
+        assert_eq!(vm.memory[16], 0x42);
+        assert_eq!(vm.reservation, None);
+    }
+
+    #[test]
+    fn test_sc_without_reservation_fails() {
+        let program = vec![
+            0xaf, 0x21, 0x11, 0x18, // SC.W x3, x1, (x2)
+            0x41, 0x00, 0x00, 0x00,
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[1] = 7;
+        vm.registers[2] = 4;
+        vm.run();
+        assert_eq!(vm.registers[3], 1);
+        assert_eq!(vm.memory[4], 0x41);
+    }
+
+    #[test]
+    fn test_sc_fails_after_store_from_other_hart() {
+        let program = vec![
+            0xaf, 0x20, 0x01, 0x10, // LR.W x1, (x2)
+            0x93, 0x80, 0x10, 0x00, // ADDI x1, x1, 1
+            0xaf, 0x21, 0x11, 0x18, // SC.W x3, x1, (x2)
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x41, 0x00, 0x00, 0x00,
+        ];
+        let mut hart0 = VM::new(program, MockEnv {});
+        hart0.registers[2] = 16;
+        while hart0.reservation.is_none() {
+            hart0.step();
+        }
+
+        // SH x1, 18(x0), only overlaps part of the reserved word
+        let env = RecordingEnv { stores: RefCell::new(vec![]) };
+        let mut memory = vec![0x23, 0x19, 0x10, 0x00];
+        memory.resize(20, 0);
+        let mut hart1 = VM::new(memory, env);
+        hart1.registers[1] = 7;
+        hart1.run();
+        for (address, bytes) in hart1.vm_environment.stores.borrow().iter() {
+            hart0.external_store(*address, bytes).unwrap();
+        }
+
+        hart0.run();
+        assert_eq!(hart0.registers[1], 0x42);
+        assert_eq!(hart0.registers[3], 1); // failure
+        assert_eq!(hart0.memory[16..20], [0x41, 0x00, 0x07, 0x00]);
+    }
+
+    #[test]
+    fn test_external_store_elsewhere_keeps_reservation() {
+        let program = vec![
+            0xaf, 0x20, 0x01, 0x10, // LR.W x1, (x2)
+            0xaf, 0x21, 0x11, 0x18, // SC.W x3, x1, (x2)
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x41, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[2] = 16;
+        while vm.reservation.is_none() {
+            vm.step();
+        }
+        vm.external_store(20, &[1, 2, 3, 4]).unwrap();
+        vm.run();
+        assert_eq!(vm.registers[3], 0);
+    }
+
+    #[test]
+    fn test_external_store_outside_of_memory() {
+        let mut vm = VM::new(vec![0; 8], MockEnv {});
+        assert_eq!(vm.external_store(6, &[1, 2, 3]), Err(MemoryRangeError { addr: 6, size: 3 }));
+        assert_eq!(vm.external_store(usize::MAX, &[1]), Err(MemoryRangeError { addr: usize::MAX, size: 1 }));
+        assert_eq!(vm.memory, [0; 8]);
+        assert_eq!(vm.external_store(5, &[1, 2, 3]), Ok(()));
+        assert_eq!(vm.memory[5..], [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_atomics_outside_of_memory() {
+        let program = vec![
+            0xaf, 0xa0, 0x21, 0x00, // AMOADD.W x1, x2, (x3)
+            0xaf, 0x22, 0x02, 0x10, // LR.W x5, (x4)
+            0x2f, 0x23, 0x22, 0x18, // SC.W x6, x2, (x4)
+            0x13, 0x00, 0x00, 0x00, // NOP
+        ];
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.registers[1] = 9;
+        vm.registers[2] = 3;
+        vm.registers[3] = -4;
+        vm.registers[4] = 14; // the word straddles the end of memory
+        vm.registers[5] = 9;
+        vm.run();
+        assert_eq!(vm.registers[1], 0);
+        assert_eq!(vm.registers[5], 0);
+        assert_eq!(vm.registers[6], 1); // failure
+        assert_eq!(vm.memory, program);
+    }
+
+    #[test]
+    fn test_amo_operations() {
+        // AMO<op>.W x1, x2, (x3)
+        let cases: [([u8; 4], i32); 10] = [
+            ([0xaf, 0xa0, 0x21, 0x08], 3),          // amoswap
+            ([0xaf, 0xa0, 0x21, 0x00], -2),         // amoadd
+            ([0xaf, 0xa0, 0x21, 0x20], -5 ^ 3),     // amoxor
+            ([0xaf, 0xa0, 0x21, 0x60], -5 & 3),     // amoand
+            ([0xaf, 0xa0, 0x21, 0x40], -5 | 3),     // amoor
+            ([0xaf, 0xa0, 0x21, 0x80], -5),         // amomin
+            ([0xaf, 0xa0, 0x21, 0xa0], 3),          // amomax
+            ([0xaf, 0xa0, 0x21, 0xc0], 3),          // amominu
+            ([0xaf, 0xa0, 0x21, 0xe0], -5),         // amomaxu
+            ([0xaf, 0xa0, 0x21, 0x28], -5),         // amocas, x1 doesn't hold the old value
+        ];
+
+        for (encoding, expected) in cases {
+            let mut program = encoding.to_vec();
+            program.extend((-5i32).to_le_bytes());
+            let mut vm = VM::new(program, MockEnv {});
+            vm.set_extension(Extension::Zacas, true).unwrap();
+            vm.registers[2] = 3;
+            vm.registers[3] = 4;
+            vm.run();
+            assert_eq!(vm.registers[1], -5, "{encoding:x?}");
+            assert_eq!(vm.memory[4..8], expected.to_le_bytes(), "{encoding:x?}");
+        }
+    }
+
+    #[test]
+    fn test_amocas_swaps_when_equal() {
+        let program = vec![
+            0x93, 0x00, 0xb0, 0xff, // ADDI x1, x0, -5
+            0xaf, 0xa0, 0x21, 0x28, // AMOCAS.W x1, x2, (x3)
+            0xfb, 0xff, 0xff, 0xff,
+        ];
+
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.set_extension(Extension::Zacas, true).unwrap();
+        vm.registers[2] = 3;
+        vm.registers[3] = 8;
+        vm.run();
+        assert_eq!(vm.registers[1], -5);
+        assert_eq!(vm.memory[8..12], 3i32.to_le_bytes());
+
+        // amocas.w is Zacas, not A
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(
+            vm.fault(),
+            Some(&Fault::NotEnabled {
+                address: 4,
+                instruction: 0x2821_a0af,
+                known: KnownInstruction {
+                    mnemonic: "amocas.w",
+                    defined_by: DefinedBy::Profile(Extension::Zacas),
+                    rv64: false,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_atomic_memory_cycles() {
+        let cycles = |encoding: [u8; 4]| {
+            let mut program = encoding.to_vec();
+            program.extend([0x41, 0x00, 0x00, 0x00]);
+            let mut vm = VM::new(program, MockEnv {});
+            vm.registers[3] = 4;
+            vm.run();
+            vm.cycle
+        };
+
+        let lw = cycles([0x83, 0xa0, 0x01, 0x00]); // LW x1, 0(x3)
+        assert_eq!(cycles([0xaf, 0xa0, 0x01, 0x10]), lw); // LR.W x1, (x3)
+        assert_eq!(cycles([0xaf, 0xa0, 0x21, 0x00]), lw + 1); // AMOADD.W x1, x2, (x3)
+        assert_eq!(cycles([0xaf, 0xa0, 0x21, 0x04]), lw + 2); // AMOADD.W.AQ x1, x2, (x3)
+        assert_eq!(cycles([0xaf, 0xa0, 0x21, 0x06]), lw + 3); // AMOADD.W.AQRL x1, x2, (x3)
+    }
+
+    #[test]
+    fn test_amo_result_forwarded() {
+        let program = vec![
+            0xaf, 0xa0, 0x21, 0x00, // AMOADD.W x1, x2, (x3)
+            0x33, 0x82, 0x10, 0x00, // ADD x4, x1, x1
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x15, 0x00, 0x00, 0x00,
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[2] = 1;
+        vm.registers[3] = 12;
+        vm.run();
+        assert_eq!(vm.registers[4], 0x2a);
+        assert_eq!(vm.memory[12], 0x16);
+    }
+
+    // === ISA PROFILE ===
+
+    #[test]
+    fn test_instruction_outside_profile_faults() {
+        let program = vec![
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+            0xb3, 0x81, 0x20, 0x02, // MUL x3, x1, x2
+            0x13, 0x02, 0x10, 0x00, // ADDI x4, x0, 1
+        ];
+
+        let mut vm = VM::with_profile(program, MockEnv {}, "rv32i".parse().unwrap());
+        vm.registers[2] = 2;
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.registers[1], 1); // older instruction completed
+        assert_eq!(vm.registers[3], 0);
+        assert_eq!(vm.registers[4], 0); // younger instruction was flushed
+        assert_eq!(
+            vm.fault(),
+            Some(&Fault::NotEnabled {
+                address: 4,
+                instruction: 0x022081b3,
+                known: KnownInstruction { mnemonic: "mul", defined_by: DefinedBy::Profile(Extension::M), rv64: false },
+            })
+        );
+    }
+
+    #[test]
+    fn test_fault_names_disabled_instruction() {
+        let mut program = [0x13, 0x00, 0x00, 0x00].repeat(16); // NOP
+        program.extend([
+            0xd3, 0x70, 0x31, 0x10, // FMUL.S f1, f2, f3
+        ]);
+
+        let mut vm = VM::with_profile(program, MockEnv {}, "rv32im".parse().unwrap());
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(
+            vm.fault().unwrap().to_string(),
+            "`fmul.s` (F extension) at 0x40 is not enabled on this rover"
+        );
+    }
+
+    #[test]
+    fn test_fault_names_unimplemented_instruction() {
+        let program = vec![
+            0xd3, 0x70, 0x31, 0x12, // FMUL.D f1, f2, f3
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(
+            vm.fault().unwrap().to_string(),
+            "`fmul.d` (D extension) at 0x0 is not enabled on this rover"
+        );
+
+        let mut vm = VM::new(vec![0xff, 0xff, 0xff, 0xff], MockEnv {});
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.fault().unwrap().to_string(), "illegal instruction 0xffffffff at 0x0");
+    }
+
+    #[test]
+    fn test_unlocked_extension_does_not_fault() {
+        let program = vec![
+            0xb3, 0x81, 0x20, 0x02, // MUL x3, x1, x2
+        ];
+
+        let mut vm = VM::with_profile(program, MockEnv {}, IsaProfile::base());
+        vm.set_extension(Extension::M, true).unwrap();
+        vm.registers[1] = 3;
+        vm.registers[2] = 2;
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[3], 6);
+        assert_eq!(vm.profile().to_string(), "rv32im");
+    }
+
+    #[test]
+    fn test_illegal_instruction_behind_taken_jump_does_not_fault() {
+        let program = vec![
+            0x6f, 0x00, 0x80, 0x00, // JAL x0, 8
+            0xff, 0xff, 0xff, 0xff, // illegal
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.fault(), None);
+        assert_eq!(vm.registers[1], 1);
+    }
+
+    #[test]
+    fn test_rv32e_upper_registers_fault() {
+        let program = vec![
+            0x93, 0x07, 0x10, 0x00, // ADDI x15, x0, 1
+            0x33, 0x88, 0x20, 0x00, // ADD x16, x1, x2
+        ];
+
+        let mut vm = VM::with_profile(program, MockEnv {}, "rv32e".parse().unwrap());
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.registers[15], 1);
+        assert_eq!(
+            vm.fault(),
+            Some(&Fault::IllegalInstruction {
+                address: 4,
+                instruction: 0x00208833
+            })
+        );
+
+        // SW x20, 0(x0), a source register is just as illegal
+        let mut vm = VM::with_profile(vec![0x23, 0x20, 0x40, 0x01], MockEnv {}, "rv32e".parse().unwrap());
+        assert_eq!(vm.run(), VmState::Faulted);
+    }
+
+    #[test]
+    fn test_rv32e_keeps_fp_registers() {
+        // FMV.W.X f20, x1
+        let mut vm = VM::with_profile(vec![0x53, 0x8a, 0x00, 0xf0], MockEnv {}, "rv32ef".parse().unwrap());
+        vm.registers[1] = 7;
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[FP_REGISTER_OFFSET + 20], 7);
+    }
+
+    #[test]
+    fn test_register_dump() {
+        let mut vm = VM::with_profile(vec![], MockEnv {}, "rv32e".parse().unwrap());
+        vm.registers[1] = 4;
+        vm.registers[16] = 5; // not part of rv32e
+        let dump = vm.register_dump();
+        assert_eq!(dump.lines().count(), 4);
+        assert_eq!(
+            dump.lines().next(),
+            Some("x0  = 0x00000000  x1  = 0x00000004  x2  = 0x00000000  x3  = 0x00000000")
+        );
+        assert!(!dump.contains("x16"));
+        assert_eq!(vm.integer_registers().len(), 16);
+
+        let vm = VM::new(vec![], MockEnv {});
+        assert_eq!(vm.register_dump().lines().count(), 8);
+    }
+
+    // === M EXTENSION ===
+
+    #[test]
+    fn test_mul_div_rem() {
+        let program = vec![
+            0xb3, 0x81, 0x20, 0x02, // MUL x3, x1, x2
+            0x33, 0x92, 0x20, 0x02, // MULH x4, x1, x2
+            0xb3, 0xa2, 0x20, 0x02, // MULHSU x5, x1, x2
+            0x33, 0xb3, 0x20, 0x02, // MULHU x6, x1, x2
+            0xb3, 0xc3, 0x20, 0x02, // DIV x7, x1, x2
+            0x33, 0xd4, 0x20, 0x02, // DIVU x8, x1, x2
+            0xb3, 0xe4, 0x20, 0x02, // REM x9, x1, x2
+            0x33, 0xf5, 0x20, 0x02, // REMU x10, x1, x2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[1] = -7;
+        vm.registers[2] = 2;
+        vm.run();
+        assert_eq!(vm.registers[3], -14);
+        assert_eq!(vm.registers[4], -1);
+        assert_eq!(vm.registers[5], -1);
+        assert_eq!(vm.registers[6], 1);
+        assert_eq!(vm.registers[7], -3);
+        assert_eq!(vm.registers[8], 2147483644);
+        assert_eq!(vm.registers[9], -1);
+        assert_eq!(vm.registers[10], 1);
+    }
+
+    #[test]
+    fn test_div_rem_edge_cases() {
+        let program = vec![
+            0xb3, 0xc1, 0x00, 0x02, // DIV x3, x1, x0
+            0x33, 0xd2, 0x00, 0x02, // DIVU x4, x1, x0
+            0xb3, 0xe2, 0x00, 0x02, // REM x5, x1, x0
+            0x33, 0xf3, 0x00, 0x02, // REMU x6, x1, x0
+            0xb3, 0xc3, 0x20, 0x02, // DIV x7, x1, x2
+            0x33, 0xe4, 0x20, 0x02, // REM x8, x1, x2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[1] = i32::MIN as i64;
+        vm.registers[2] = -1;
+        vm.run();
+        assert_eq!(vm.registers[3], -1);
+        assert_eq!(vm.registers[4], -1);
+        assert_eq!(vm.registers[5], i32::MIN as i64);
+        assert_eq!(vm.registers[6], i32::MIN as i64);
+        assert_eq!(vm.registers[7], i32::MIN as i64); // overflow
+        assert_eq!(vm.registers[8], 0);
+    }
+
+    // === CUSTOM INSTRUCTIONS ===
+
+    // vec2.dist, manhattan distance between two points packed as (x << 16) | y
+    fn vec2_dist() -> CustomInstruction {
+        CustomInstruction {
+            name: "vec2.dist",
+            mask: 0xfe00707f,
+            match_val: OPCODE_CUSTOM_0,
+            execute: |_, a, b| {
+                let dx = (a >> 16) - (b >> 16);
+                let dy = (a as i16 as i32) - (b as i16 as i32);
+                dx.abs() + dy.abs()
+            },
+            latency: 1,
+            energy: 5,
+        }
+    }
+
+    #[test]
+    fn test_custom_instruction() {
+        let program = vec![
+            0x93, 0x00, 0x30, 0x00, // ADDI x1, x0, 3
+            0x8b, 0x81, 0x20, 0x00, // VEC2.DIST x3, x1, x2
+        ];
+
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.register_custom_instruction(vec2_dist()).unwrap();
+        vm.registers[2] = (4 << 16) | 0xffff; // (4, -1)
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[3], 8);
+        assert_eq!(vm.energy(), 6); // 1 for the addi and 5 for vec2.dist
+        let cycles = vm.cycle;
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.register_custom_instruction(CustomInstruction {
+            latency: 4,
+            ..vec2_dist()
+        })
+        .unwrap();
+        vm.registers[2] = (4 << 16) | 0xffff;
+        vm.run();
+        assert_eq!(vm.registers[3], 8);
+        assert_eq!(vm.cycle, cycles + 3);
+    }
+
+    #[test]
+    fn test_custom_instruction_immediate_bits() {
+        // SCAN x4, x1, x0 with funct3 = 1 and 5 in the funct7 bits
+        let mut vm = VM::new(vec![0x2b, 0x92, 0x00, 0x0a], MockEnv {});
+        vm.register_custom_instruction(CustomInstruction {
+            name: "scan",
+            mask: 0x707f,
+            match_val: 0x1000 | OPCODE_CUSTOM_1,
+            execute: |instruction, a, _| a + (instruction >> 25) as i32,
+            latency: 2,
+            energy: 40,
+        })
+        .unwrap();
+        vm.registers[1] = 10;
+        vm.run();
+        assert_eq!(vm.registers[4], 15);
+        assert_eq!(vm.energy(), 40);
+    }
+
+    #[test]
+    fn test_custom_instruction_validation() {
+        let mut vm = VM::new(vec![], MockEnv {});
+
+        let add = CustomInstruction { match_val: 0x33, ..vec2_dist() };
+        assert_eq!(vm.register_custom_instruction(add), Err(CustomInstructionError::NotInCustomSpace));
+
+        let loose_opcode = CustomInstruction { mask: 0xfe00703f, ..vec2_dist() };
+        assert_eq!(vm.register_custom_instruction(loose_opcode), Err(CustomInstructionError::NotInCustomSpace));
+
+        let outside_mask = CustomInstruction { match_val: 0x8000_000b, mask: 0x707f, ..vec2_dist() };
+        assert_eq!(vm.register_custom_instruction(outside_mask), Err(CustomInstructionError::MatchOutsideMask));
+
+        let instant = CustomInstruction { latency: 0, ..vec2_dist() };
+        assert_eq!(vm.register_custom_instruction(instant), Err(CustomInstructionError::ZeroLatency));
+
+        vm.register_custom_instruction(vec2_dist()).unwrap();
+        // funct3 0 with any funct7 also matches vec2.dist
+        let wide = CustomInstruction { mask: 0x707f, ..vec2_dist() };
+        assert_eq!(
+            vm.register_custom_instruction(wide),
+            Err(CustomInstructionError::Overlap {
+                mask: 0xfe00707f,
+                match_val: OPCODE_CUSTOM_0
+            })
+        );
+
+        let other_funct3 = CustomInstruction { match_val: 0x2000 | OPCODE_CUSTOM_0, ..vec2_dist() };
+        assert_eq!(vm.register_custom_instruction(other_funct3), Ok(()));
+    }
+
+    // === BIT MANIPULATION ===
+
+    #[test]
+    fn test_bitmanip_disabled_by_default() {
+        // SH1ADD x3, x1, x2
+        let mut vm = VM::new(vec![0xb3, 0xa1, 0x20, 0x20], MockEnv {});
+        vm.registers[1] = 5;
+        vm.registers[2] = 1;
+        vm.registers[3] = 7;
+        vm.run();
+        assert_eq!(vm.registers[3], 7);
+        assert!(!vm.has_extension(Extension::Zba));
+    }
+
+    #[test]
+    fn test_bitmanip_unlocked_at_runtime() {
+        let program = vec![
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0xb3, 0xa1, 0x20, 0x20, // SH1ADD x3, x1, x2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[1] = 5;
+        vm.registers[2] = 1;
+        vm.step();
+        vm.set_extension(Extension::Zba, true).unwrap();
+        vm.run();
+        assert_eq!(vm.registers[3], 11);
+
+        vm.set_extension(Extension::Zba, false).unwrap();
+        assert!(!vm.has_extension(Extension::Zba));
+    }
+
+    #[test]
+    fn test_zba() {
+        let program = vec![
+            0xb3, 0xa1, 0x20, 0x20, // SH1ADD x3, x1, x2
+            0x33, 0xc2, 0x20, 0x20, // SH2ADD x4, x1, x2
+            0xb3, 0xe2, 0x20, 0x20, // SH3ADD x5, x1, x2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zba, true).unwrap();
+        vm.registers[1] = 3;
+        vm.registers[2] = 100;
+        vm.run();
+        assert_eq!(vm.registers[3], 106);
+        assert_eq!(vm.registers[4], 112);
+        assert_eq!(vm.registers[5], 124);
+    }
+
+    #[test]
+    fn test_zbb_counting() {
+        let program = vec![
+            0x13, 0x91, 0x00, 0x60, // CLZ x2, x1
+            0x93, 0x91, 0x10, 0x60, // CTZ x3, x1
+            0x13, 0x92, 0x20, 0x60, // CPOP x4, x1
+        ];
+
+        let mut vm = VM::new(program.clone(), MockEnv {});
+        vm.set_extension(Extension::Zbb, true).unwrap();
+        vm.registers[1] = 0x00f0_0100;
+        vm.run();
+        assert_eq!(vm.registers[2], 8);
+        assert_eq!(vm.registers[3], 8);
+        assert_eq!(vm.registers[4], 5);
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zbb, true).unwrap();
+        vm.run();
+        assert_eq!(vm.registers[2], 32);
+        assert_eq!(vm.registers[3], 32);
+        assert_eq!(vm.registers[4], 0);
+    }
+
+    #[test]
+    fn test_zbb_logic_and_min_max() {
+        let program = vec![
+            0xb3, 0xf1, 0x20, 0x40, // ANDN x3, x1, x2
+            0x33, 0xe2, 0x20, 0x40, // ORN x4, x1, x2
+            0xb3, 0xc2, 0x20, 0x40, // XNOR x5, x1, x2
+            0x33, 0xe3, 0x20, 0x0a, // MAX x6, x1, x2
+            0xb3, 0xf3, 0x20, 0x0a, // MAXU x7, x1, x2
+            0x33, 0xc4, 0x20, 0x0a, // MIN x8, x1, x2
+            0xb3, 0xd4, 0x20, 0x0a, // MINU x9, x1, x2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zbb, true).unwrap();
+        vm.registers[1] = -4;
+        vm.registers[2] = 6;
+        vm.run();
+        assert_eq!(vm.registers[3], -4 & !6);
+        assert_eq!(vm.registers[4], -4 | !6);
+        assert_eq!(vm.registers[5], !(-4 ^ 6));
+        assert_eq!(vm.registers[6], 6);
+        assert_eq!(vm.registers[7], -4);
+        assert_eq!(vm.registers[8], -4);
+        assert_eq!(vm.registers[9], 6);
+    }
+
+    #[test]
+    fn test_zbb_bytes_and_rotates() {
+        let program = vec![
+            0x13, 0x91, 0x40, 0x60, // SEXT.B x2, x1
+            0x93, 0x91, 0x50, 0x60, // SEXT.H x3, x1
+            0x33, 0xc2, 0x00, 0x08, // ZEXT.H x4, x1
+            0x93, 0xd2, 0x40, 0x60, // RORI x5, x1, 4
+            0x13, 0xd3, 0x70, 0x28, // ORC.B x6, x1
+            0x93, 0xd3, 0x80, 0x69, // REV8 x7, x1
+            0x33, 0x94, 0xa0, 0x60, // ROL x8, x1, x10
+            0xb3, 0xd4, 0xa0, 0x60, // ROR x9, x1, x10
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zbb, true).unwrap();
+        vm.registers[1] = 0x1200_8081;
+        vm.registers[10] = 36; // only the low 5 bits count
+        vm.run();
+        assert_eq!(vm.registers[2], 0xffff_ff81_u32 as i32 as i64);
+        assert_eq!(vm.registers[3], 0xffff_8081_u32 as i32 as i64);
+        assert_eq!(vm.registers[4], 0x8081);
+        assert_eq!(vm.registers[5], 0x1120_0808);
+        assert_eq!(vm.registers[6], 0xff00_ffff_u32 as i32 as i64);
+        assert_eq!(vm.registers[7], 0x8180_0012_u32 as i32 as i64);
+        assert_eq!(vm.registers[8], 0x2008_0811);
+        assert_eq!(vm.registers[9], 0x1120_0808);
+    }
+
+    #[test]
+    fn test_zbs() {
+        let program = vec![
+            0xb3, 0x91, 0x20, 0x48, // BCLR x3, x1, x2
+            0x33, 0x92, 0x20, 0x28, // BSET x4, x1, x2
+            0xb3, 0x92, 0x20, 0x68, // BINV x5, x1, x2
+            0x33, 0xd3, 0x20, 0x48, // BEXT x6, x1, x2
+            0x93, 0x93, 0xf0, 0x49, // BCLRI x7, x1, 31
+            0x13, 0x94, 0x40, 0x28, // BSETI x8, x1, 4
+            0x93, 0x94, 0x00, 0x68, // BINVI x9, x1, 0
+            0x13, 0xd5, 0xf0, 0x49, // BEXTI x10, x1, 31
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zbs, true).unwrap();
+        vm.registers[1] = 0x8000_0009_u32 as i32 as i64;
+        vm.registers[2] = 35; // bit 3
+        vm.run();
+        assert_eq!(vm.registers[3], 0x8000_0001_u32 as i32 as i64);
+        assert_eq!(vm.registers[4], 0x8000_0009_u32 as i32 as i64);
+        assert_eq!(vm.registers[5], 0x8000_0001_u32 as i32 as i64);
+        assert_eq!(vm.registers[6], 1);
+        assert_eq!(vm.registers[7], 0x9);
+        assert_eq!(vm.registers[8], 0x8000_0019_u32 as i32 as i64);
+        assert_eq!(vm.registers[9], 0x8000_0008_u32 as i32 as i64);
+        assert_eq!(vm.registers[10], 1);
+    }
+
+    // === SCALAR CRYPTO ===
+
+    #[test]
+    fn test_aes32() {
+        let program = vec![
+            0xb3, 0x81, 0x20, 0x22, // AES32ESI x3, x1, x2, 0
+            0x33, 0x82, 0x51, 0x66, // AES32ESMI x4, x3, x5, 1
+            0x33, 0x03, 0x70, 0xea, // AES32DSI x6, x0, x7, 3
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zkne, true).unwrap();
+        vm.set_extension(Extension::Zknd, true).unwrap();
+        vm.registers[1] = 0x1000;
+        vm.registers[2] = 0x53; // the S-box maps 0x53 to 0xed
+        vm.registers[5] = 0x5300;
+        vm.registers[7] = 0xed00_0000_u32 as i32 as i64;
+        vm.run();
+        assert_eq!(vm.registers[3], 0x10ed);
+        // the MixColumns column (2, 1, 1, 3) * 0xed rotated into place for row 1, the forwarded x3
+        // xored in
+        assert_eq!(vm.registers[4], 0xeded_d1c1_u32 as i32 as i64);
+        assert_eq!(vm.registers[6], 0x5300_0000);
+    }
+
+    #[test]
+    fn test_sha2() {
+        let program = vec![
+            0x13, 0x94, 0x24, 0x10, // SHA256SIG0 x8, x9
+            0x33, 0x85, 0xc5, 0x50, // SHA512SUM0R x10, x11, x12
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zknh, true).unwrap();
+        vm.registers[9] = 0x6162_6380;
+        vm.registers[11] = 0x89ab_cdef_u32 as i32 as i64;
+        vm.registers[12] = 0x0123_4567;
+        vm.run();
+        assert_eq!(vm.registers[8], 0x940e_90ef_u32 as i32 as i64);
+        // the low half of Σ0(0x0123456789abcdef)
+        assert_eq!(vm.registers[10], 0x0c7e_c1ab);
+    }
+
+    #[test]
+    fn test_crypto_gated_by_profile() {
+        // AES32ESI x3, x1, x2, 0
+        let mut vm = VM::new(vec![0xb3, 0x81, 0x20, 0x22], MockEnv {});
+        vm.set_extension(Extension::Zknd, true).unwrap();
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(
+            vm.fault(),
+            Some(&Fault::NotEnabled {
+                address: 0,
+                instruction: 0x2220_81b3,
+                known: KnownInstruction {
+                    mnemonic: "aes32esi",
+                    defined_by: DefinedBy::Profile(Extension::Zkne),
+                    rv64: false,
+                },
+            })
+        );
+    }
+
+    // === ZICOND ===
+
+    #[test]
+    fn test_czero() {
+        let program = vec![
+            0xb3, 0xd1, 0x20, 0x0e, // CZERO.EQZ x3, x1, x2
+            0x33, 0xf2, 0x20, 0x0e, // CZERO.NEZ x4, x1, x2
+            0xb3, 0xd2, 0x00, 0x0e, // CZERO.EQZ x5, x1, x0
+            0x33, 0xf3, 0x00, 0x0e, // CZERO.NEZ x6, x1, x0
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zicond, true).unwrap();
+        vm.registers[1] = -9;
+        vm.registers[2] = 4;
+        vm.run();
+        assert_eq!(vm.registers[3], -9);
+        assert_eq!(vm.registers[4], 0);
+        assert_eq!(vm.registers[5], 0);
+        assert_eq!(vm.registers[6], -9);
+    }
+
+    #[test]
+    fn test_czero_forwarding() {
+        let program = vec![
+            0x93, 0x00, 0x70, 0x00, // ADDI x1, x0, 7
+            0x13, 0x01, 0x10, 0x00, // ADDI x2, x0, 1
+            0xb3, 0xd1, 0x20, 0x0e, // CZERO.EQZ x3, x1, x2
+            0x33, 0xf2, 0x30, 0x0e, // CZERO.NEZ x4, x1, x3
+            0x83, 0x02, 0x80, 0x01, // LB x5, 24(x0)
+            0x33, 0xd3, 0x50, 0x0e, // CZERO.EQZ x6, x1, x5
+            // data at 24 for LB x5, its low byte 0x13 is the loaded condition. It runs as a NOP
+            // after the program
+            0x13, 0x00, 0x00, 0x00,
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.set_extension(Extension::Zicond, true).unwrap();
+        assert_eq!(vm.run(), VmState::Halted);
+        // the value comes from memory and the condition from execute, then the condition in turn
+        assert_eq!(vm.registers[3], 7);
+        assert_eq!(vm.registers[4], 0);
+        // the condition is loaded right before, which stalls once on top of the 7 + 4 cycles
+        assert_eq!(vm.registers[5], 0x13);
+        assert_eq!(vm.registers[6], 7);
+        assert_eq!(vm.cycle, 12);
+    }
+
+    // runs a program that leaves a result in x3 and x3 + 1 in x4, returns x3 and the cycles taken
+    fn run_branchless(program: &[u8], x1: i64, x2: i64) -> (i64, usize) {
+        let mut vm = VM::new(program.to_vec(), MockEnv {});
+        vm.set_extension(Extension::Zicond, true).unwrap();
+        vm.registers[1] = x1;
+        vm.registers[2] = x2;
+        vm.run();
+        assert_eq!(vm.registers[4], vm.registers[3] + 1);
+        (vm.registers[3], vm.cycle)
+    }
+
+    #[test]
+    fn test_branchless_zeroing_cycles() {
+        // x3 = x2 == 0 ? 0 : x1
+        let branchy = [
+            0xb3, 0x81, 0x00, 0x00, // ADD x3, x1, x0
+            0x63, 0x14, 0x01, 0x00, // BNE x2, x0, 8
+            0xb3, 0x01, 0x00, 0x00, // ADD x3, x0, x0
+            0x13, 0x82, 0x11, 0x00, // ADDI x4, x3, 1
+        ];
+        let branchless = [
+            0xb3, 0xd1, 0x20, 0x0e, // CZERO.EQZ x3, x1, x2
+            0x13, 0x82, 0x11, 0x00, // ADDI x4, x3, 1
+        ];
+
+        // skipping the ADD saves nothing, the taken branch flushes the instruction fetched behind it
+        assert_eq!(run_branchless(&branchy, 5, 1), (5, 8));
+        assert_eq!(run_branchless(&branchy, 5, 0), (0, 8));
+        assert_eq!(run_branchless(&branchless, 5, 1), (5, 6));
+        assert_eq!(run_branchless(&branchless, 5, 0), (0, 6));
+    }
+
+    #[test]
+    fn test_branchless_select_cycles() {
+        // x3 = x1 < x2 ? x1 : x2
+        let branchy = [
+            0xb3, 0xa2, 0x20, 0x00, // SLT x5, x1, x2
+            0x63, 0x86, 0x02, 0x00, // BEQ x5, x0, 12
+            0xb3, 0x81, 0x00, 0x00, // ADD x3, x1, x0
+            0x6f, 0x00, 0x80, 0x00, // JAL x0, 8
+            0xb3, 0x01, 0x01, 0x00, // ADD x3, x2, x0
+            0x13, 0x82, 0x11, 0x00, // ADDI x4, x3, 1
+        ];
+        let branchless = [
+            0xb3, 0xa2, 0x20, 0x00, // SLT x5, x1, x2
+            0x33, 0xd3, 0x50, 0x0e, // CZERO.EQZ x6, x1, x5
+            0xb3, 0x73, 0x51, 0x0e, // CZERO.NEZ x7, x2, x5
+            0xb3, 0x61, 0x73, 0x00, // OR x3, x6, x7
+            0x13, 0x82, 0x11, 0x00, // ADDI x4, x3, 1
+        ];
+
+        // both ways through the branchy select end in a taken branch or jump, the branchless one
+        // costs the same whatever the data
+        assert_eq!(run_branchless(&branchy, 1, 2), (1, 10));
+        assert_eq!(run_branchless(&branchy, 2, 1), (1, 9));
+        assert_eq!(run_branchless(&branchless, 1, 2), (1, 9));
+        assert_eq!(run_branchless(&branchless, 2, 1), (1, 9));
+    }
+
+    // === J TYPES ===
+
+    #[test]
+    fn test_jal() {
+        // JAL x1, 8
+        // ADDI x2, x0, 42
+        // ADDI x3, x0, 99
 
         let program = vec![
             0xef, 0x00, 0x80, 0x00, // JAL x1, 8
@@ -1039,6 +4300,36 @@ mod tests {
         assert_eq!(vm.registers[3], 99); // x3 set by ADDI
     }
 
+    #[test]
+    fn test_jal_backwards() {
+        let program = vec![
+            0x6f, 0x00, 0xc0, 0x00, // JAL x0, 12
+            0x13, 0x01, 0x70, 0x00, // ADDI x2, x0, 7
+            0x6f, 0x00, 0x80, 0x00, // JAL x0, 8
+            0xef, 0xf0, 0x9f, 0xff, // JAL x1, -8
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[1], 16);
+        assert_eq!(vm.registers[2], 7);
+    }
+
+    #[test]
+    fn test_jalr_register_target() {
+        let program = vec![
+            0x93, 0x02, 0x30, 0x00, // ADDI x5, x0, 3
+            0xe7, 0x80, 0x92, 0x00, // JALR x1, 9(x5), the lowest bit of the target is cleared
+            0x93, 0x01, 0x10, 0x00, // ADDI x3, x0, 1 (skipped)
+            0x13, 0x02, 0x20, 0x00, // ADDI x4, x0, 2
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[1], 8);
+        assert_eq!(vm.registers[3], 0);
+        assert_eq!(vm.registers[4], 2);
+    }
 
     // === U TYPES ===
 
@@ -1057,4 +4348,390 @@ mod tests {
         vm.step_no_pipeline();
         assert_eq!(vm.registers[8], 4096);
     }
+
+    // === FENCE ===
+
+    const ADDI_X3_7: i32 = 0x00700193;
+
+    #[test]
+    fn test_store_to_fetched_instruction_is_not_seen() {
+        let program = vec![
+            0x23, 0x24, 0x20, 0x00, // SW x2, 8(x0)
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x93, 0x01, 0x10, 0x00, // ADDI x3, x0, 1, already fetched when the store commits
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[2] = ADDI_X3_7 as i64;
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[3], 1);
+        assert_eq!(vm.memory[8..12], ADDI_X3_7.to_le_bytes());
+    }
+
+    #[test]
+    fn test_store_to_unfetched_instruction_is_seen() {
+        let program = vec![
+            0x23, 0x26, 0x20, 0x00, // SW x2, 12(x0)
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x13, 0x00, 0x00, 0x00, // NOP
+            0x93, 0x01, 0x10, 0x00, // ADDI x3, x0, 1, fetched in the cycle the store commits
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[2] = ADDI_X3_7 as i64;
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[3], 7);
+    }
+
+    #[test]
+    fn test_fence_i_refetches_stored_instruction() {
+        let program = vec![
+            0x23, 0x24, 0x20, 0x00, // SW x2, 8(x0)
+            0x0f, 0x10, 0x00, 0x00, // FENCE.I
+            0x93, 0x01, 0x10, 0x00, // ADDI x3, x0, 1
+        ];
+
+        let mut vm = VM::new(program, MockEnv {});
+        vm.registers[2] = ADDI_X3_7 as i64;
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[3], 7);
+    }
+
+    #[test]
+    fn test_fence() {
+        let program = vec![
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+            0x0f, 0x00, 0xf0, 0x0f, // FENCE iorw, iorw
+            0x13, 0x81, 0x10, 0x00, // ADDI x2, x1, 1
+        ];
+
+        let mut vm = VM::with_profile(program, MockEnv {}, "rv32i".parse().unwrap());
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[2], 2);
+    }
+
+    // === RV64 ===
+
+    fn rv64(program: Vec<u8>) -> VM<MockEnv> {
+        VM::with_profile(program, MockEnv {}, "rv64im".parse().unwrap())
+    }
+
+    #[test]
+    fn test_rv64_rejects_rv32_only_extensions() {
+        let mut vm = rv64(vec![]);
+        let error = IsaProfileError::UnsupportedExtension("zbs".to_string());
+        assert_eq!(vm.set_extension(Extension::Zbs, true), Err(error));
+        assert!(!vm.has_extension(Extension::Zbs));
+        assert_eq!(vm.set_extension(Extension::Zicond, true), Ok(()));
+    }
+
+    #[test]
+    fn test_rv64_add_and_addw() {
+        let program = vec![
+            0x33, 0x81, 0x10, 0x00, // ADD x2, x1, x1
+            0xbb, 0x81, 0x10, 0x00, // ADDW x3, x1, x1
+        ];
+
+        let mut vm = rv64(program.clone());
+        vm.registers[1] = i32::MAX as i64;
+        vm.step_no_pipeline();
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[2], 0xffff_fffe);
+        assert_eq!(vm.registers[3], -2); // sign-extended from bit 31
+
+        // the same add wraps at 32 bits on RV32
+        let mut vm = VM::with_profile(program, MockEnv {}, "rv32i".parse().unwrap());
+        vm.registers[1] = i32::MAX as i64;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[2], -2);
+    }
+
+    #[test]
+    fn test_rv64_doubleword_memory() {
+        let mut program = vec![
+            0x23, 0x38, 0x10, 0x00, // SD x1, 16(x0)
+            0x03, 0x31, 0x00, 0x01, // LD x2, 16(x0)
+            0x83, 0x21, 0x00, 0x01, // LW x3, 16(x0)
+            0x03, 0x62, 0x00, 0x01, // LWU x4, 16(x0)
+        ];
+        program.extend([0; 8]);
+
+        let mut vm = rv64(program);
+        vm.registers[1] = 0x1122_3344_8899_aabb;
+        for _ in 0..4 {
+            vm.step_no_pipeline();
+        }
+        assert_eq!(vm.memory[16..24], 0x1122_3344_8899_aabb_i64.to_le_bytes());
+        assert_eq!(vm.registers[2], 0x1122_3344_8899_aabb);
+        assert_eq!(vm.registers[3], 0x8899_aabb_u32 as i32 as i64);
+        assert_eq!(vm.registers[4], 0x8899_aabb);
+    }
+
+    #[test]
+    fn test_rv64_wide_shifts() {
+        let program = vec![
+            0x13, 0x91, 0x80, 0x02, // SLLI x2, x1, 40
+            0x93, 0x51, 0x81, 0x42, // SRAI x3, x2, 40
+            0x1b, 0x52, 0x41, 0x40, // SRAIW x4, x2, 4
+        ];
+
+        let mut vm = rv64(program);
+        vm.registers[1] = 0x80_0001;
+        for _ in 0..3 {
+            vm.step_no_pipeline();
+        }
+        assert_eq!(vm.registers[2], 0x8000_0100_0000_0000_u64 as i64);
+        assert_eq!(vm.registers[3], 0xffff_ffff_ff80_0001_u64 as i64);
+        assert_eq!(vm.registers[4], 0); // only the low word is shifted
+    }
+
+    #[test]
+    fn test_rv64_mulh() {
+        // MULH x3, x1, x2
+        let mut vm = rv64(vec![0xb3, 0x91, 0x20, 0x02]);
+        vm.registers[1] = i64::MIN;
+        vm.registers[2] = 4;
+        vm.step_no_pipeline();
+        assert_eq!(vm.registers[3], -2);
+    }
+
+    #[test]
+    fn test_rv64_instruction_faults_on_rv32() {
+        // ADDW x3, x1, x1
+        let mut vm = VM::with_profile(vec![0xbb, 0x81, 0x10, 0x00], MockEnv {}, "rv32i".parse().unwrap());
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(
+            vm.fault(),
+            Some(&Fault::NotEnabled {
+                address: 0,
+                instruction: 0x0010_81bb,
+                known: KnownInstruction { mnemonic: "addw", defined_by: DefinedBy::Base, rv64: true },
+            })
+        );
+    }
+
+    #[test]
+    fn test_rv64_register_dump() {
+        let mut vm = rv64(vec![]);
+        vm.registers[1] = -1;
+        assert_eq!(
+            vm.register_dump().lines().next(),
+            Some("x0  = 0x0000000000000000  x1  = 0xffffffffffffffff  x2  = 0x0000000000000000  x3  = 0x0000000000000000")
+        );
+    }
+
+    // === V EXTENSION ===
+
+    fn rv32v(program: Vec<u8>) -> VM<MockEnv> {
+        VM::with_profile(program, MockEnv {}, "rv32iv_zicsr".parse().unwrap())
+    }
+
+    #[test]
+    fn test_vector_add_and_reduce() {
+        let mut program = vec![
+            0xd7, 0x72, 0x05, 0x0d, // VSETVLI x5, x10, e32, m1, ta, ma
+            0x87, 0xe0, 0x05, 0x02, // VLE32.V v1, (x11)
+            0x57, 0x81, 0x10, 0x02, // VADD.VV v2, v1, v1
+            0xd7, 0x21, 0x20, 0x02, // VREDSUM.VS v3, v2, v0
+            0x27, 0x61, 0x06, 0x02, // VSE32.V v2, (x12)
+        ];
+        program.resize(32, 0);
+        program.extend([1, 2, 3, 4].iter().flat_map(|x: &i32| x.to_le_bytes()));
+        program.resize(64, 0);
+
+        let mut vm = rv32v(program);
+        vm.registers[10] = 100; // more than the 4 elements of 32 bits in a 128 bit register
+        vm.registers[11] = 32;
+        vm.registers[12] = 48;
+        for _ in 0..5 {
+            vm.step_no_pipeline();
+        }
+        assert_eq!(vm.registers[5], 4);
+        assert_eq!(vm.vector.element(3, 0, 32), 20);
+        let doubled: Vec<u8> = [2, 4, 6, 8].iter().flat_map(|x: &i32| x.to_le_bytes()).collect();
+        assert_eq!(vm.vector_register(2), doubled);
+        assert_eq!(vm.memory[48..64], doubled);
+    }
+
+    #[test]
+    fn test_vector_cost_follows_vl() {
+        let program = vec![
+            0xd7, 0x72, 0x05, 0x0d, // VSETVLI x5, x10, e32, m1, ta, ma
+            0x57, 0xb1, 0x12, 0x02, // VADD.VI v2, v1, 5
+        ];
+        let cost = |avl| {
+            let mut vm = rv32v(program.clone());
+            vm.registers[10] = avl;
+            vm.step_no_pipeline();
+            let (cycle, energy) = (vm.cycle, vm.energy());
+            vm.step_no_pipeline();
+            (vm.cycle - cycle, vm.energy() - energy)
+        };
+        assert_eq!(cost(1), (5, 1));
+        assert_eq!(cost(4), (8, 4)); // one cycle and one unit of energy per element
+        assert_eq!(cost(0), (5, 1));
+    }
+
+    #[test]
+    fn test_vector_strided_masked() {
+        let mut program = vec![
+            0x57, 0x70, 0x85, 0x00, // VSETVLI x0, x10, e16, m1, tu, mu
+            0x87, 0xd0, 0xc5, 0x0a, // VLSE16.V v1, (x11), x12
+            0xd7, 0xb0, 0x1f, 0x00, // VADD.VI v1, v1, -1, v0.t
+        ];
+        program.resize(16, 0);
+        program.extend([10, 0, 0, 0, 20, 0, 0, 0, 30, 0]);
+
+        let mut vm = rv32v(program);
+        vm.registers[10] = 3;
+        vm.registers[11] = 16;
+        vm.registers[12] = 4;
+        vm.vector.set_element(0, 0, 8, 0b101); // only elements 0 and 2 are active
+        for _ in 0..3 {
+            vm.step_no_pipeline();
+        }
+        assert_eq!(vm.vector_register(1)[..8], [9, 0, 20, 0, 29, 0, 0, 0]);
+    }
+
+    // the models that clock the pipeline differently
+    const MODELS: [CpuModel; 4] = [CpuModel::SingleCycle, CpuModel::FiveStage, CpuModel::SevenStage, CpuModel::DualIssue];
+
+    #[test]
+    fn test_store_outside_of_memory() {
+        let program = vec![
+            0x23, 0x22, 0x00, 0x06, // SW x0, 100(x0)
+            0x93, 0x00, 0x10, 0x00, // ADDI x1, x0, 1
+        ];
+        for model in MODELS {
+            let mut vm = VM::new(program.clone(), MockEnv {});
+            vm.set_cpu_model(model);
+            assert_eq!(vm.run(), VmState::Faulted, "{model:?}");
+            assert_eq!(vm.fault(), Some(&Fault::StoreOutOfRange { address: 0, addr: 100, size: 4 }), "{model:?}");
+            assert_eq!(vm.registers[1], 0, "{model:?}"); // flushed behind the store
+            assert_eq!(vm.memory, program);
+        }
+    }
+
+    #[test]
+    fn test_load_outside_of_memory() {
+        let program = vec![
+            0x83, 0x20, 0xc0, 0xff, // LW x1, -4(x0)
+            0x13, 0x01, 0x10, 0x00, // ADDI x2, x0, 1
+        ];
+        for profile in ["rv32i", "rv64i"] {
+            for model in MODELS {
+                let mut vm = VM::with_profile(program.clone(), MockEnv {}, profile.parse().unwrap());
+                vm.set_cpu_model(model);
+                vm.registers[1] = 9;
+                assert_eq!(vm.run(), VmState::Faulted, "{profile} {model:?}");
+                let fault = Fault::LoadOutOfRange { address: 0, addr: usize::MAX - 3, size: 4 };
+                assert_eq!(vm.fault(), Some(&fault), "{profile} {model:?}");
+                assert_eq!(vm.registers[1..3], [9, 0], "{profile} {model:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fetch_outside_of_memory() {
+        let cases = [
+            ([0x6f, 0xf0, 0x9f, 0xff], usize::MAX - 3), // JAL x0, -8
+            ([0xe3, 0x0c, 0x00, 0xfe], usize::MAX - 3), // BEQ x0, x0, -8
+            ([0x6f, 0x00, 0x00, 0x06], 100),            // JAL x0, 96
+        ];
+        for (encoding, target) in cases {
+            let mut program = vec![0x13, 0x00, 0x00, 0x00]; // NOP
+            program.extend(encoding);
+            for model in MODELS {
+                let mut vm = VM::new(program.clone(), MockEnv {});
+                vm.set_cpu_model(model);
+                assert_eq!(vm.run(), VmState::Faulted, "{encoding:x?} {model:?}");
+                assert_eq!(vm.fault(), Some(&Fault::FetchOutOfRange { address: target }), "{encoding:x?} {model:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_vector_store_outside_of_memory() {
+        let mut program = vec![
+            0x57, 0x70, 0x85, 0x00, // VSETVLI x0, x10, e16, m1, tu, mu
+            0xa7, 0xd0, 0xc5, 0x0a, // VSSE16.V v1, (x11), x12
+        ];
+        program.resize(18, 0);
+
+        // the third element would be at 18..20, past the end of memory
+        let mut vm = rv32v(program.clone());
+        vm.registers[10] = 3;
+        vm.registers[11] = 10;
+        vm.registers[12] = 4;
+        for index in 0..3 {
+            vm.vector.set_element(1, index, 16, index as i64 + 1);
+        }
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.fault(), Some(&Fault::StoreOutOfRange { address: 4, addr: 18, size: 2 }));
+        assert_eq!(vm.memory[10..], [1, 0, 0, 0, 2, 0, 0, 0]);
+
+        // a negative stride wraps below address 0
+        let mut vm = rv32v(program);
+        vm.registers[10] = 2;
+        vm.registers[11] = 2;
+        vm.registers[12] = -4;
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.fault(), Some(&Fault::StoreOutOfRange { address: 4, addr: usize::MAX - 1, size: 2 }));
+    }
+
+    #[test]
+    fn test_vector_min_max() {
+        let program = vec![
+            0xd7, 0x72, 0x05, 0x0d, // VSETVLI x5, x10, e32, m1, ta, ma
+            0x57, 0xc1, 0x16, 0x12, // VMINU.VX v2, v1, x13
+            0xd7, 0xc1, 0x16, 0x1e, // VMAX.VX v3, v1, x13
+            0x57, 0x22, 0x12, 0x1a, // VREDMAXU.VS v4, v1, v4
+        ];
+
+        let mut vm = rv32v(program);
+        vm.registers[10] = 4;
+        vm.registers[13] = 7;
+        for (index, value) in [-5, 3, 100, 7].into_iter().enumerate() {
+            vm.vector.set_element(1, index, 32, value);
+        }
+        for _ in 0..4 {
+            vm.step_no_pipeline();
+        }
+        let elements = |register| (0..4).map(|x| vm.vector.element(register, x, 32)).collect::<Vec<_>>();
+        assert_eq!(elements(2), [7, 3, 7, 7]);
+        assert_eq!(elements(3), [7, 7, 100, 7]);
+        assert_eq!(vm.vector.element(4, 0, 32), -5); // 0xfffffffb is the largest unsigned
+    }
+
+    #[test]
+    fn test_vector_illegal_configurations() {
+        // vtype starts out with vill set
+        let mut vm = rv32v(vec![0xd7, 0x00, 0x21, 0x02]); // VADD.VV v1, v2, v2
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.fault(), Some(&Fault::IllegalInstruction { address: 0, instruction: 0x0221_00d7 }));
+
+        let program = vec![
+            0x57, 0x70, 0x15, 0x01, // VSETVLI x0, x10, e32, m2, tu, mu
+            0xd7, 0x00, 0x21, 0x02, // VADD.VV v1, v2, v2, v1 doesn't start a group of two
+        ];
+        let mut vm = rv32v(program);
+        vm.registers[10] = 8;
+        assert_eq!(vm.run(), VmState::Faulted);
+        assert_eq!(vm.fault(), Some(&Fault::IllegalInstruction { address: 4, instruction: 0x0221_00d7 }));
+    }
+
+    #[test]
+    fn test_vector_length_from_profile() {
+        let program = vec![
+            0xd7, 0x72, 0x00, 0x01, // VSETVLI x5, x0, e32, m1, tu, mu
+            0x93, 0x83, 0x12, 0x00, // ADDI x7, x5, 1
+            0x73, 0x23, 0x20, 0xc2, // CSRRS x6, vlenb, x0
+        ];
+
+        let mut vm = VM::with_profile(program, MockEnv {}, "rv32iv_zicsr_zvl256b".parse().unwrap());
+        assert_eq!(vm.run(), VmState::Halted);
+        assert_eq!(vm.registers[5], 8); // VLMAX
+        assert_eq!(vm.registers[7], 9); // vl is forwarded like any other result
+        assert_eq!(vm.registers[6], 32);
+    }
 }
@@ -0,0 +1,63 @@
+use super::common::{
+    EXMEM, ExecuteResult, IDEX, OperandsFormat, REGISTER_COUNT, VectorOperation, VectorSource,
+};
+
+pub fn extract_vtype(
+    instruction: u32,
+    registers: &[i64; REGISTER_COUNT],
+    operation: VectorOperation,
+) -> OperandsFormat {
+    let field = |shift: u32| ((instruction >> shift) & 0x1f) as usize;
+    let (rd, vd) = match operation {
+        VectorOperation::SetVl => (field(7), 0),
+        _ => (0, field(7)),
+    };
+    // only the fields that name integer registers take part in hazard detection
+    let r1 = match operation {
+        VectorOperation::Elementwise(_, VectorSource::Vector | VectorSource::Immediate)
+        | VectorOperation::Reduction(_) => 0,
+        _ => field(15),
+    };
+    let r2 = match operation {
+        VectorOperation::Load { strided: true, .. } | VectorOperation::Store { strided: true, .. } => field(20),
+        _ => 0,
+    };
+    let imm = match operation {
+        VectorOperation::SetVl => ((instruction >> 20) & 0x7ff) as i32,
+        _ => (instruction as i32) << 12 >> 27,
+    };
+
+    OperandsFormat::Vtype {
+        r1_val: registers[r1],
+        r2_val: registers[r2],
+        vs1: field(15),
+        vs2: field(20),
+        masked: (instruction >> 25) & 1 == 0,
+        rd,
+        r1,
+        r2,
+        vd,
+        imm,
+        operation,
+        instruction,
+    }
+}
+
+// the vector registers are read and written by the vm in the execute stage, vsetvli puts the new
+// vl into rd
+pub fn vector_result(id_ex: &IDEX) -> ExecuteResult {
+    let Some(OperandsFormat::Vtype { rd, .. }) = id_ex.operands else { unreachable!() };
+    ExecuteResult {
+        ex_mem: EXMEM {
+            rd: (rd != 0).then_some(rd),
+            calculation_result: 0,
+            memory_operation: None,
+            operands: id_ex.operands.clone(),
+        },
+        flush: false,
+        new_pc: None,
+        trap_type: None,
+        fflags: 0,
+        csr_operation: None,
+    }
+}